@@ -4,13 +4,29 @@ import type {
   AppSettings,
   FileItem,
   AIModel,
+  EnvironmentVariable,
+  ContextReport,
+  ModelPrice,
+  UsageDimension,
+  UsageReport,
   Workspace,
-  CommandResult,
   ApiResponse,
   FileContent,
   ChatSession,
+  ChatSessionSummary,
+  ChatBranch,
+  ChatSearchHit,
+  ChatExportFormat,
+  ChatImportSource,
+  ChatImportReport,
   ChatMessage,
+  ChatSessionFilter,
+  RetentionPolicy,
+  RetentionReport,
   BackendChatSession,
+  TerminalState,
+  CommandHistoryEntry,
+  HistorySearchMode,
   BackendChatMessage,
 } from '@/utils/types';
 
@@ -109,6 +125,8 @@ export async function sendChatMessageStreaming(
   workspaceDir?: string,
   codeCliChanged?: boolean,
   codeCliTaskId?: string | null,
+  model?: string,
+  editMessageId?: string | null,
 ): Promise<string> {
   return invoke('send_chat_message_streaming', {
     message,
@@ -120,9 +138,47 @@ export async function sendChatMessageStreaming(
     workspaceDir,
     codeCliChanged,
     codeCliTaskId,
+    model,
+    editMessageId,
   });
 }
 
+// Lists model ids served by an OpenAI-compatible endpoint (Ollama / llama.cpp / vLLM).
+export async function discoverAIModels(baseUrl: string, apiKey?: string): Promise<string[]> {
+  return invoke('discover_ai_models', { baseUrl, apiKey });
+}
+
+// Estimates which context files fit the model's budget before sending a message.
+export async function previewChatContext(
+  message: string,
+  contextFiles: string[],
+  codeCli?: string,
+  model?: string,
+  workspaceDir?: string,
+): Promise<ContextReport> {
+  return invoke('preview_chat_context', { message, contextFiles, codeCli, model, workspaceDir });
+}
+
+// Requests, tokens, estimated cost and failure rate of chat runs.
+// `since`/`until` take a local day (`YYYY-MM-DD`, inclusive) or an RFC 3339 instant.
+export async function getUsageReport(options: {
+  workspaceId?: string;
+  since?: string;
+  until?: string;
+  groupBy?: UsageDimension[];
+} = {}): Promise<UsageReport> {
+  return invoke('get_usage_report', options);
+}
+
+export async function getModelPrices(): Promise<ModelPrice[]> {
+  return invoke('get_model_prices');
+}
+
+export async function setModelPrices(prices: ModelPrice[]): Promise<void> {
+  return invoke('set_model_prices', { prices });
+}
+
+/** Registered models; plaintext API keys come back as `[REDACTED]`, vault references unchanged */
 export async function getAIModels(): Promise<AIModel[]> {
   return invoke('get_ai_models');
 }
@@ -131,13 +187,67 @@ export async function setAIModel(modelId: string): Promise<void> {
   return invoke('set_ai_model', { modelId });
 }
 
+export async function getCurrentAIModel(): Promise<string | null> {
+  return invoke('get_current_ai_model');
+}
+
+export async function addAIModel(model: AIModel): Promise<void> {
+  return invoke('add_ai_model', { model });
+}
+
+/** Passing the masked `[REDACTED]` API key back keeps the stored key */
+export async function updateAIModel(model: AIModel): Promise<void> {
+  return invoke('update_ai_model', { model });
+}
+
+export async function removeAIModel(modelId: string): Promise<void> {
+  return invoke('remove_ai_model', { modelId });
+}
+
+// Environment variables stored in the data directory `.env` file. Secret values are
+// moved into the vault on save and returned as `vault:<name>` references.
+export async function getEnvironmentVariables(): Promise<EnvironmentVariable[]> {
+  return invoke('get_environment_variables');
+}
+
+export async function saveEnvironmentVariables(variables: EnvironmentVariable[]): Promise<void> {
+  return invoke('save_environment_variables', { variables });
+}
+
+// Secret vault commands. `setSecret` returns the `vault:<name>` reference to store
+// in place of the raw value (model API keys, secret environment variables).
+export async function setSecret(name: string, value: string): Promise<string> {
+  return invoke('set_secret', { name, value });
+}
+
+export async function getSecret(name: string): Promise<string | null> {
+  return invoke('get_secret', { name });
+}
+
+export async function deleteSecret(name: string): Promise<boolean> {
+  return invoke('delete_secret', { name });
+}
+
+export async function listSecrets(): Promise<string[]> {
+  return invoke('list_secrets');
+}
+
 // Terminal commands
+/**
+ * Run a command without a shell. Resolves with a command id right away; output
+ * arrives as `command-output` events and the end as one `command-exit` event.
+ */
 export async function executeCommand(
   command: string,
   args?: string[],
-  cwd?: string
-): Promise<CommandResult> {
-  return invoke('execute_command', { command, args, cwd });
+  cwd?: string,
+  timeoutMs?: number
+): Promise<string> {
+  return invoke('execute_command', { command, args: args ?? [], cwd, timeoutMs });
+}
+
+export async function cancelCommand(commandId: string): Promise<void> {
+  return invoke('cancel_command', { commandId });
 }
 
 // myclaude (codeagent-wrapper) command
@@ -157,20 +267,82 @@ export async function executeCodeagentWrapper(
   });
 }
 
+/** Run a command through `shell` in a terminal session's directory; streams like `executeCommand` */
 export async function executeTerminalCommand(
   sessionId: string,
   shell: string,
-  command: string
+  command: string,
+  timeoutMs?: number
 ): Promise<string> {
   return invoke('execute_terminal_command', {
     sessionId,
     shell,
     command,
+    timeoutMs,
+  });
+}
+
+/** Distinct commands run in a workspace (the active one by default), most recent run of each */
+export async function searchCommandHistory(
+  query?: string,
+  options?: { mode?: HistorySearchMode; limit?: number; workspaceId?: string }
+): Promise<CommandHistoryEntry[]> {
+  return invoke('search_command_history', {
+    workspaceId: options?.workspaceId,
+    query,
+    mode: options?.mode,
+    limit: options?.limit,
   });
 }
 
-export async function spawnTerminal(cwd?: string): Promise<string> {
-  return invoke('spawn_terminal', { cwd });
+/** Run a history entry again in its directory with a terminal session's environment; streams like `executeCommand` */
+export async function rerunCommandHistory(
+  historyId: number,
+  sessionId: string,
+  timeoutMs?: number
+): Promise<string> {
+  return invoke('rerun_command_history', { historyId, sessionId, timeoutMs });
+}
+
+/** Start a terminal session with an interactive shell; output arrives as `terminal-output` events */
+export async function spawnTerminal(
+  cwd?: string,
+  options?: { shell?: string; cols?: number; rows?: number }
+): Promise<string> {
+  return invoke('spawn_terminal', {
+    cwd,
+    shell: options?.shell,
+    cols: options?.cols,
+    rows: options?.rows,
+  });
+}
+
+/** Current directory and git branch of a terminal, as followed by the backend */
+export async function getTerminalState(sessionId: string): Promise<TerminalState> {
+  return invoke('get_terminal_state', { terminalId: sessionId });
+}
+
+/** Open terminals in the order they were opened */
+export async function listTerminals(): Promise<TerminalState[]> {
+  return invoke('list_terminals');
+}
+
+/** Output kept for a terminal (ANSI sequences included), for replaying into a new view */
+export async function getTerminalScrollback(sessionId: string): Promise<string> {
+  return invoke('get_terminal_scrollback', { terminalId: sessionId });
+}
+
+/** Reopen the terminals the current workspace had open when the app last quit */
+export async function restoreTerminals(cols?: number, rows?: number): Promise<TerminalState[]> {
+  return invoke('restore_terminals', { cols, rows });
+}
+
+export async function writeTerminal(sessionId: string, data: string): Promise<void> {
+  return invoke('write_terminal', { terminalId: sessionId, data });
+}
+
+export async function resizeTerminal(sessionId: string, cols: number, rows: number): Promise<void> {
+  return invoke('resize_terminal', { terminalId: sessionId, cols, rows });
 }
 
 export async function killTerminal(sessionId: string): Promise<void> {
@@ -362,6 +534,10 @@ function normalizeChatMessage(message: BackendChatMessage): ChatMessage {
     files: message.files || [],
     model: message.model,
     fileMetadata: message.fileMetadata || message.file_metadata,
+    parentId: message.parentId ?? message.parent_id ?? null,
+    run: message.run ?? null,
+    codeCli: message.codeCli ?? message.code_cli ?? null,
+    codeCliTaskId: message.codeCliTaskId ?? message.code_cli_task_id ?? null,
   };
 }
 
@@ -381,6 +557,30 @@ function normalizeChatSession(session: BackendChatSession): ChatSession {
     messageCount,
     firstMessagePreview: session.firstMessagePreview || session.first_message_preview || '',
     codeCliTaskIds: session.codeCliTaskIds || session.code_cli_task_ids || {},
+    activeLeafId: session.activeLeafId ?? session.active_leaf_id ?? null,
+    branchCount: session.branchCount ?? session.branch_count ?? 1,
+    runStats: session.runStats ?? session.run_stats,
+    tags: session.tags ?? [],
+    pinned: session.pinned ?? false,
+    archivedAt: session.archivedAt ?? session.archived_at ?? null,
+    summary: session.summary ?? null,
+    summaryThroughId: session.summaryThroughId ?? session.summary_through_id ?? null,
+  };
+}
+
+function normalizeChatSessionSummary(session: BackendChatSession): ChatSessionSummary {
+  return {
+    id: session.id,
+    name: session.name,
+    sessionId: session.sessionId || session.session_id || '',
+    workspaceId: session.workspaceId || session.workspace_id || '',
+    createdAt: session.createdAt || session.created_at || '',
+    updatedAt: session.updatedAt || session.updated_at || '',
+    messageCount: session.messageCount ?? session.message_count ?? 0,
+    firstMessagePreview: session.firstMessagePreview || session.first_message_preview || '',
+    tags: session.tags ?? [],
+    pinned: session.pinned ?? false,
+    archivedAt: session.archivedAt ?? session.archived_at ?? null,
   };
 }
 
@@ -388,20 +588,118 @@ export async function saveChatSession(
   sessionId: string | null,
   name: string | null,
   messages: ChatMessage[],
-  codeagentSessionId?: string | null
+  workspaceId?: string | null,
+  codeCliTaskIds?: Record<string, string>,
 ): Promise<ChatSession> {
   const session = await invoke<BackendChatSession>('save_chat_session', {
     sessionId,
     name,
-    messages,
-    codeagent_session_id: codeagentSessionId,
+    workspaceId,
+    messages: messages.map((message) => ({
+      id: message.id,
+      role: message.role,
+      content: message.content,
+      timestamp: message.timestamp,
+      files: message.files,
+      model: message.model,
+      session_id: message.sessionId,
+      workspace_id: message.workspaceId,
+      parent_id: message.parentId,
+      run: message.run,
+      code_cli: message.codeCli,
+      code_cli_task_id: message.codeCliTaskId,
+    })),
+    codeCliTaskIds,
   });
   return normalizeChatSession(session);
 }
 
-export async function loadChatSessions(workspaceId: string, limit?: number): Promise<ChatSession[]> {
-  const sessions = await invoke<BackendChatSession[]>('load_chat_sessions', { workspaceId, limit });
-  return sessions.map((session) => normalizeChatSession(session));
+// Archived sessions are only listed with `filter.archived`; `filter.tags` must all match.
+// Summaries carry no messages; open a session with `loadChatSession`.
+export async function loadChatSessions(
+  workspaceId: string,
+  limit?: number,
+  offset?: number,
+  filter: ChatSessionFilter = {},
+): Promise<ChatSessionSummary[]> {
+  const sessions = await invoke<BackendChatSession[]>('load_chat_sessions', {
+    workspaceId,
+    limit,
+    offset,
+    ...filter,
+  });
+  return sessions.map((session) => normalizeChatSessionSummary(session));
+}
+
+export async function loadChatSession(sessionId: string): Promise<ChatSession> {
+  const session = await invoke<BackendChatSession>('load_chat_session', { sessionId });
+  return normalizeChatSession(session);
+}
+
+export async function setChatSessionTags(sessionId: string, tags: string[]): Promise<ChatSession> {
+  const session = await invoke<BackendChatSession>('set_chat_session_tags', { sessionId, tags });
+  return normalizeChatSession(session);
+}
+
+export async function setChatSessionPinned(sessionId: string, pinned: boolean): Promise<ChatSession> {
+  const session = await invoke<BackendChatSession>('set_chat_session_pinned', { sessionId, pinned });
+  return normalizeChatSession(session);
+}
+
+export async function setChatSessionArchived(sessionId: string, archived: boolean): Promise<ChatSession> {
+  const session = await invoke<BackendChatSession>('set_chat_session_archived', { sessionId, archived });
+  return normalizeChatSession(session);
+}
+
+export async function getChatRetentionPolicy(): Promise<RetentionPolicy> {
+  return invoke('get_chat_retention_policy');
+}
+
+// Saves the policy and applies it right away.
+export async function setChatRetentionPolicy(policy: RetentionPolicy): Promise<RetentionReport> {
+  return invoke('set_chat_retention_policy', { policy });
+}
+
+export async function listChatBranches(sessionId: string): Promise<ChatBranch[]> {
+  return invoke('list_chat_branches', { sessionId });
+}
+
+export async function switchChatBranch(sessionId: string, messageId: string): Promise<ChatSession> {
+  const session = await invoke<BackendChatSession>('switch_chat_branch', { sessionId, messageId });
+  return normalizeChatSession(session);
+}
+
+export async function searchChatSessions(
+  query: string,
+  workspaceId?: string,
+  limit?: number,
+): Promise<ChatSearchHit[]> {
+  return invoke('search_chat_sessions', { query, workspaceId, limit });
+}
+
+export async function exportChatSession(
+  sessionId: string,
+  format: ChatExportFormat,
+  outputPath?: string,
+): Promise<string> {
+  return invoke('export_chat_session', { sessionId, format, outputPath });
+}
+
+export async function exportWorkspaceChatSessions(
+  workspaceId: string,
+  format: ChatExportFormat,
+  outputPath?: string,
+): Promise<string> {
+  return invoke('export_workspace_chat_sessions', { workspaceId, format, outputPath });
+}
+
+export async function importChatSessions(
+  paths: string[],
+  workspaceId: string,
+  source?: ChatImportSource,
+  codeCli?: string,
+): Promise<ChatImportReport> {
+  return invoke('import_chat_sessions', { paths, workspaceId, source, codeCli });
 }
 
 export async function deleteChatSession(sessionId: string): Promise<void> {
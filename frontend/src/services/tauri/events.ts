@@ -13,9 +13,14 @@ export type EventType =
   | 'terminal:output'
   | 'terminal:session:started'
   | 'terminal:session:ended'
+  | 'terminal-output'
+  | 'terminal-exit'
+  | 'command-output'
+  | 'command-exit'
   | 'chat:message:received'
   | 'chat:message:sent'
   | 'ai-response'
+  | 'ai-context'
   | 'settings:updated'
   | 'workspace:changed'
   | 'app:error'
@@ -48,9 +53,14 @@ class EventService {
       'terminal:output',
       'terminal:session:started',
       'terminal:session:ended',
+      'terminal-output',
+      'terminal-exit',
+      'command-output',
+      'command-exit',
       'chat:message:received',
       'chat:message:sent',
       'ai-response',
+      'ai-context',
       'settings:updated',
       'workspace:changed',
       'app:error',
@@ -4,6 +4,7 @@ import { ref } from 'vue';
 import {
   sendChatMessageStreaming,
   loadChatSessions,
+  loadChatSession,
   deleteChatSession,
   updateChatSessionName,
   cancelStreamingRequest,
@@ -11,6 +12,7 @@ import {
 import type {
   ChatMessage,
   ChatSession,
+  ChatSessionSummary,
   SendMessageOptions,
   AiResponseEventPayload,
   ClipboardAttachment,
@@ -24,7 +26,7 @@ export const useChatStore = defineStore('chat', () => {
   const currentRequestId = ref<string>('');
   const currentCodeCli = ref<string>('');
   const isStreaming = ref(false);
-  const sessions = ref<ChatSession[]>([]);
+  const sessions = ref<ChatSessionSummary[]>([]);
   const isSessionsLoading = ref(false);
   const codeCliChanged = ref(false);
   const codeCliTaskIds = ref<Record<string, string>>({});
@@ -211,7 +213,7 @@ export const useChatStore = defineStore('chat', () => {
     pendingUserMessageId.value = '';
   }
 
-  async function fetchSessions(workspaceId: string, limit = 50): Promise<ChatSession[]> {
+  async function fetchSessions(workspaceId: string, limit = 50): Promise<ChatSessionSummary[]> {
     isSessionsLoading.value = true;
     try {
       sessions.value = await loadChatSessions(workspaceId, limit);
@@ -221,6 +223,11 @@ export const useChatStore = defineStore('chat', () => {
     }
   }
 
+  // The list only holds summaries; messages are loaded per session
+  async function fetchSession(sessionId: string): Promise<ChatSession> {
+    return loadChatSession(sessionId);
+  }
+
   async function removeSession(sessionId: string): Promise<void> {
     await deleteChatSession(sessionId);
     sessions.value = sessions.value.filter((session) => session.id !== sessionId);
@@ -302,6 +309,7 @@ export const useChatStore = defineStore('chat', () => {
     loadSessionFromHistory,
     clearChat,
     fetchSessions,
+    fetchSession,
     removeSession,
     renameSession,
     cancelStreaming,
@@ -38,6 +38,7 @@ export interface AIModel {
   provider: string;
   endpoint: string;
   apiKey?: string;
+  isActive?: boolean;
 }
 
 export interface CodeCli {
@@ -209,6 +210,37 @@ export interface ChatMessage {
   files?: string[];
   model?: string;
   fileMetadata?: Record<string, FileMetadata>;
+  /** Previous message on this message's branch */
+  parentId?: string | null;
+  /** How an assistant message was produced */
+  run?: RunMetadata | null;
+  /** Code CLI that produced an assistant message, with its CLI session id */
+  codeCli?: string | null;
+  codeCliTaskId?: string | null;
+}
+
+export interface RunMetadata {
+  /** e.g. `codeagent:codex` or `anthropic` */
+  backend: string;
+  model?: string | null;
+  workdir?: string | null;
+  duration_ms: number;
+  exit_code?: number | null;
+  stderr_tail?: string | null;
+  usage?: { input_tokens: number; output_tokens: number } | null;
+  cancelled: boolean;
+  /** Set for failed runs; the message content then holds the error */
+  error?: string | null;
+}
+
+export interface RunStats {
+  runs: number;
+  failed_runs: number;
+  cancelled_runs: number;
+  total_duration_ms: number;
+  max_duration_ms: number;
+  input_tokens: number;
+  output_tokens: number;
 }
 
 export interface ChatResponse {
@@ -244,25 +276,166 @@ export interface ChatSession {
   messageCount: number;
   firstMessagePreview: string;
   codeCliTaskIds?: Record<string, string>;
+  /** Last message of the active branch */
+  activeLeafId?: string | null;
+  branchCount?: number;
+  /** Runs of all branches */
+  runStats?: RunStats;
+  tags?: string[];
+  /** Pinned sessions are listed first and never archived automatically */
+  pinned?: boolean;
+  archivedAt?: string | null;
+  /** Summary of the active branch up to `summaryThroughId` */
+  summary?: string | null;
+  summaryThroughId?: string | null;
+}
+
+/** A session as listed, without its messages (see `loadChatSession`) */
+export interface ChatSessionSummary {
+  id: string;
+  name?: string;
+  sessionId?: string;
+  workspaceId: string;
+  createdAt: string;
+  updatedAt: string;
+  /** Messages of all branches */
+  messageCount: number;
+  firstMessagePreview: string;
+  tags?: string[];
+  pinned?: boolean;
+  archivedAt?: string | null;
+}
+
+export interface ChatSessionFilter {
+  /** Only archived sessions instead of only unarchived ones */
+  archived?: boolean;
+  pinned?: boolean;
+  /** Tags a session must all carry */
+  tags?: string[];
+}
+
+export interface RetentionPolicy {
+  /** Archive sessions not updated for this many days */
+  archive_after_days?: number | null;
+  /** Delete sessions archived this many days ago */
+  delete_archived_after_days?: number | null;
+}
+
+export interface RetentionReport {
+  archived: number;
+  deleted: number;
 }
 
 export interface AiResponseEventPayload {
   request_id: string;
   delta: string;
   done: boolean;
+  status?: 'streaming' | 'done' | 'error' | 'cancelled';
   session_id?: string | null;
   workspace_id?: string | null;
   code_cli_task_id?: string | null;
   timestamp: string;
 }
 
+export interface ContextFileReport {
+  path: string;
+  status: 'included' | 'truncated' | 'skipped';
+  tokens: number;
+  original_tokens?: number | null;
+  reason?: string | null;
+}
+
+export interface ContextReport {
+  model: string;
+  budget_tokens: number;
+  total_tokens: number;
+  included_files: number;
+  omitted_files: number;
+  files: ContextFileReport[];
+}
+
+export interface ModelPrice {
+  /** Model name; a trailing `*` matches every model starting with the rest */
+  model: string;
+  /** USD per million tokens */
+  input_per_mtok: number;
+  output_per_mtok: number;
+}
+
+export type UsageDimension = 'day' | 'workspace' | 'backend' | 'model';
+
+export interface UsageRow {
+  day?: string | null;
+  workspace_id?: string | null;
+  workspace_name?: string | null;
+  backend?: string | null;
+  model?: string | null;
+  requests: number;
+  failed: number;
+  cancelled: number;
+  failure_rate: number;
+  input_tokens: number;
+  output_tokens: number;
+  duration_ms: number;
+  estimated_cost_usd: number;
+  /** Requests that reported tokens for a model without a price */
+  unpriced_requests: number;
+}
+
+export interface UsageReport {
+  rows: UsageRow[];
+  totals: UsageRow;
+}
+
+export interface AiContextEventPayload {
+  request_id: string;
+  session_id?: string | null;
+  context: ContextReport;
+  timestamp: string;
+}
+
 export type BackendChatMessage = Partial<ChatMessage> & {
   session_id?: string;
   workspace_id?: string;
   timestamp?: string;
   file_metadata?: Record<string, FileMetadata>;
+  parent_id?: string | null;
+  code_cli?: string | null;
+  code_cli_task_id?: string | null;
 };
 
+export interface ChatBranch {
+  leaf_id: string;
+  fork_message_id?: string | null;
+  preview: string;
+  message_count: number;
+  updated_at: string;
+  is_active: boolean;
+}
+
+export interface ChatSearchHit {
+  session_id: string;
+  session_name?: string | null;
+  workspace_id?: string | null;
+  message_id?: string | null;
+  role?: string | null;
+  field: 'name' | 'content' | 'files';
+  snippet: string;
+  /** [start, end) character offsets into `snippet` */
+  highlights: [number, number][];
+  timestamp: string;
+}
+
+export type ChatExportFormat = 'markdown' | 'html' | 'jsonl';
+
+export type ChatImportSource = 'claude_code' | 'codex' | 'gemini' | 'jsonl';
+
+export interface ChatImportReport {
+  imported: string[];
+  skipped: number;
+  failed: { path: string; message: string }[];
+}
+
 export type BackendChatSession = Partial<ChatSession> & {
   id: string;
   messages?: BackendChatMessage[];
@@ -273,6 +446,11 @@ export type BackendChatSession = Partial<ChatSession> & {
   session_id?: string;
   workspace_id?: string;
   code_cli_task_ids?: Record<string, string>;
+  active_leaf_id?: string | null;
+  branch_count?: number;
+  run_stats?: RunStats;
+  archived_at?: string | null;
+  summary_through_id?: string | null;
 };
 
 // Terminal types
@@ -292,6 +470,71 @@ export interface TerminalOutput {
   timestamp: string;
 }
 
+export interface TerminalState {
+  id: string;
+  name: string;
+  cwd: string;
+  git_branch: string | null;
+  /** Whether the interactive shell is still running */
+  running: boolean;
+  pid: number | null;
+  shell: string | null;
+  workspace_id: number | null;
+}
+
+export interface TerminalOutputEventPayload {
+  terminal_id: string;
+  output: string;
+  timestamp: string;
+}
+
+export interface TerminalExitEventPayload {
+  terminal_id: string;
+  exit_code: number | null;
+  timestamp: string;
+}
+
+export interface CommandOutputEventPayload {
+  command_id: string;
+  stream: 'stdout' | 'stderr';
+  data: string;
+  timestamp: string;
+}
+
+export interface CommandExit {
+  /** Null when killed by a signal */
+  exit_code: number | null;
+  signal: number | null;
+  duration_ms: number;
+  /** Output beyond the backend's limit was dropped */
+  truncated: boolean;
+  timed_out: boolean;
+  cancelled: boolean;
+}
+
+/** A command run through `executeTerminalCommand`, as recorded in the history */
+export interface CommandHistoryEntry {
+  id: number;
+  workspace_id: number | null;
+  command: string;
+  shell: string;
+  cwd: string;
+  /** Null while running, or when killed by a signal, timeout or cancellation */
+  exit_code: number | null;
+  /** Null while running */
+  duration_ms: number | null;
+  started_at: string;
+}
+
+/** `prefix` matches the start of the command (case-sensitive); `fuzzy` its characters in order */
+export type HistorySearchMode = 'prefix' | 'fuzzy';
+
+export interface CommandExitEventPayload {
+  command_id: string;
+  exit: CommandExit;
+  timestamp: string;
+}
+
 // Command types
 export interface CommandResult {
   success: boolean;
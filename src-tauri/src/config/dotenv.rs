@@ -0,0 +1,462 @@
+//! Dotenv module
+//!
+//! Parser and writer for the `.env` file in the data directory. The parsed
+//! document keeps comments, blank lines, `export` prefixes and quoting so that
+//! writing it back only changes what was edited.
+//!
+//! Supported syntax:
+//! - `KEY=value`, `export KEY=value`, whitespace around `=`
+//! - unquoted values (trimmed, ` #` starts an inline comment)
+//! - `'single quoted'` values: literal, may span lines
+//! - `"double quoted"` values: `\n \r \t \\ \" \$` escapes, may span lines
+//! - `${VAR}` / `$VAR` interpolation in unquoted and double-quoted values,
+//!   resolved against earlier keys in the file, then the process environment;
+//!   `\$` is a literal `$` and an unterminated `${` is kept as written
+
+use std::fmt;
+use thiserror::Error;
+
+/// Parse error with the 1-based line it occurred on
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("line {line}: {message}")]
+pub struct DotenvError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Quoting used for a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quote {
+    None,
+    Single,
+    Double,
+}
+
+/// One logical line of a `.env` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvLine {
+    /// Empty or whitespace-only line
+    Blank,
+    /// Full-line comment, including the leading `#`
+    Comment(String),
+    /// Variable assignment
+    Var {
+        key: String,
+        /// Value as written, without the quotes; escapes and `$` references are
+        /// decoded together by `EnvDocument::vars`
+        raw: String,
+        quote: Quote,
+        export: bool,
+        /// Inline comment after the value, including the leading `#`
+        comment: Option<String>,
+    },
+}
+
+/// A parsed `.env` file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvDocument {
+    pub lines: Vec<EnvLine>,
+}
+
+impl EnvDocument {
+    /// Parse `.env` content
+    pub fn parse(input: &str) -> Result<Self, DotenvError> {
+        let lines: Vec<&str> = input.lines().collect();
+        let mut out = Vec::new();
+        let mut i = 0;
+        while i < lines.len() {
+            let line_no = i + 1;
+            let line = lines[i].trim_start();
+            let trimmed = line.trim_end();
+            i += 1;
+
+            if trimmed.is_empty() {
+                out.push(EnvLine::Blank);
+                continue;
+            }
+            if trimmed.starts_with('#') {
+                out.push(EnvLine::Comment(trimmed.to_string()));
+                continue;
+            }
+
+            let (export, rest) = match line.strip_prefix("export") {
+                Some(rest) if rest.starts_with(char::is_whitespace) => (true, rest.trim_start()),
+                _ => (false, line),
+            };
+            let Some((key, raw_value)) = rest.split_once('=') else {
+                return Err(error(line_no, format!("expected KEY=VALUE, found `{}`", trimmed)));
+            };
+            let key = key.trim();
+            if !is_valid_key(key) {
+                return Err(error(line_no, format!("invalid variable name `{}`", key)));
+            }
+            let raw_value = raw_value.trim_start();
+
+            let (raw, quote, comment) = match raw_value.chars().next() {
+                Some(q @ ('\'' | '"')) => {
+                    // Quoted values may continue on the following lines
+                    let mut text = raw_value[1..].to_string();
+                    let close = loop {
+                        if let Some(pos) = find_closing_quote(&text, q) {
+                            break pos;
+                        }
+                        if i >= lines.len() {
+                            return Err(error(line_no, format!("unterminated {} quote", q)));
+                        }
+                        text.push('\n');
+                        text.push_str(lines[i]);
+                        i += 1;
+                    };
+                    let body = &text[..close];
+                    let trailing = text[close + 1..].trim();
+                    let comment = match trailing {
+                        "" => None,
+                        t if t.starts_with('#') => Some(t.to_string()),
+                        t => {
+                            return Err(error(line_no, format!("unexpected `{}` after closing quote", t)));
+                        }
+                    };
+                    let quote = if q == '\'' { Quote::Single } else { Quote::Double };
+                    (body.to_string(), quote, comment)
+                }
+                _ => {
+                    let (value, comment) = split_inline_comment(raw_value);
+                    (value.trim_end().to_string(), Quote::None, comment)
+                }
+            };
+
+            out.push(EnvLine::Var {
+                key: key.to_string(),
+                raw,
+                quote,
+                export,
+                comment,
+            });
+        }
+        Ok(Self { lines: out })
+    }
+
+    /// Variables in file order with interpolation applied; later duplicates win
+    pub fn vars(&self) -> Vec<(String, String)> {
+        let mut vars: Vec<(String, String)> = Vec::new();
+        for line in &self.lines {
+            if let EnvLine::Var { key, raw, quote, .. } = line {
+                let value = expand(raw, *quote, &vars);
+                match vars.iter_mut().find(|(k, _)| k == key) {
+                    Some(existing) => existing.1 = value,
+                    None => vars.push((key.clone(), value)),
+                }
+            }
+        }
+        vars
+    }
+
+    /// Set `key` to the literal `value`, keeping its position, `export` and comment
+    pub fn set(&mut self, key: &str, value: &str) {
+        let (raw, quote) = literal_value(value);
+        for line in self.lines.iter_mut() {
+            if let EnvLine::Var {
+                key: k,
+                raw: r,
+                quote: q,
+                ..
+            } = line
+            {
+                if k == key {
+                    *r = raw;
+                    *q = quote;
+                    return;
+                }
+            }
+        }
+        self.lines.push(EnvLine::Var {
+            key: key.to_string(),
+            raw,
+            quote,
+            export: false,
+            comment: None,
+        });
+    }
+
+    /// Remove every assignment of `key`
+    pub fn remove(&mut self, key: &str) {
+        self.lines
+            .retain(|line| !matches!(line, EnvLine::Var { key: k, .. } if k == key));
+    }
+
+    /// Replace the variables with `vars`, keeping comments and the layout of unchanged keys.
+    ///
+    /// Values that are unchanged after interpolation keep their original spelling
+    /// (so `${HOME}/bin` is not flattened to an absolute path).
+    pub fn replace_vars(&mut self, vars: &[(String, String)]) {
+        let current = self.vars();
+        let keys: Vec<&str> = current.iter().map(|(k, _)| k.as_str()).collect();
+        for key in keys {
+            if !vars.iter().any(|(k, _)| k == key) {
+                self.remove(key);
+            }
+        }
+        for (key, value) in vars {
+            let unchanged = current.iter().any(|(k, v)| k == key && v == value);
+            if !unchanged {
+                self.set(key, value);
+            }
+        }
+    }
+}
+
+impl fmt::Display for EnvDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            match line {
+                EnvLine::Blank => writeln!(f)?,
+                EnvLine::Comment(comment) => writeln!(f, "{}", comment)?,
+                EnvLine::Var {
+                    key,
+                    raw,
+                    quote,
+                    export,
+                    comment,
+                } => {
+                    if *export {
+                        write!(f, "export ")?;
+                    }
+                    match quote {
+                        Quote::None => write!(f, "{}={}", key, raw)?,
+                        Quote::Single => write!(f, "{}='{}'", key, raw)?,
+                        Quote::Double => write!(f, "{}=\"{}\"", key, raw)?,
+                    }
+                    if let Some(comment) = comment {
+                        write!(f, " {}", comment)?;
+                    }
+                    writeln!(f)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse `.env` content into interpolated key/value pairs
+pub fn parse(input: &str) -> Result<Vec<(String, String)>, DotenvError> {
+    Ok(EnvDocument::parse(input)?.vars())
+}
+
+fn error(line: usize, message: String) -> DotenvError {
+    DotenvError { line, message }
+}
+
+fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+/// Byte index of the unescaped closing quote in `text`
+fn find_closing_quote(text: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (idx, c) in text.char_indices() {
+        if quote == '"' && escaped {
+            escaped = false;
+            continue;
+        }
+        if quote == '"' && c == '\\' {
+            escaped = true;
+            continue;
+        }
+        if c == quote {
+            return Some(idx);
+        }
+    }
+    None
+}
+
+fn split_inline_comment(value: &str) -> (&str, Option<String>) {
+    let mut prev_ws = true;
+    for (idx, c) in value.char_indices() {
+        if c == '#' && prev_ws {
+            return (&value[..idx], Some(value[idx..].trim_end().to_string()));
+        }
+        prev_ws = c.is_whitespace();
+    }
+    (value, None)
+}
+
+/// Decode escapes and expand `${VAR}` / `$VAR` in one pass, so an escaped
+/// backslash before `$` does not turn into an escaped dollar.
+///
+/// Names resolve against `vars`, then the process environment. Unquoted values
+/// only know the `\$` escape; single-quoted values are literal.
+fn expand(raw: &str, quote: Quote, vars: &[(String, String)]) -> String {
+    if quote == Quote::Single {
+        return raw.to_string();
+    }
+    let lookup = |name: &str| {
+        vars.iter()
+            .rev()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.clone())
+            .or_else(|| std::env::var(name).ok())
+            .unwrap_or_default()
+    };
+
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '\\' => {
+                let decoded = match (chars.peek().map(|(_, c)| *c), quote) {
+                    (Some('$'), _) => Some('$'),
+                    (Some('n'), Quote::Double) => Some('\n'),
+                    (Some('r'), Quote::Double) => Some('\r'),
+                    (Some('t'), Quote::Double) => Some('\t'),
+                    (Some(c @ ('"' | '\\')), Quote::Double) => Some(c),
+                    _ => None,
+                };
+                match decoded {
+                    Some(decoded) => {
+                        chars.next();
+                        out.push(decoded);
+                    }
+                    None => out.push('\\'),
+                }
+            }
+            '$' if raw[idx + 1..].starts_with('{') => {
+                let name_start = idx + 2;
+                match raw[name_start..].find('}') {
+                    Some(len) => {
+                        out.push_str(&lookup(&raw[name_start..name_start + len]));
+                        while chars.next_if(|(i, _)| *i <= name_start + len).is_some() {}
+                    }
+                    // Unterminated: keep `${` and the rest as written
+                    None => out.push('$'),
+                }
+            }
+            '$' if chars.peek().is_some_and(|(_, c)| c.is_ascii_alphabetic() || *c == '_') => {
+                let mut name = String::new();
+                while let Some((_, c)) = chars.next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '_') {
+                    name.push(c);
+                }
+                out.push_str(&lookup(&name));
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Source form and quoting that read back as exactly `value`
+fn literal_value(value: &str) -> (String, Quote) {
+    if !value.contains('$') && !needs_quotes(value) {
+        (value.to_string(), Quote::None)
+    } else if !value.contains('\'') {
+        (value.to_string(), Quote::Single)
+    } else {
+        (escape(value), Quote::Double)
+    }
+}
+
+/// Whether `value` would read back differently if written unquoted
+fn needs_quotes(value: &str) -> bool {
+    value != value.trim()
+        || value.starts_with(['"', '\'', '#'])
+        || value.contains(['\n', '\r'])
+        || split_inline_comment(value).1.is_some()
+}
+
+/// Double-quoted source form of a literal value
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '$' => out.push_str("\\$"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(input: &str) -> Vec<(String, String)> {
+        parse(input).unwrap()
+    }
+
+    fn value(input: &str, key: &str) -> String {
+        vars(input).into_iter().find(|(k, _)| k == key).unwrap().1
+    }
+
+    #[test]
+    fn parses_quoting_comments_and_export() {
+        let input = "# comment\nexport A=1\nB = \"x=y\\n${A}\" # note\nC='lit $A'\nD=foo bar # inline\nE=\"multi\nline\"\nURL=http://h/?a=b#frag\n";
+        assert_eq!(
+            vars(input),
+            [
+                ("A", "1"),
+                ("B", "x=y\n1"),
+                ("C", "lit $A"),
+                ("D", "foo bar"),
+                ("E", "multi\nline"),
+                ("URL", "http://h/?a=b#frag"),
+            ]
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+        );
+    }
+
+    #[test]
+    fn escapes_and_interpolation_are_one_pass() {
+        let input = "HOME_DIR=/home/me\nA=\"x\\\\$HOME_DIR\"\nB=\"x\\$HOME_DIR\"\nC=x\\$HOME_DIR\nD=\"\\\\\\\\${HOME_DIR}\"\nE=\"a\\tb\\\"c\\q\"\n";
+        assert_eq!(value(input, "A"), "x\\/home/me");
+        assert_eq!(value(input, "B"), "x$HOME_DIR");
+        assert_eq!(value(input, "C"), "x$HOME_DIR");
+        assert_eq!(value(input, "D"), "\\\\/home/me");
+        assert_eq!(value(input, "E"), "a\tb\"c\\q");
+    }
+
+    #[test]
+    fn unterminated_brace_is_kept() {
+        assert_eq!(value("A=1\nB=\"pre ${A post\"\n", "B"), "pre ${A post");
+        assert_eq!(value("A=1\nB=${A}${\n", "B"), "1${");
+        assert_eq!(value("A=1\nB=$A-$1-$\n", "B"), "1-$1-$");
+    }
+
+    #[test]
+    fn reports_line_numbers() {
+        assert_eq!(parse("A=1\nnoequals\n").unwrap_err().line, 2);
+        assert_eq!(parse("A=1\n1BAD=x\n").unwrap_err().line, 2);
+        assert_eq!(parse("A=\"open\nB=2\n").unwrap_err().line, 1);
+        assert_eq!(parse("A='x' trailing\n").unwrap_err().line, 1);
+    }
+
+    #[test]
+    fn writing_back_keeps_layout_and_values() {
+        let input = "# keep\nexport A=1\nB=\"${A}/bin\" # path\nC='lit'\n";
+        let mut document = EnvDocument::parse(input).unwrap();
+        assert_eq!(document.to_string(), input);
+
+        let edited = [
+            ("A", "1"),
+            ("B", "1/bin"),
+            ("C", "changed"),
+            ("G", "it's $x"),
+            ("H", "a\\b"),
+            ("I", "a\\$b \"q\""),
+            ("J", " padded # not a comment"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+        document.replace_vars(&edited);
+        let output = document.to_string();
+        assert!(output.starts_with("# keep\nexport A=1\nB=\"${A}/bin\" # path\nC=changed\n"));
+        assert_eq!(parse(&output).unwrap(), edited);
+    }
+}
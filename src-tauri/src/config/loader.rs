@@ -3,6 +3,7 @@
 //! This module handles loading and managing application configuration.
 use std::path::PathBuf;
 use crate::utils::error::{AppError, AppResult};
+use crate::config::dotenv::EnvDocument;
 use crate::config::schema::AppConfig;
 
 /// Get user home directory across multiple operating systems
@@ -33,7 +34,13 @@ pub fn load_settings() -> AppResult<AppConfig> {
     let mut config: AppConfig = cfg.try_deserialize()
         .map_err(|e| AppError::ConfigError(format!("Failed to deserialize config: {}", e)))?;
     // Step 3: 环境变量
-    config.env_vars = load_env_from_file().unwrap_or_default();
+    config.env_vars = match env_file_path().and_then(|path| load_env_document(&path)) {
+        Ok(document) => document.vars(),
+        Err(e) => {
+            tracing::warn!("Failed to load environment variables: {}", e);
+            Vec::new()
+        }
+    };
     // Step 4: 返回最终配置
     Ok(config)
 }
@@ -81,29 +88,63 @@ pub fn save_config(config: &AppConfig) -> AppResult<()> {
     Ok(())
 }
 
+/// Path of the `.env` file in the data directory
+fn env_file_path() -> AppResult<PathBuf> {
+    Ok(PathBuf::from(get_default_data_dir()?).join(".env"))
+}
+
+/// Load the `.env` file as a document (empty if the file does not exist)
+fn load_env_document(env_file: &PathBuf) -> AppResult<EnvDocument> {
+    if !env_file.exists() {
+        return Ok(EnvDocument::default());
+    }
+
+    let env_str = std::fs::read_to_string(env_file)
+        .map_err(|e| AppError::ConfigError(format!("Failed to read environment file: {}", e)))?;
+
+    EnvDocument::parse(&env_str)
+        .map_err(|e| AppError::ConfigError(format!("Invalid environment file {:?}: {}", env_file, e)))
+}
+
 /// Load Environment Variables from file
 pub fn load_env_from_file() -> AppResult<Vec<(String, String)>> {
-    let config_dir = PathBuf::from(get_default_data_dir()?);
-
-    let env_file = config_dir.join(".env");
+    let env_file = env_file_path()?;
 
     if !env_file.exists() {
         return Err(AppError::ConfigError("Environment file not found".to_string()));
     }
 
-    let env_str = std::fs::read_to_string(&env_file)
-        .map_err(|e| AppError::ConfigError(format!("Failed to read environment file: {}", e)))?;
-
-    let env_vars: Vec<(String, String)> = env_str.lines()
-        .filter(|line| !line.is_empty() && !line.starts_with('#'))
-        .map(|line| {
-            let mut parts = line.split('=');
-            let key = parts.next().unwrap().trim().to_string();
-            let value = parts.next().unwrap().trim().to_string();
-            (key, value)
-        })
-        .collect();
+    let env_vars = load_env_document(&env_file)?.vars();
 
     tracing::info!("Environment variables loaded successfully from: {:?}", env_file);
     Ok(env_vars)
 }
+
+/// Save Environment Variables to file, keeping comments and the spelling of unchanged values.
+///
+/// A file that does not parse is backed up to `.env.bak` and replaced.
+pub fn save_env_to_file(env_vars: &[(String, String)]) -> AppResult<()> {
+    let env_file = env_file_path()?;
+    // 文件无法解析时直接覆盖，否则环境变量页面无法修复它；原文件另存为 .env.bak
+    let mut document = match load_env_document(&env_file) {
+        Ok(document) => document,
+        Err(e) if env_file.exists() => {
+            tracing::warn!("Overwriting unreadable environment file: {}", e);
+            std::fs::copy(&env_file, env_file.with_extension("bak"))
+                .map_err(|e| AppError::ConfigError(format!("Failed to back up environment file: {}", e)))?;
+            EnvDocument::default()
+        }
+        Err(e) => return Err(e),
+    };
+    document.replace_vars(env_vars);
+
+    if let Some(dir) = env_file.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| AppError::ConfigError(format!("Failed to create config directory: {}", e)))?;
+    }
+    std::fs::write(&env_file, document.to_string())
+        .map_err(|e| AppError::ConfigError(format!("Failed to write environment file: {}", e)))?;
+
+    tracing::info!("Environment variables saved successfully to: {:?}", env_file);
+    Ok(())
+}
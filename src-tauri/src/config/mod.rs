@@ -1,8 +1,11 @@
 //! Configuration modules
 
+pub mod dotenv;
 pub mod loader;
 pub mod schema;
 
 /// Re-exports
-pub use loader::{save_config, get_default_data_dir, get_user_home, load_config, load_settings};
-pub use schema::{AppConfig, AppSettings, DatabaseSettings};
\ No newline at end of file
+pub use loader::{
+    get_default_data_dir, get_user_home, load_config, load_env_from_file, load_settings, save_config, save_env_to_file,
+};
+pub use schema::{AppConfig, AppSettings, DatabaseSettings};
@@ -6,9 +6,13 @@ use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use tauri::async_runtime::JoinHandle;
 use tauri::{App, AppHandle, Manager, State};
+use tokio::sync::watch;
+use tracing::{error, info, warn};
 
 use crate::utils::error::AppResult;
 use crate::config::schema::AppConfig;
+use crate::services::ai::AiService;
+use crate::services::secret_vault::SharedVault;
 use crate::services::terminal::TerminalService;
 
 /// Application state shared across the application
@@ -20,10 +24,46 @@ pub struct AppState {
     pub config: Mutex<AppConfig>,
     /// Database connection pool
     pub db_pool: Arc<crate::database::connection::DatabasePool>,
+    /// Secret vault shared by every command, so concurrent changes are not lost
+    pub vault: SharedVault,
     /// Terminal service for managing terminal sessions
     pub terminal: TerminalService,
+    /// AI model registry; requests run against a clone so the lock is never held while streaming
+    pub ai: Mutex<AiService>,
+    /// Held across changing, saving and publishing the AI model registry so concurrent changes are not lost
+    pub ai_update: tokio::sync::Mutex<()>,
     /// Active streaming tasks for cancellation
-    pub streaming_tasks: Mutex<HashMap<String, Arc<Mutex<Option<JoinHandle<()>>>>>>,
+    pub streaming_tasks: Mutex<HashMap<String, Arc<StreamingTask>>>,
+    /// Running non-interactive commands for cancellation, by command id
+    pub command_tasks: Mutex<HashMap<String, Arc<StreamingTask>>>,
+}
+
+/// In-flight streaming request
+#[derive(Debug)]
+pub struct StreamingTask {
+    /// Background task handle (taken by the cleanup task that awaits it)
+    pub handle: Mutex<Option<JoinHandle<()>>>,
+    /// Cancellation signal observed by the AI service while the wrapper is running
+    pub cancel: watch::Sender<bool>,
+}
+
+impl StreamingTask {
+    /// Create a task entry together with the receiver handed to the AI service
+    pub fn new() -> (Self, watch::Receiver<bool>) {
+        let (cancel, cancel_rx) = watch::channel(false);
+        (
+            Self {
+                handle: Mutex::new(None),
+                cancel,
+            },
+            cancel_rx,
+        )
+    }
+
+    /// Request cancellation; the running task kills the wrapper and reports partial output
+    pub fn cancel(&self) {
+        let _ = self.cancel.send(true);
+    }
 }
 
 impl AppState {
@@ -33,12 +73,19 @@ impl AppState {
         config: AppConfig,
         db_pool: Arc<crate::database::connection::DatabasePool>,
     ) -> Self {
+        let vault = SharedVault::default();
+        let mut ai = AiService::new();
+        ai.set_vault(vault.clone());
         Self {
             app_handle,
             config: Mutex::new(config),
             db_pool,
+            vault,
             terminal: TerminalService::new(),
+            ai: Mutex::new(ai),
+            ai_update: tokio::sync::Mutex::new(()),
             streaming_tasks: Mutex::new(HashMap::new()),
+            command_tasks: Mutex::new(HashMap::new()),
         }
     }
 }
@@ -80,6 +127,41 @@ pub fn get_config(state: State<'_, AppState>) -> AppConfig {
     state.inner().config.lock().unwrap().clone()
 }
 
+/// Get a snapshot of the AI service from Tauri state
+pub fn get_ai_service(state: State<'_, AppState>) -> AiService {
+    state.inner().ai.lock().unwrap().clone()
+}
+
+/// Load the persisted AI model registry into the managed AI service
+pub async fn load_ai_service(app_handle: &AppHandle, db: &sea_orm::DatabaseConnection) -> AppResult<()> {
+    let _update = app_handle.state::<AppState>().ai_update.lock().await;
+    let mut ai = get_ai_service(app_handle.state::<AppState>());
+    ai.load_settings(db).await?;
+    // Keys saved in plaintext by older versions move into the vault
+    match ai.seal_api_keys() {
+        Ok(0) => {}
+        Ok(_) => ai.save_settings(db).await?,
+        Err(e) => warn!("Failed to move model API keys into the vault: {}", e),
+    }
+    *app_handle.state::<AppState>().ai.lock().unwrap() = ai;
+    Ok(())
+}
+
+/// Save the open terminals so their workspace reopens them on the next launch
+pub fn save_terminal_sessions(app_handle: &AppHandle) {
+    let Some(state) = app_handle.try_state::<AppState>() else {
+        return;
+    };
+    let result = tauri::async_runtime::block_on(async {
+        let db = crate::database::connection::get_db_connection(app_handle).await?;
+        state.terminal.save_sessions(&db).await
+    });
+    match result {
+        Ok(saved) => info!("Saved {} terminal sessions", saved),
+        Err(e) => error!("Failed to save terminal sessions: {}", e),
+    }
+}
+
 /// Get database connection pool from Tauri state
 pub fn get_db_pool(state: State<'_, AppState>) -> Arc<crate::database::connection::DatabasePool> {
     state.inner().db_pool.clone()
@@ -3,12 +3,15 @@
 //! This module handles database connections and connection pooling.
 
 use std::sync::Arc;
+use std::time::Duration;
 use tauri::{App, AppHandle, Manager};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
+use sea_orm::sqlx::sqlite::{SqliteJournalMode, SqliteSynchronous};
 use sea_orm::{ConnectOptions, Database, DatabaseConnection};
 use tokio::sync::Mutex;
 
+use super::integrity;
 use crate::config::schema::AppConfig;
 use crate::migration;
 use crate::utils::error::{AppError, AppResult};
@@ -33,19 +36,51 @@ impl DatabasePool {
         let mut conn = self.connection.lock().await;
 
         if conn.is_none() {
-            *conn = Some(Self::create_connection(database_url, max_connections, min_connections).await?);
+            *conn = Some(Self::open_checked(database_url, max_connections, min_connections).await?);
         }
 
         Ok(conn.as_ref().unwrap().clone())
     }
 
+    /// Connect and check the database, restoring the last backup when it is damaged.
+    /// A failed connection (locked file, bad permissions) is returned as is and never
+    /// replaces the database files.
+    async fn open_checked(
+        database_url: &str,
+        max_connections: u32,
+        min_connections: u32,
+    ) -> AppResult<DatabaseConnection> {
+        let conn = Self::create_connection(database_url, max_connections, min_connections).await?;
+        if integrity::is_intact(&conn).await {
+            return Ok(conn);
+        }
+        warn!("Database failed its integrity check, restoring the backup");
+        conn.close().await?;
+
+        match integrity::sqlite_path(database_url) {
+            Some(path) => match integrity::restore_backup(&path)? {
+                Some(_) => {}
+                None => error!("Database {:?} is damaged and has no backup", path),
+            },
+            None => error!("Database is damaged and cannot be restored: {}", database_url),
+        }
+        Self::create_connection(database_url, max_connections, min_connections).await
+    }
+
     /// Create a new database connection
     async fn create_connection(database_url: &str, max_connections: u32, min_connections: u32) -> AppResult<DatabaseConnection> {
         let mut opt = ConnectOptions::new(database_url.to_string());
         opt.max_connections(max_connections)
             .min_connections(min_connections)
             .sqlx_logging(true)
-            .sqlx_logging_level(tracing::log::LevelFilter::Debug);
+            .sqlx_logging_level(tracing::log::LevelFilter::Debug)
+            // WAL keeps readers off the writer's lock and survives crashes mid-write;
+            // writers wait for each other instead of failing with "database is locked"
+            .map_sqlx_sqlite_opts(|opts| {
+                opts.journal_mode(SqliteJournalMode::Wal)
+                    .synchronous(SqliteSynchronous::Normal)
+                    .busy_timeout(Duration::from_secs(10))
+            });
 
         info!("Connecting to database: {}", database_url);
 
@@ -92,6 +127,14 @@ pub fn init(app: &mut App) -> AppResult<()> {
                     error!("Failed to run database migrations: {}", e);
                 } else {
                     info!("Database migrations completed successfully");
+                    if let Err(e) = crate::core::app::load_ai_service(&app_handle, &db).await {
+                        error!("Failed to load AI model registry: {}", e);
+                    }
+                    if let Err(e) = crate::services::chat_session::import_legacy_sessions(&db).await {
+                        error!("Failed to import chat sessions: {}", e);
+                    }
+                    backup_database(&app_handle, &db).await;
+                    tauri::async_runtime::spawn(crate::services::chat_retention::run(db));
                 }
             }
             Err(e) => {
@@ -104,6 +147,17 @@ pub fn init(app: &mut App) -> AppResult<()> {
     Ok(())
 }
 
+/// Keep a copy of the database as it was after a clean start
+async fn backup_database(app_handle: &AppHandle, db: &DatabaseConnection) {
+    let config = app_handle.state::<AppConfig>();
+    let Some(path) = integrity::sqlite_path(&config.database.url) else {
+        return;
+    };
+    if let Err(e) = integrity::write_backup(db, &integrity::backup_path(&path)).await {
+        error!("Failed to back up database: {}", e);
+    }
+}
+
 /// Get database connection from Tauri state with config
 pub async fn get_db_connection(app_handle: &AppHandle) -> AppResult<DatabaseConnection> {
     let config = app_handle.state::<AppConfig>();
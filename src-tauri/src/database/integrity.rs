@@ -0,0 +1,109 @@
+//! Database integrity checks and backups
+//!
+//! Chat sessions live in the SQLite database, so a damaged file would lose every
+//! session at once. After each clean start a consistent copy is written next to
+//! the database (`app.db.bak`). When a later start finds the database damaged, it
+//! is moved aside as `app.db.corrupt-<timestamp>` and the copy is restored.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, Statement};
+use tracing::{info, warn};
+
+use crate::utils::error::{AppError, AppResult};
+
+/// Files SQLite keeps next to the database in WAL mode
+const SIDECAR_SUFFIXES: [&str; 2] = ["-wal", "-shm"];
+
+/// Database file of a `sqlite:` url; `None` for in-memory databases
+pub fn sqlite_path(url: &str) -> Option<PathBuf> {
+    let rest = url.strip_prefix("sqlite://").or_else(|| url.strip_prefix("sqlite:"))?;
+    let path = rest.split('?').next().unwrap_or_default();
+    if path.is_empty() || path == ":memory:" {
+        return None;
+    }
+    Some(PathBuf::from(path))
+}
+
+/// Backup location for the database at `db_path`
+pub fn backup_path(db_path: &Path) -> PathBuf {
+    with_suffix(db_path, ".bak")
+}
+
+/// Whether `PRAGMA quick_check` reports no problems
+pub async fn is_intact(db: &DatabaseConnection) -> bool {
+    let result = db
+        .query_one(Statement::from_string(DbBackend::Sqlite, "PRAGMA quick_check"))
+        .await
+        .and_then(|row| match row {
+            Some(row) => row.try_get_by_index::<String>(0),
+            None => Ok(String::new()),
+        });
+    match result {
+        Ok(status) if status == "ok" => true,
+        Ok(status) => {
+            warn!("Database integrity check failed: {}", status);
+            false
+        }
+        Err(e) => {
+            warn!("Database integrity check failed: {}", e);
+            false
+        }
+    }
+}
+
+/// Write a consistent copy of the database to `backup`, replacing it atomically
+pub async fn write_backup(db: &DatabaseConnection, backup: &Path) -> AppResult<()> {
+    let tmp = with_suffix(backup, ".tmp");
+    if tmp.exists() {
+        fs::remove_file(&tmp)?;
+    }
+    db.execute(Statement::from_sql_and_values(
+        DbBackend::Sqlite,
+        "VACUUM INTO ?",
+        [tmp.to_string_lossy().to_string().into()],
+    ))
+    .await
+    .map_err(|e| AppError::DatabaseError(format!("Failed to back up database: {}", e)))?;
+    fs::rename(&tmp, backup)?;
+    info!("Database backed up to {:?}", backup);
+    Ok(())
+}
+
+/// Move the damaged database at `db_path` aside and restore its backup.
+///
+/// Returns where the damaged file was moved, or `None` when there is no backup
+/// (the database is then left untouched).
+pub fn restore_backup(db_path: &Path) -> AppResult<Option<PathBuf>> {
+    let backup = backup_path(db_path);
+    if !backup.exists() {
+        return Ok(None);
+    }
+
+    let quarantined = with_suffix(db_path, &format!(".corrupt-{}", Local::now().format("%Y%m%d%H%M%S")));
+    if db_path.exists() {
+        fs::rename(db_path, &quarantined)?;
+    }
+    // The WAL belongs to the damaged file; keep it with it
+    for suffix in SIDECAR_SUFFIXES {
+        let sidecar = with_suffix(db_path, suffix);
+        if sidecar.exists() {
+            fs::rename(&sidecar, with_suffix(&quarantined, suffix))?;
+        }
+    }
+    fs::copy(&backup, db_path)?;
+
+    warn!(
+        "Database restored from {:?}, damaged file kept at {:?}",
+        backup, quarantined
+    );
+    Ok(Some(quarantined))
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
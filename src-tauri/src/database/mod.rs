@@ -1,5 +1,6 @@
 //! Database modules
 
 pub mod connection;
+pub mod integrity;
 pub mod models;
 pub mod repositories;
\ No newline at end of file
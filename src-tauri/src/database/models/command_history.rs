@@ -0,0 +1,42 @@
+//! Shell command history database model
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "command_history")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i32,
+    /// Workspace of the terminal the command ran in
+    pub workspace_id: Option<i32>,
+    #[sea_orm(column_type = "Text")]
+    pub command: String,
+    /// Shell the command line was passed to, e.g. `bash`
+    pub shell: String,
+    pub cwd: String,
+    /// `None` while running, or when killed by a signal, timeout or cancellation
+    pub exit_code: Option<i32>,
+    /// `None` while running
+    pub duration_ms: Option<i64>,
+    pub started_at: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::workspace::Entity",
+        from = "Column::WorkspaceId",
+        to = "super::workspace::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Workspace,
+}
+
+impl Related<super::workspace::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Workspace.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,4 +1,6 @@
 //! Conversion database model
+//!
+//! A conversion is one chat session; its messages live in `conversion_message`.
 
 use sea_orm::entity::prelude::*;
 use sea_orm::Set;
@@ -9,12 +11,38 @@ use serde::{Deserialize, Serialize};
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = true)]
     pub id: i32,
-    /// Conversion name
-    pub name: String,
-    /// Conversion ID
+    /// Conversion ID (the chat session id used by the frontend)
+    #[sea_orm(unique)]
     pub conversion_id: String,
-    /// Description of the setting
+    /// Conversion name
+    pub name: Option<String>,
+    /// Owning workspace
+    pub workspace_id: Option<i32>,
+    /// Session id reported by the code CLI, when it differs from `conversion_id`
+    pub session_id: Option<String>,
+    /// Last message of the branch currently shown
+    pub active_leaf_id: Option<String>,
+    /// Code CLI task ids keyed by CLI name (JSON object), as of the latest append
+    #[sea_orm(column_type = "Text")]
+    pub code_cli_task_ids: String,
+    /// Number of messages in the conversion
+    pub message_count: i32,
+    /// First characters of the first message
+    pub first_message_preview: String,
+    /// Description of the conversion
     pub description: Option<String>,
+    /// Tag names (JSON array)
+    #[sea_orm(column_type = "Text")]
+    pub tags: String,
+    /// Pinned sessions are listed first and never archived by the retention policy
+    pub pinned: bool,
+    /// When the session was archived; archived sessions are hidden from the list
+    pub archived_at: Option<ChronoDateTimeUtc>,
+    /// Rolling summary of the messages up to `summary_through_id`
+    #[sea_orm(column_type = "Text", nullable)]
+    pub summary: Option<String>,
+    /// Last message covered by `summary`
+    pub summary_through_id: Option<String>,
     /// Created timestamp
     pub created_at: ChronoDateTimeUtc,
     /// Updated timestamp
@@ -22,86 +50,44 @@ pub struct Model {
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::workspace::Entity",
+        from = "Column::WorkspaceId",
+        to = "super::workspace::Column::Id",
+        on_delete = "SetNull"
+    )]
+    Workspace,
+    #[sea_orm(has_many = "super::conversion_message::Entity")]
+    Messages,
+}
 
-impl ActiveModelBehavior for ActiveModel {
-    /// Set timestamps before saving
-    #[doc = " Will be called before `ActiveModel::insert` and `ActiveModel::update`"]
-    #[must_use]
-    #[allow(elided_named_lifetimes,clippy::async_yields_async,clippy::diverging_sub_expression,clippy::let_unit_value,clippy::needless_arbitrary_self_type,clippy::no_effect_underscore_binding,clippy::shadow_same,clippy::type_complexity,clippy::type_repetition_in_bounds,clippy::used_underscore_binding)]
-    fn before_save<'life0,'async_trait,C, >(mut self,db: &'life0 C,insert:bool,) ->  ::core::pin::Pin<Box<dyn ::core::future::Future<Output = Result<Self,DbErr> > + ::core::marker::Send+'async_trait> >where C:ConnectionTrait,C:'async_trait+ ,'life0:'async_trait,Self: ::core::marker::Send+'async_trait{
-        Box::pin(async move {
-            if let::core::option::Option::Some(__ret) =  ::core::option::Option::None:: <Result<Self,DbErr> >{
-                #[allow(unreachable_code)]
-                return __ret;
-            }let insert = insert;
-            let __ret:Result<Self,DbErr>  = {
-                let now = chrono::Utc::now();
-                if insert {
-                    self.created_at = Set(now);
-                }
-                self.updated_at = Set(now);
-                Ok(self)
-            };
-            #[allow(unreachable_code)]
-            __ret
-        })
-    }
-    
-    #[doc = " Create a new ActiveModel with default values. Also used by `Default::default()`."]
-    fn new() -> Self {
-        <Self as ActiveModelTrait> ::default()
-    }
-    
-    #[doc = " Will be called after `ActiveModel::insert`, `ActiveModel::update`, and `ActiveModel::save`"]
-    #[must_use]
-    #[allow(elided_named_lifetimes,clippy::async_yields_async,clippy::diverging_sub_expression,clippy::let_unit_value,clippy::needless_arbitrary_self_type,clippy::no_effect_underscore_binding,clippy::shadow_same,clippy::type_complexity,clippy::type_repetition_in_bounds,clippy::used_underscore_binding)]
-    fn after_save<'life0,'async_trait,C, >(model: <Self::Entity as EntityTrait> ::Model,db: &'life0 C,insert:bool,) ->  ::core::pin::Pin<Box<dyn ::core::future::Future<Output = Result< <Self::Entity as EntityTrait> ::Model,DbErr> > + ::core::marker::Send+'async_trait> >where C:ConnectionTrait,C:'async_trait+ ,'life0:'async_trait,Self: ::core::marker::Send+'async_trait{
-        Box::pin(async move {
-            if let::core::option::Option::Some(__ret) =  ::core::option::Option::None:: <Result< <Self::Entity as EntityTrait> ::Model,DbErr> >{
-                #[allow(unreachable_code)]
-                return __ret;
-            }let model = model;
-            let insert = insert;
-            let __ret:Result< <Self::Entity as EntityTrait> ::Model,DbErr>  = {
-                Ok(model)
-            };
-            #[allow(unreachable_code)]
-            __ret
-        })
+impl Related<super::workspace::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Workspace.def()
     }
-    
-    #[doc = " Will be called before `ActiveModel::delete`"]
-    #[must_use]
-    #[allow(elided_named_lifetimes,clippy::async_yields_async,clippy::diverging_sub_expression,clippy::let_unit_value,clippy::needless_arbitrary_self_type,clippy::no_effect_underscore_binding,clippy::shadow_same,clippy::type_complexity,clippy::type_repetition_in_bounds,clippy::used_underscore_binding)]
-    fn before_delete<'life0,'async_trait,C, >(self,db: &'life0 C) ->  ::core::pin::Pin<Box<dyn ::core::future::Future<Output = Result<Self,DbErr> > + ::core::marker::Send+'async_trait> >where C:ConnectionTrait,C:'async_trait+ ,'life0:'async_trait,Self: ::core::marker::Send+'async_trait{
-        Box::pin(async move {
-            if let::core::option::Option::Some(__ret) =  ::core::option::Option::None:: <Result<Self,DbErr> >{
-                #[allow(unreachable_code)]
-                return __ret;
-            }let __self = self;
-            let __ret:Result<Self,DbErr>  = {
-                Ok(__self)
-            };
-            #[allow(unreachable_code)]
-            __ret
-        })
+}
+
+impl Related<super::conversion_message::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Messages.def()
     }
-    
-    #[doc = " Will be called after `ActiveModel::delete`"]
-    #[must_use]
-    #[allow(elided_named_lifetimes,clippy::async_yields_async,clippy::diverging_sub_expression,clippy::let_unit_value,clippy::needless_arbitrary_self_type,clippy::no_effect_underscore_binding,clippy::shadow_same,clippy::type_complexity,clippy::type_repetition_in_bounds,clippy::used_underscore_binding)]
-    fn after_delete<'life0,'async_trait,C, >(self,db: &'life0 C) ->  ::core::pin::Pin<Box<dyn ::core::future::Future<Output = Result<Self,DbErr> > + ::core::marker::Send+'async_trait> >where C:ConnectionTrait,C:'async_trait+ ,'life0:'async_trait,Self: ::core::marker::Send+'async_trait{
-        Box::pin(async move {
-            if let::core::option::Option::Some(__ret) =  ::core::option::Option::None:: <Result<Self,DbErr> >{
-                #[allow(unreachable_code)]
-                return __ret;
-            }let __self = self;
-            let __ret:Result<Self,DbErr>  = {
-                Ok(__self)
-            };
-            #[allow(unreachable_code)]
-            __ret
-        })
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    /// Set timestamps before saving, keeping explicitly set ones (e.g. from imports)
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let now = chrono::Utc::now();
+        if insert && !self.created_at.is_set() {
+            self.created_at = Set(now);
+        }
+        if !self.updated_at.is_set() {
+            self.updated_at = Set(now);
+        }
+        Ok(self)
     }
 }
@@ -0,0 +1,62 @@
+//! Conversion message database model
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "conversion_message")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i32,
+    /// Owning conversion (`conversion.id`)
+    pub conversion_id: i32,
+    /// Message ID as seen by the frontend
+    pub message_id: String,
+    /// Position of the message within the conversion, starting at 0 (insertion order)
+    pub position: i32,
+    /// `message_id` of the previous message on its branch; `None` for the first message
+    pub parent_id: Option<String>,
+    /// `user`, `assistant` or `system`
+    pub role: String,
+    /// Message text
+    #[sea_orm(column_type = "Text")]
+    pub content: String,
+    /// Attached files (JSON array)
+    #[sea_orm(column_type = "Text", nullable)]
+    pub files: Option<String>,
+    /// Model that produced the message
+    pub model: Option<String>,
+    /// Code CLI that produced the message
+    pub code_cli: Option<String>,
+    /// Code CLI session the message belongs to (resume id)
+    pub code_cli_task_id: Option<String>,
+    /// Message timestamp
+    pub created_at: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::conversion::Entity",
+        from = "Column::ConversionId",
+        to = "super::conversion::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Conversion,
+    #[sea_orm(has_one = "super::message_run::Entity")]
+    MessageRun,
+}
+
+impl Related<super::conversion::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Conversion.def()
+    }
+}
+
+impl Related<super::message_run::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MessageRun.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,50 @@
+//! Message run database model
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "message_run")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i32,
+    /// Assistant message produced by the run (`conversion_message.id`)
+    #[sea_orm(unique)]
+    pub message_id: i32,
+    /// Backend that served the request, e.g. `codeagent:codex` or `anthropic`
+    pub backend: String,
+    pub model: Option<String>,
+    pub workdir: Option<String>,
+    pub duration_ms: i64,
+    /// Exit code of codeagent-wrapper
+    pub exit_code: Option<i32>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub stderr_tail: Option<String>,
+    pub input_tokens: Option<i64>,
+    pub output_tokens: Option<i64>,
+    /// The run was cancelled and the message holds partial output
+    pub cancelled: bool,
+    /// Error of a failed run
+    #[sea_orm(column_type = "Text", nullable)]
+    pub error: Option<String>,
+    pub created_at: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::conversion_message::Entity",
+        from = "Column::MessageId",
+        to = "super::conversion_message::Column::Id",
+        on_delete = "Cascade"
+    )]
+    ConversionMessage,
+}
+
+impl Related<super::conversion_message::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ConversionMessage.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
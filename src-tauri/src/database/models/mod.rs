@@ -1,5 +1,9 @@
 //! Database models module
 
+pub mod command_history;
+pub mod conversion;
+pub mod conversion_message;
+pub mod message_run;
 pub mod settings;
+pub mod terminal_session;
 pub mod workspace;
-pub mod conversion;
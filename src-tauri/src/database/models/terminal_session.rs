@@ -0,0 +1,43 @@
+//! Saved terminal session database model
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "terminal_session")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    /// Workspace the terminal was opened in
+    pub workspace_id: Option<i32>,
+    pub name: String,
+    /// Last known working directory of the shell
+    pub cwd: String,
+    /// Shell selected in the UI; `None` for the platform default
+    pub shell: Option<String>,
+    /// Tail of the output, ANSI sequences included
+    #[sea_orm(column_type = "Text")]
+    pub scrollback: String,
+    /// Tab position within the workspace
+    pub position: i32,
+    pub updated_at: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::workspace::Entity",
+        from = "Column::WorkspaceId",
+        to = "super::workspace::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Workspace,
+}
+
+impl Related<super::workspace::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Workspace.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,86 @@
+//! Shell command history repository
+
+use chrono::Utc;
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+
+use crate::database::models::command_history::{
+    self, ActiveModel as CommandHistoryActiveModel, Entity as CommandHistory, Model as CommandHistoryModel,
+};
+use crate::utils::error::{AppError, AppResult};
+
+/// Shell command history repository
+pub struct CommandHistoryRepository;
+
+impl CommandHistoryRepository {
+    /// Record a command that just started
+    pub async fn create(
+        db: &DatabaseConnection,
+        workspace_id: Option<i32>,
+        command: &str,
+        shell: &str,
+        cwd: &str,
+    ) -> AppResult<CommandHistoryModel> {
+        let entry = CommandHistoryActiveModel {
+            workspace_id: Set(workspace_id),
+            command: Set(command.to_string()),
+            shell: Set(shell.to_string()),
+            cwd: Set(cwd.to_string()),
+            exit_code: Set(None),
+            duration_ms: Set(None),
+            started_at: Set(Utc::now()),
+            ..Default::default()
+        };
+        entry
+            .insert(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// Store how a recorded command ended
+    pub async fn finish(db: &DatabaseConnection, id: i32, exit_code: Option<i32>, duration_ms: i64) -> AppResult<()> {
+        CommandHistory::update_many()
+            .col_expr(command_history::Column::ExitCode, Expr::value(exit_code))
+            .col_expr(command_history::Column::DurationMs, Expr::value(duration_ms))
+            .filter(command_history::Column::Id.eq(id))
+            .exec(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub async fn find_by_id(db: &DatabaseConnection, id: i32) -> AppResult<Option<CommandHistoryModel>> {
+        CommandHistory::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// Most recent commands of a workspace, newest first, optionally only those
+    /// starting with `prefix` (case-sensitive)
+    pub async fn list_recent(
+        db: &DatabaseConnection,
+        workspace_id: Option<i32>,
+        prefix: Option<&str>,
+        limit: u64,
+    ) -> AppResult<Vec<CommandHistoryModel>> {
+        let mut query = CommandHistory::find().filter(match workspace_id {
+            Some(id) => command_history::Column::WorkspaceId.eq(id),
+            None => command_history::Column::WorkspaceId.is_null(),
+        });
+        if let Some(prefix) = prefix.filter(|prefix| !prefix.is_empty()) {
+            // LIKE ignores ASCII case in SQLite; shell commands do not
+            query = query.filter(Expr::cust_with_values(
+                "substr(command, 1, length(?)) = ?",
+                [prefix, prefix],
+            ));
+        }
+        query
+            .order_by_desc(command_history::Column::StartedAt)
+            .order_by_desc(command_history::Column::Id)
+            .limit(limit)
+            .all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+}
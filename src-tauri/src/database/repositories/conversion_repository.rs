@@ -0,0 +1,706 @@
+//! Conversion repository
+//!
+//! Chat sessions (`conversion`) and their messages (`conversion_message`).
+
+use std::collections::HashMap;
+
+use crate::database::models::conversion::{self, Entity as Conversion, Model as ConversionModel};
+use crate::database::models::conversion_message::{self, Entity as ConversionMessage, Model as ConversionMessageModel};
+use crate::database::models::message_run::{self, Entity as MessageRun, Model as MessageRunModel};
+use crate::utils::error::{AppError, AppResult};
+use chrono::{DateTime, Utc};
+use sea_orm::sea_query::{Expr, LikeExpr, Query};
+use sea_orm::*;
+
+/// Messages per INSERT statement
+const INSERT_CHUNK_SIZE: usize = 500;
+
+/// Session fields written by `replace` and `append_messages`
+#[derive(Debug, Clone, Default)]
+pub struct ConversionRecord {
+    pub conversion_id: String,
+    pub name: Option<String>,
+    pub workspace_id: Option<i32>,
+    pub session_id: Option<String>,
+    /// Last message of the branch to show; `append_messages` uses the last appended message
+    pub active_leaf_id: Option<String>,
+    /// JSON object of code CLI task ids
+    pub code_cli_task_ids: String,
+    /// JSON array of tags and pinned flag; `None` keeps those of an existing conversion
+    pub tags: Option<String>,
+    pub pinned: Option<bool>,
+    /// Keep these timestamps instead of the current time (used by imports)
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Which conversions `list_by_workspace` returns
+#[derive(Debug, Clone, Default)]
+pub struct ConversionFilter {
+    /// Only archived conversions instead of only unarchived ones
+    pub archived: bool,
+    pub pinned: Option<bool>,
+    /// Tags a conversion must all carry
+    pub tags: Vec<String>,
+}
+
+/// A message to store
+#[derive(Debug, Clone)]
+pub struct MessageRecord {
+    pub message_id: String,
+    /// `message_id` of the previous message on its branch
+    pub parent_id: Option<String>,
+    pub role: String,
+    pub content: String,
+    /// JSON array of attached files
+    pub files: Option<String>,
+    pub model: Option<String>,
+    pub code_cli: Option<String>,
+    pub code_cli_task_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// How an assistant message was produced
+    pub run: Option<RunRecord>,
+}
+
+/// Run metadata stored with an assistant message
+#[derive(Debug, Clone, Default)]
+pub struct RunRecord {
+    pub backend: String,
+    pub model: Option<String>,
+    pub workdir: Option<String>,
+    pub duration_ms: i64,
+    pub exit_code: Option<i32>,
+    pub stderr_tail: Option<String>,
+    pub input_tokens: Option<i64>,
+    pub output_tokens: Option<i64>,
+    pub cancelled: bool,
+    pub error: Option<String>,
+}
+
+/// A message matched by a search, with its conversion
+#[derive(Debug, Clone, FromQueryResult)]
+pub struct MessageSearchRow {
+    pub message_id: String,
+    pub role: String,
+    pub content: String,
+    pub files: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub conversion_id: String,
+    pub name: Option<String>,
+    pub workspace_id: Option<i32>,
+    /// bm25 rank, lower is better (0 when ranked by recency)
+    pub rank: f64,
+}
+
+/// conversion repository
+pub struct ConversionRepository;
+
+impl ConversionRepository {
+    /// Get a conversion by its conversion id
+    pub async fn get_by_conversion_id(
+        db: &DatabaseConnection,
+        conversion_id: &str,
+    ) -> AppResult<Option<ConversionModel>> {
+        let conversion = Conversion::find()
+            .filter(conversion::Column::ConversionId.eq(conversion_id))
+            .one(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(conversion)
+    }
+
+    /// The conversion of a workspace continuing a CLI session, e.g. one imported from that tool
+    pub async fn get_by_session_id(
+        db: &DatabaseConnection,
+        workspace_id: i32,
+        session_id: &str,
+    ) -> AppResult<Option<ConversionModel>> {
+        let conversion = Conversion::find()
+            .filter(conversion::Column::WorkspaceId.eq(workspace_id))
+            .filter(conversion::Column::SessionId.eq(session_id))
+            .order_by(conversion::Column::Id, Order::Asc)
+            .one(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(conversion)
+    }
+
+    /// One page of conversions for a workspace, pinned first, then most recently updated
+    pub async fn list_by_workspace(
+        db: &DatabaseConnection,
+        workspace_id: Option<i32>,
+        filter: &ConversionFilter,
+        offset: u64,
+        limit: u64,
+    ) -> AppResult<Vec<ConversionModel>> {
+        let mut query = Conversion::find()
+            .filter(match workspace_id {
+                Some(id) => conversion::Column::WorkspaceId.eq(id),
+                None => conversion::Column::WorkspaceId.is_null(),
+            })
+            .filter(if filter.archived {
+                conversion::Column::ArchivedAt.is_not_null()
+            } else {
+                conversion::Column::ArchivedAt.is_null()
+            });
+        if let Some(pinned) = filter.pinned {
+            query = query.filter(conversion::Column::Pinned.eq(pinned));
+        }
+        for tag in &filter.tags {
+            query = query.filter(Expr::cust_with_values(
+                "EXISTS (SELECT 1 FROM json_each(conversion.tags) WHERE json_each.value = ?)",
+                [tag.clone()],
+            ));
+        }
+        let conversions = query
+            .order_by(conversion::Column::Pinned, Order::Desc)
+            .order_by(conversion::Column::UpdatedAt, Order::Desc)
+            .order_by(conversion::Column::Id, Order::Desc)
+            .offset(offset)
+            .limit(limit)
+            .all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(conversions)
+    }
+
+    /// Number of conversions in a workspace
+    pub async fn count_by_workspace(db: &DatabaseConnection, workspace_id: Option<i32>) -> AppResult<u64> {
+        let count = Conversion::find()
+            .filter(match workspace_id {
+                Some(id) => conversion::Column::WorkspaceId.eq(id),
+                None => conversion::Column::WorkspaceId.is_null(),
+            })
+            .count(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(count)
+    }
+
+    /// Messages of the given conversions (by primary key) with their runs, ordered by position
+    pub async fn get_messages(
+        db: &DatabaseConnection,
+        ids: &[i32],
+    ) -> AppResult<Vec<(ConversionMessageModel, Option<MessageRunModel>)>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let messages = ConversionMessage::find()
+            .find_also_related(MessageRun)
+            .filter(conversion_message::Column::ConversionId.is_in(ids.iter().copied()))
+            .order_by(conversion_message::Column::ConversionId, Order::Asc)
+            .order_by(conversion_message::Column::Position, Order::Asc)
+            .all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(messages)
+    }
+
+    /// Create or overwrite a conversion together with all of its messages
+    pub async fn replace(
+        db: &DatabaseConnection,
+        record: ConversionRecord,
+        messages: &[MessageRecord],
+    ) -> AppResult<ConversionModel> {
+        let txn = db.begin().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let existing = Conversion::find()
+            .filter(conversion::Column::ConversionId.eq(record.conversion_id.as_str()))
+            .one(&txn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let mut active_model: conversion::ActiveModel = match existing {
+            Some(existing_model) => {
+                Self::delete_messages(&txn, existing_model.id).await?;
+                existing_model.into()
+            }
+            None => conversion::ActiveModel {
+                conversion_id: Set(record.conversion_id.clone()),
+                tags: Set("[]".to_string()),
+                pinned: Set(false),
+                ..Default::default()
+            },
+        };
+        if let Some(tags) = record.tags {
+            active_model.tags = Set(tags);
+        }
+        if let Some(pinned) = record.pinned {
+            active_model.pinned = Set(pinned);
+        }
+        active_model.name = Set(record.name);
+        active_model.workspace_id = Set(record.workspace_id);
+        active_model.session_id = Set(record.session_id);
+        active_model.active_leaf_id = Set(record.active_leaf_id);
+        active_model.code_cli_task_ids = Set(record.code_cli_task_ids);
+        active_model.message_count = Set(messages.len() as i32);
+        active_model.first_message_preview = Set(messages.first().map(|m| preview(&m.content)).unwrap_or_default());
+        if let Some(created_at) = record.created_at {
+            active_model.created_at = Set(created_at);
+        }
+        if let Some(updated_at) = record.updated_at {
+            active_model.updated_at = Set(updated_at);
+        }
+        let model = active_model
+            .save(&txn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .try_into_model()
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::insert_messages(&txn, model.id, 0, messages).await?;
+
+        txn.commit().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(model)
+    }
+
+    /// Append messages to a conversion, creating it from `record` when missing.
+    ///
+    /// `name`, `workspace_id` and `created_at` of an existing conversion are kept;
+    /// `session_id` and `code_cli_task_ids` are overwritten, and the last appended
+    /// message becomes the active leaf. Parent pointers are taken from `messages`.
+    pub async fn append_messages(
+        db: &DatabaseConnection,
+        record: ConversionRecord,
+        messages: &[MessageRecord],
+    ) -> AppResult<ConversionModel> {
+        let txn = db.begin().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let existing = Conversion::find()
+            .filter(conversion::Column::ConversionId.eq(record.conversion_id.as_str()))
+            .one(&txn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let (mut active_model, start): (conversion::ActiveModel, i32) = match existing {
+            Some(existing_model) => {
+                let start = existing_model.message_count;
+                let mut active_model: conversion::ActiveModel = existing_model.into();
+                // New messages bring an archived session back
+                active_model.archived_at = Set(None);
+                if start == 0 {
+                    active_model.first_message_preview =
+                        Set(messages.first().map(|m| preview(&m.content)).unwrap_or_default());
+                }
+                (active_model, start)
+            }
+            None => (
+                conversion::ActiveModel {
+                    conversion_id: Set(record.conversion_id.clone()),
+                    name: Set(record.name),
+                    workspace_id: Set(record.workspace_id),
+                    first_message_preview: Set(messages.first().map(|m| preview(&m.content)).unwrap_or_default()),
+                    tags: Set(record.tags.unwrap_or_else(|| "[]".to_string())),
+                    pinned: Set(record.pinned.unwrap_or_default()),
+                    ..Default::default()
+                },
+                0,
+            ),
+        };
+        active_model.session_id = Set(record.session_id);
+        if let Some(last) = messages.last() {
+            active_model.active_leaf_id = Set(Some(last.message_id.clone()));
+        }
+        active_model.code_cli_task_ids = Set(record.code_cli_task_ids);
+        active_model.message_count = Set(start + messages.len() as i32);
+        let model = active_model
+            .save(&txn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .try_into_model()
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::insert_messages(&txn, model.id, start, messages).await?;
+
+        txn.commit().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(model)
+    }
+
+    /// Rename a conversion
+    pub async fn rename(
+        db: &DatabaseConnection,
+        conversion_id: &str,
+        name: &str,
+    ) -> AppResult<Option<ConversionModel>> {
+        let Some(existing) = Self::get_by_conversion_id(db, conversion_id).await? else {
+            return Ok(None);
+        };
+
+        let mut active_model: conversion::ActiveModel = existing.into();
+        active_model.name = Set(Some(name.to_string()));
+        let model = active_model
+            .update(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(Some(model))
+    }
+
+    /// Select the branch ending at `leaf_id`
+    pub async fn set_active_leaf(
+        db: &DatabaseConnection,
+        conversion_id: &str,
+        leaf_id: &str,
+    ) -> AppResult<Option<ConversionModel>> {
+        let Some(existing) = Self::get_by_conversion_id(db, conversion_id).await? else {
+            return Ok(None);
+        };
+
+        let mut active_model: conversion::ActiveModel = existing.into();
+        active_model.active_leaf_id = Set(Some(leaf_id.to_string()));
+        let model = active_model
+            .update(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(Some(model))
+    }
+
+    /// Replace the tags (JSON array) of a conversion; not counted as activity
+    pub async fn set_tags(
+        db: &DatabaseConnection,
+        conversion_id: &str,
+        tags: &str,
+    ) -> AppResult<Option<ConversionModel>> {
+        Self::update_quietly(db, conversion_id, |model| model.tags = Set(tags.to_string())).await
+    }
+
+    /// Pin or unpin a conversion; not counted as activity
+    pub async fn set_pinned(
+        db: &DatabaseConnection,
+        conversion_id: &str,
+        pinned: bool,
+    ) -> AppResult<Option<ConversionModel>> {
+        Self::update_quietly(db, conversion_id, |model| model.pinned = Set(pinned)).await
+    }
+
+    /// Archive (with the time of archiving) or unarchive a conversion; not counted as activity
+    pub async fn set_archived_at(
+        db: &DatabaseConnection,
+        conversion_id: &str,
+        archived_at: Option<DateTime<Utc>>,
+    ) -> AppResult<Option<ConversionModel>> {
+        Self::update_quietly(db, conversion_id, |model| model.archived_at = Set(archived_at)).await
+    }
+
+    /// Store the rolling summary of the messages up to `through_id`; not counted as activity
+    pub async fn set_summary(
+        db: &DatabaseConnection,
+        conversion_id: &str,
+        summary: &str,
+        through_id: &str,
+    ) -> AppResult<Option<ConversionModel>> {
+        Self::update_quietly(db, conversion_id, |model| {
+            model.summary = Set(Some(summary.to_string()));
+            model.summary_through_id = Set(Some(through_id.to_string()));
+        })
+        .await
+    }
+
+    /// Archive unpinned conversions last updated before `before`; returns how many
+    pub async fn archive_inactive(
+        db: &DatabaseConnection,
+        before: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> AppResult<u64> {
+        let result = Conversion::update_many()
+            .col_expr(conversion::Column::ArchivedAt, Expr::value(now))
+            .filter(conversion::Column::ArchivedAt.is_null())
+            .filter(conversion::Column::Pinned.eq(false))
+            .filter(conversion::Column::UpdatedAt.lt(before))
+            .exec(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected)
+    }
+
+    /// Ids of conversions archived before `before`
+    pub async fn list_archived_before(db: &DatabaseConnection, before: DateTime<Utc>) -> AppResult<Vec<String>> {
+        let ids = Conversion::find()
+            .select_only()
+            .column(conversion::Column::ConversionId)
+            .filter(conversion::Column::ArchivedAt.lt(before))
+            .into_tuple::<String>()
+            .all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(ids)
+    }
+
+    /// Update a conversion without touching `updated_at`
+    async fn update_quietly<F>(
+        db: &DatabaseConnection,
+        conversion_id: &str,
+        change: F,
+    ) -> AppResult<Option<ConversionModel>>
+    where
+        F: FnOnce(&mut conversion::ActiveModel),
+    {
+        let Some(existing) = Self::get_by_conversion_id(db, conversion_id).await? else {
+            return Ok(None);
+        };
+
+        let updated_at = existing.updated_at;
+        let mut active_model: conversion::ActiveModel = existing.into();
+        change(&mut active_model);
+        active_model.updated_at = Set(updated_at);
+        let model = active_model
+            .update(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(Some(model))
+    }
+
+    /// Delete a conversion and its messages
+    pub async fn delete(db: &DatabaseConnection, conversion_id: &str) -> AppResult<bool> {
+        let Some(existing) = Self::get_by_conversion_id(db, conversion_id).await? else {
+            return Ok(false);
+        };
+
+        let txn = db.begin().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Self::delete_messages(&txn, existing.id).await?;
+        existing
+            .delete(&txn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        txn.commit().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(true)
+    }
+
+    /// Full-text search over message content and attached files, best matches first.
+    ///
+    /// `fts_query` is an FTS5 MATCH expression; content weighs twice as much as files.
+    pub async fn search_messages(
+        db: &DatabaseConnection,
+        fts_query: &str,
+        workspace_id: Option<i32>,
+        limit: u64,
+    ) -> AppResult<Vec<MessageSearchRow>> {
+        let mut sql = String::from(
+            "SELECT m.message_id, m.role, m.content, m.files, m.created_at, \
+                    c.conversion_id, c.name, c.workspace_id, \
+                    bm25(conversion_message_fts, 1.0, 0.5) AS rank \
+             FROM conversion_message_fts \
+             JOIN conversion_message m ON m.id = conversion_message_fts.rowid \
+             JOIN conversion c ON c.id = m.conversion_id \
+             WHERE conversion_message_fts MATCH ?",
+        );
+        let mut values: Vec<Value> = vec![fts_query.into()];
+        if let Some(id) = workspace_id {
+            sql.push_str(" AND c.workspace_id = ?");
+            values.push(id.into());
+        }
+        sql.push_str(" ORDER BY rank LIMIT ?");
+        values.push((limit as i64).into());
+
+        let rows = MessageSearchRow::find_by_statement(Statement::from_sql_and_values(DbBackend::Sqlite, sql, values))
+            .all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(rows)
+    }
+
+    /// Substring search over message content and attached files, newest first.
+    ///
+    /// Used for terms too short for the trigram index; every term must match.
+    pub async fn search_messages_like(
+        db: &DatabaseConnection,
+        terms: &[String],
+        workspace_id: Option<i32>,
+        limit: u64,
+    ) -> AppResult<Vec<MessageSearchRow>> {
+        let mut sql = String::from(
+            "SELECT m.message_id, m.role, m.content, m.files, m.created_at, \
+                    c.conversion_id, c.name, c.workspace_id, 0.0 AS rank \
+             FROM conversion_message m \
+             JOIN conversion c ON c.id = m.conversion_id \
+             WHERE 1 = 1",
+        );
+        let mut values: Vec<Value> = Vec::new();
+        for term in terms {
+            sql.push_str(" AND (m.content LIKE ? ESCAPE '\\' OR m.files LIKE ? ESCAPE '\\')");
+            let pattern = like_pattern(term);
+            values.push(pattern.clone().into());
+            values.push(pattern.into());
+        }
+        if let Some(id) = workspace_id {
+            sql.push_str(" AND c.workspace_id = ?");
+            values.push(id.into());
+        }
+        sql.push_str(" ORDER BY m.created_at DESC LIMIT ?");
+        values.push((limit as i64).into());
+
+        let rows = MessageSearchRow::find_by_statement(Statement::from_sql_and_values(DbBackend::Sqlite, sql, values))
+            .all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(rows)
+    }
+
+    /// Conversions whose name contains every term, most recently updated first
+    pub async fn search_names(
+        db: &DatabaseConnection,
+        terms: &[String],
+        workspace_id: Option<i32>,
+        limit: u64,
+    ) -> AppResult<Vec<ConversionModel>> {
+        let mut condition = Condition::all().add(conversion::Column::Name.is_not_null());
+        for term in terms {
+            condition =
+                condition.add(Expr::col(conversion::Column::Name).like(LikeExpr::new(like_pattern(term)).escape('\\')));
+        }
+        if let Some(id) = workspace_id {
+            condition = condition.add(conversion::Column::WorkspaceId.eq(id));
+        }
+
+        let conversions = Conversion::find()
+            .filter(condition)
+            .order_by(conversion::Column::UpdatedAt, Order::Desc)
+            .limit(limit)
+            .all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(conversions)
+    }
+
+    async fn insert_messages(
+        txn: &DatabaseTransaction,
+        conversion_pk: i32,
+        start: i32,
+        messages: &[MessageRecord],
+    ) -> AppResult<()> {
+        // Chunked to stay under SQLite's bound-parameter limit
+        for (chunk_index, chunk) in messages.chunks(INSERT_CHUNK_SIZE).enumerate() {
+            let offset = start + (chunk_index * INSERT_CHUNK_SIZE) as i32;
+            let rows = chunk.iter().enumerate().map(|(i, m)| conversion_message::ActiveModel {
+                conversion_id: Set(conversion_pk),
+                message_id: Set(m.message_id.clone()),
+                position: Set(offset + i as i32),
+                parent_id: Set(m.parent_id.clone()),
+                role: Set(m.role.clone()),
+                content: Set(m.content.clone()),
+                files: Set(m.files.clone()),
+                model: Set(m.model.clone()),
+                code_cli: Set(m.code_cli.clone()),
+                code_cli_task_id: Set(m.code_cli_task_id.clone()),
+                created_at: Set(m.created_at),
+                ..Default::default()
+            });
+            ConversionMessage::insert_many(rows)
+                .exec(txn)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        }
+
+        if messages.iter().any(|m| m.run.is_some()) {
+            Self::insert_runs(txn, conversion_pk, start, messages).await?;
+        }
+        Ok(())
+    }
+
+    /// Runs of messages just inserted at positions `start..`
+    async fn insert_runs(
+        txn: &DatabaseTransaction,
+        conversion_pk: i32,
+        start: i32,
+        messages: &[MessageRecord],
+    ) -> AppResult<()> {
+        let ids: HashMap<i32, i32> = ConversionMessage::find()
+            .select_only()
+            .column(conversion_message::Column::Position)
+            .column(conversion_message::Column::Id)
+            .filter(conversion_message::Column::ConversionId.eq(conversion_pk))
+            .filter(conversion_message::Column::Position.gte(start))
+            .into_tuple::<(i32, i32)>()
+            .all(txn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .into_iter()
+            .collect();
+
+        let runs: Vec<message_run::ActiveModel> = messages
+            .iter()
+            .enumerate()
+            .filter_map(|(i, m)| {
+                let run = m.run.as_ref()?;
+                let message_pk = *ids.get(&(start + i as i32))?;
+                Some(message_run::ActiveModel {
+                    message_id: Set(message_pk),
+                    backend: Set(run.backend.clone()),
+                    model: Set(run.model.clone()),
+                    workdir: Set(run.workdir.clone()),
+                    duration_ms: Set(run.duration_ms),
+                    exit_code: Set(run.exit_code),
+                    stderr_tail: Set(run.stderr_tail.clone()),
+                    input_tokens: Set(run.input_tokens),
+                    output_tokens: Set(run.output_tokens),
+                    cancelled: Set(run.cancelled),
+                    error: Set(run.error.clone()),
+                    created_at: Set(m.created_at),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        for chunk in runs.chunks(INSERT_CHUNK_SIZE) {
+            MessageRun::insert_many(chunk.to_vec())
+                .exec(txn)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Delete the messages of a conversion and their runs.
+    ///
+    /// Explicit so it does not depend on SQLite foreign key enforcement.
+    async fn delete_messages(txn: &DatabaseTransaction, conversion_pk: i32) -> AppResult<()> {
+        MessageRun::delete_many()
+            .filter(
+                message_run::Column::MessageId.in_subquery(
+                    Query::select()
+                        .column(conversion_message::Column::Id)
+                        .from(ConversionMessage)
+                        .and_where(conversion_message::Column::ConversionId.eq(conversion_pk))
+                        .to_owned(),
+                ),
+            )
+            .exec(txn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        ConversionMessage::delete_many()
+            .filter(conversion_message::Column::ConversionId.eq(conversion_pk))
+            .exec(txn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// `%term%` with LIKE wildcards in `term` escaped by a backslash
+fn like_pattern(term: &str) -> String {
+    let escaped = term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// First 100 characters of a message, with `...` when cut
+fn preview(content: &str) -> String {
+    let mut chars = content.chars();
+    let preview: String = chars.by_ref().take(100).collect();
+    if chars.next().is_some() {
+        format!("{}...", preview)
+    } else {
+        preview
+    }
+}
@@ -0,0 +1,77 @@
+//! Message run repository
+//!
+//! Aggregates over `message_run`; the runs themselves are written together with
+//! their messages by `ConversionRepository`.
+
+use crate::utils::error::{AppError, AppResult};
+use chrono::{DateTime, Utc};
+use sea_orm::*;
+
+/// Runs of one day, workspace, backend and model
+#[derive(Debug, Clone, FromQueryResult)]
+pub struct RunUsageRow {
+    /// Local date, `YYYY-MM-DD`
+    pub day: String,
+    pub workspace_id: Option<i32>,
+    pub workspace_name: Option<String>,
+    pub backend: String,
+    pub model: Option<String>,
+    pub requests: i64,
+    pub failed: i64,
+    pub cancelled: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub duration_ms: i64,
+}
+
+/// Message run repository
+pub struct MessageRunRepository;
+
+impl MessageRunRepository {
+    /// Run totals grouped by local day, workspace, backend and model, newest day first
+    pub async fn usage(
+        db: &DatabaseConnection,
+        workspace_id: Option<i32>,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> AppResult<Vec<RunUsageRow>> {
+        let mut sql = String::from(
+            "SELECT date(r.created_at, 'localtime') AS day, c.workspace_id, w.name AS workspace_name, \
+                    r.backend, r.model, COUNT(*) AS requests, \
+                    SUM(CASE WHEN r.error IS NOT NULL THEN 1 ELSE 0 END) AS failed, \
+                    SUM(CASE WHEN r.cancelled THEN 1 ELSE 0 END) AS cancelled, \
+                    COALESCE(SUM(r.input_tokens), 0) AS input_tokens, \
+                    COALESCE(SUM(r.output_tokens), 0) AS output_tokens, \
+                    COALESCE(SUM(r.duration_ms), 0) AS duration_ms \
+             FROM message_run r \
+             JOIN conversion_message m ON m.id = r.message_id \
+             JOIN conversion c ON c.id = m.conversion_id \
+             LEFT JOIN workspace w ON w.id = c.workspace_id \
+             WHERE 1 = 1",
+        );
+        let mut values: Vec<Value> = Vec::new();
+        if let Some(id) = workspace_id {
+            sql.push_str(" AND c.workspace_id = ?");
+            values.push(id.into());
+        }
+        if let Some(since) = since {
+            sql.push_str(" AND r.created_at >= ?");
+            values.push(since.into());
+        }
+        if let Some(until) = until {
+            sql.push_str(" AND r.created_at < ?");
+            values.push(until.into());
+        }
+        sql.push_str(
+            " GROUP BY day, c.workspace_id, r.backend, r.model \
+             ORDER BY day DESC, duration_ms DESC",
+        );
+
+        let rows = RunUsageRow::find_by_statement(Statement::from_sql_and_values(DbBackend::Sqlite, sql, values))
+            .all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(rows)
+    }
+}
@@ -1,5 +1,9 @@
 //! Database repositories module
 
-pub mod settings_repository;
+pub mod command_history_repository;
+pub mod conversion_repository;
+pub mod message_run_repository;
 pub mod recent_directories_repository;
+pub mod settings_repository;
+pub mod terminal_session_repository;
 pub mod workspace_repository;
@@ -0,0 +1,85 @@
+//! Saved terminal session repository
+
+use chrono::Utc;
+use sea_orm::*;
+
+use crate::database::models::terminal_session::{
+    self, ActiveModel as TerminalSessionActiveModel, Entity as TerminalSession, Model as TerminalSessionModel,
+};
+use crate::utils::error::{AppError, AppResult};
+
+/// A terminal session to save
+#[derive(Debug, Clone)]
+pub struct TerminalSessionRecord {
+    pub id: String,
+    pub workspace_id: Option<i32>,
+    pub name: String,
+    pub cwd: String,
+    pub shell: Option<String>,
+    pub scrollback: String,
+    pub position: i32,
+}
+
+/// Saved terminal session repository
+pub struct TerminalSessionRepository;
+
+impl TerminalSessionRepository {
+    /// Save sessions, replacing earlier saves with the same ids
+    pub async fn save_all(db: &DatabaseConnection, records: Vec<TerminalSessionRecord>) -> AppResult<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let txn = db.begin().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let ids: Vec<String> = records.iter().map(|record| record.id.clone()).collect();
+        TerminalSession::delete_many()
+            .filter(terminal_session::Column::Id.is_in(ids))
+            .exec(&txn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let now = Utc::now();
+        let models = records.into_iter().map(|record| TerminalSessionActiveModel {
+            id: Set(record.id),
+            workspace_id: Set(record.workspace_id),
+            name: Set(record.name),
+            cwd: Set(record.cwd),
+            shell: Set(record.shell),
+            scrollback: Set(record.scrollback),
+            position: Set(record.position),
+            updated_at: Set(now),
+        });
+        TerminalSession::insert_many(models)
+            .exec(&txn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        txn.commit().await.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Sessions saved for a workspace, in tab order
+    pub async fn find_by_workspace(
+        db: &DatabaseConnection,
+        workspace_id: Option<i32>,
+    ) -> AppResult<Vec<TerminalSessionModel>> {
+        let condition = match workspace_id {
+            Some(id) => terminal_session::Column::WorkspaceId.eq(id),
+            None => terminal_session::Column::WorkspaceId.is_null(),
+        };
+        TerminalSession::find()
+            .filter(condition)
+            .order_by_asc(terminal_session::Column::Position)
+            .all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// Delete a saved session once it has been restored
+    pub async fn delete(db: &DatabaseConnection, id: &str) -> AppResult<()> {
+        TerminalSession::delete_by_id(id.to_string())
+            .exec(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+}
@@ -62,14 +62,25 @@ fn main() {
             tauri_module::commands::save_clipboard_image,
             tauri_module::commands::execute_command,
             tauri_module::commands::execute_terminal_command,
+            tauri_module::commands::cancel_command,
+            tauri_module::commands::search_command_history,
+            tauri_module::commands::rerun_command_history,
             tauri_module::commands::spawn_terminal,
             tauri_module::commands::kill_terminal,
+            tauri_module::commands::write_terminal,
+            tauri_module::commands::resize_terminal,
+            tauri_module::commands::get_terminal_state,
+            tauri_module::commands::list_terminals,
+            tauri_module::commands::get_terminal_scrollback,
+            tauri_module::commands::restore_terminals,
             tauri_module::settings_commands::get_settings,
             tauri_module::settings_commands::save_settings,
             tauri_module::settings_commands::reset_settings,
             tauri_module::settings_commands::get_setting,
             tauri_module::settings_commands::save_setting,
             tauri_module::settings_commands::get_settings_by_category,
+            tauri_module::settings_commands::get_environment_variables,
+            tauri_module::settings_commands::save_environment_variables,
             tauri_module::commands::add_recent_directory,
             tauri_module::commands::get_recent_directories,
             tauri_module::commands::clear_recent_directories,
@@ -84,9 +95,36 @@ fn main() {
             tauri_module::commands::clear_logs,
             tauri_module::chat_session_commands::save_chat_session,
             tauri_module::chat_session_commands::load_chat_sessions,
+            tauri_module::chat_session_commands::load_chat_session,
             tauri_module::chat_session_commands::delete_chat_session,
             tauri_module::chat_session_commands::update_chat_session_name,
+            tauri_module::chat_session_commands::set_chat_session_tags,
+            tauri_module::chat_session_commands::set_chat_session_pinned,
+            tauri_module::chat_session_commands::set_chat_session_archived,
+            tauri_module::chat_session_commands::get_chat_retention_policy,
+            tauri_module::chat_session_commands::set_chat_retention_policy,
+            tauri_module::chat_session_commands::list_chat_branches,
+            tauri_module::chat_session_commands::switch_chat_branch,
+            tauri_module::chat_session_commands::search_chat_sessions,
+            tauri_module::chat_session_commands::export_chat_session,
+            tauri_module::chat_session_commands::export_workspace_chat_sessions,
+            tauri_module::chat_session_commands::import_chat_sessions,
             tauri_module::notification_commands::show_system_notification,
+            tauri_module::ai_commands::get_ai_models,
+            tauri_module::ai_commands::get_current_ai_model,
+            tauri_module::ai_commands::set_ai_model,
+            tauri_module::ai_commands::add_ai_model,
+            tauri_module::ai_commands::update_ai_model,
+            tauri_module::ai_commands::remove_ai_model,
+            tauri_module::ai_commands::discover_ai_models,
+            tauri_module::ai_commands::preview_chat_context,
+            tauri_module::ai_commands::get_usage_report,
+            tauri_module::ai_commands::get_model_prices,
+            tauri_module::ai_commands::set_model_prices,
+            tauri_module::secret_commands::set_secret,
+            tauri_module::secret_commands::get_secret,
+            tauri_module::secret_commands::delete_secret,
+            tauri_module::secret_commands::list_secrets,
         ])
 
         // Setup application state
@@ -119,7 +157,13 @@ fn main() {
             Ok(())
         })
 
-        // Run the application
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        // Build and run the application
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                // Reopened for their workspace on the next launch
+                core::app::save_terminal_sessions(app_handle);
+            }
+        });
 }
@@ -0,0 +1,216 @@
+//! Migration: Store chat sessions in the conversion table
+//!
+//! The original `conversion` table was never written to, so it is recreated with
+//! the columns a chat session needs, next to a `conversion_message` table.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Conversion::Table).if_exists().to_owned())
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Conversion::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Conversion::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Conversion::ConversionId)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Conversion::Name).string().null())
+                    .col(ColumnDef::new(Conversion::WorkspaceId).integer().null())
+                    .col(ColumnDef::new(Conversion::SessionId).string().null())
+                    .col(
+                        ColumnDef::new(Conversion::CodeCliTaskIds)
+                            .text()
+                            .not_null()
+                            .default("{}"),
+                    )
+                    .col(ColumnDef::new(Conversion::MessageCount).integer().not_null().default(0))
+                    .col(
+                        ColumnDef::new(Conversion::FirstMessagePreview)
+                            .string()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(ColumnDef::new(Conversion::Description).string().null())
+                    .col(ColumnDef::new(Conversion::CreatedAt).timestamp().not_null())
+                    .col(ColumnDef::new(Conversion::UpdatedAt).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_conversion_workspace")
+                            .from(Conversion::Table, Conversion::WorkspaceId)
+                            .to(Workspace::Table, Workspace::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Session lists are filtered by workspace and ordered by last update
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_conversion_workspace_updated")
+                    .table(Conversion::Table)
+                    .col(Conversion::WorkspaceId)
+                    .col(Conversion::UpdatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ConversionMessage::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ConversionMessage::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ConversionMessage::ConversionId).integer().not_null())
+                    .col(ColumnDef::new(ConversionMessage::MessageId).string().not_null())
+                    .col(ColumnDef::new(ConversionMessage::Position).integer().not_null())
+                    .col(ColumnDef::new(ConversionMessage::Role).string().not_null())
+                    .col(ColumnDef::new(ConversionMessage::Content).text().not_null())
+                    .col(ColumnDef::new(ConversionMessage::Files).text().null())
+                    .col(ColumnDef::new(ConversionMessage::Model).string().null())
+                    .col(ColumnDef::new(ConversionMessage::CreatedAt).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_conversion_message_conversion")
+                            .from(ConversionMessage::Table, ConversionMessage::ConversionId)
+                            .to(Conversion::Table, Conversion::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_conversion_message_position")
+                    .table(ConversionMessage::Table)
+                    .col(ConversionMessage::ConversionId)
+                    .col(ConversionMessage::Position)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_conversion_message_message_id")
+                    .table(ConversionMessage::Table)
+                    .col(ConversionMessage::MessageId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ConversionMessage::Table).if_exists().to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Conversion::Table).if_exists().to_owned())
+            .await?;
+
+        // Restore the original (unused) conversion table
+        manager
+            .create_table(
+                Table::create()
+                    .table(Conversion::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Conversion::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Conversion::Name).string().not_null())
+                    .col(ColumnDef::new(Conversion::ConversionId).string().not_null())
+                    .col(ColumnDef::new(Conversion::Description).string().null().default(""))
+                    .col(ColumnDef::new(Conversion::CreatedAt).timestamp().not_null())
+                    .col(ColumnDef::new(Conversion::UpdatedAt).timestamp().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_conversion_id")
+                    .table(Conversion::Table)
+                    .col(Conversion::ConversionId)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+/// Define the table and column identifiers
+#[derive(DeriveIden)]
+enum Conversion {
+    Table,
+    Id,
+    ConversionId,
+    Name,
+    WorkspaceId,
+    SessionId,
+    CodeCliTaskIds,
+    MessageCount,
+    FirstMessagePreview,
+    Description,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum ConversionMessage {
+    Table,
+    Id,
+    ConversionId,
+    MessageId,
+    Position,
+    Role,
+    Content,
+    Files,
+    Model,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Workspace {
+    Table,
+    Id,
+}
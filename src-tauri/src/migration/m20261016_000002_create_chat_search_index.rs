@@ -0,0 +1,71 @@
+//! Migration: Full-text index over chat messages
+//!
+//! An external-content FTS5 table over `conversion_message.content` and `files`,
+//! kept in sync by triggers. The trigram tokenizer matches substrings, which also
+//! works for CJK text that has no word boundaries.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS conversion_message_fts USING fts5(
+                content, files,
+                content='conversion_message', content_rowid='id',
+                tokenize='trigram'
+            )",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE TRIGGER IF NOT EXISTS conversion_message_fts_ai AFTER INSERT ON conversion_message BEGIN
+                INSERT INTO conversion_message_fts(rowid, content, files) VALUES (new.id, new.content, new.files);
+            END",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE TRIGGER IF NOT EXISTS conversion_message_fts_ad AFTER DELETE ON conversion_message BEGIN
+                INSERT INTO conversion_message_fts(conversion_message_fts, rowid, content, files)
+                VALUES ('delete', old.id, old.content, old.files);
+            END",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE TRIGGER IF NOT EXISTS conversion_message_fts_au AFTER UPDATE ON conversion_message BEGIN
+                INSERT INTO conversion_message_fts(conversion_message_fts, rowid, content, files)
+                VALUES ('delete', old.id, old.content, old.files);
+                INSERT INTO conversion_message_fts(rowid, content, files) VALUES (new.id, new.content, new.files);
+            END",
+        )
+        .await?;
+
+        // Index messages that already exist
+        db.execute_unprepared("INSERT INTO conversion_message_fts(conversion_message_fts) VALUES ('rebuild')")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared("DROP TRIGGER IF EXISTS conversion_message_fts_au")
+            .await?;
+        db.execute_unprepared("DROP TRIGGER IF EXISTS conversion_message_fts_ad")
+            .await?;
+        db.execute_unprepared("DROP TRIGGER IF EXISTS conversion_message_fts_ai")
+            .await?;
+        db.execute_unprepared("DROP TABLE IF EXISTS conversion_message_fts")
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+//! Migration: Conversation branches
+//!
+//! Messages get a parent pointer so a session becomes a tree, and remember the
+//! code CLI task id they were produced with so each branch resumes the right CLI
+//! session. `conversion.active_leaf_id` selects the branch shown in the UI.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports one column per ALTER TABLE
+        for column in [
+            ColumnDef::new(ConversionMessage::ParentId).string().null().to_owned(),
+            ColumnDef::new(ConversionMessage::CodeCli).string().null().to_owned(),
+            ColumnDef::new(ConversionMessage::CodeCliTaskId)
+                .string()
+                .null()
+                .to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ConversionMessage::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Conversion::Table)
+                    .add_column(ColumnDef::new(Conversion::ActiveLeafId).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_conversion_message_parent")
+                    .table(ConversionMessage::Table)
+                    .col(ConversionMessage::ConversionId)
+                    .col(ConversionMessage::ParentId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing sessions are linear: each message follows the previous one
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "UPDATE conversion_message SET parent_id = (
+                SELECT p.message_id FROM conversion_message p
+                WHERE p.conversion_id = conversion_message.conversion_id
+                  AND p.position = conversion_message.position - 1
+            )",
+        )
+        .await?;
+        db.execute_unprepared(
+            "UPDATE conversion SET active_leaf_id = (
+                SELECT m.message_id FROM conversion_message m
+                WHERE m.conversion_id = conversion.id
+                ORDER BY m.position DESC LIMIT 1
+            )",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_conversion_message_parent")
+                    .table(ConversionMessage::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Conversion::Table)
+                    .drop_column(Conversion::ActiveLeafId)
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in [
+            ConversionMessage::CodeCliTaskId,
+            ConversionMessage::CodeCli,
+            ConversionMessage::ParentId,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ConversionMessage::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Define the table and column identifiers
+#[derive(DeriveIden)]
+enum Conversion {
+    Table,
+    ActiveLeafId,
+}
+
+#[derive(DeriveIden)]
+enum ConversionMessage {
+    Table,
+    ConversionId,
+    ParentId,
+    CodeCli,
+    CodeCliTaskId,
+}
@@ -0,0 +1,100 @@
+//! Migration: Run metadata of assistant messages
+//!
+//! One row per AI request: backend, model, workdir, duration, exit code, stderr
+//! tail, token usage and the error of failed runs, so slow or failing runs can be
+//! inspected afterwards and aggregated.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MessageRun::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MessageRun::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MessageRun::MessageId).integer().not_null().unique_key())
+                    .col(ColumnDef::new(MessageRun::Backend).string().not_null())
+                    .col(ColumnDef::new(MessageRun::Model).string().null())
+                    .col(ColumnDef::new(MessageRun::Workdir).string().null())
+                    .col(ColumnDef::new(MessageRun::DurationMs).big_integer().not_null())
+                    .col(ColumnDef::new(MessageRun::ExitCode).integer().null())
+                    .col(ColumnDef::new(MessageRun::StderrTail).text().null())
+                    .col(ColumnDef::new(MessageRun::InputTokens).big_integer().null())
+                    .col(ColumnDef::new(MessageRun::OutputTokens).big_integer().null())
+                    .col(
+                        ColumnDef::new(MessageRun::Cancelled)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(MessageRun::Error).text().null())
+                    .col(ColumnDef::new(MessageRun::CreatedAt).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_message_run_message")
+                            .from(MessageRun::Table, MessageRun::MessageId)
+                            .to(ConversionMessage::Table, ConversionMessage::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_message_run_created")
+                    .table(MessageRun::Table)
+                    .col(MessageRun::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MessageRun::Table).if_exists().to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+/// Define the table and column identifiers
+#[derive(DeriveIden)]
+enum MessageRun {
+    Table,
+    Id,
+    MessageId,
+    Backend,
+    Model,
+    Workdir,
+    DurationMs,
+    ExitCode,
+    StderrTail,
+    InputTokens,
+    OutputTokens,
+    Cancelled,
+    Error,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum ConversionMessage {
+    Table,
+    Id,
+}
@@ -0,0 +1,77 @@
+//! Migration: Session tags, pinning and archiving
+//!
+//! `conversion.tags` holds a JSON array of tag names. Archived sessions keep
+//! their messages but are hidden from the session list; the retention policy
+//! archives and later deletes sessions based on `updated_at` and `archived_at`.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports one column per ALTER TABLE
+        for column in [
+            ColumnDef::new(Conversion::Tags)
+                .text()
+                .not_null()
+                .default("[]")
+                .to_owned(),
+            ColumnDef::new(Conversion::Pinned)
+                .boolean()
+                .not_null()
+                .default(false)
+                .to_owned(),
+            ColumnDef::new(Conversion::ArchivedAt).timestamp().null().to_owned(),
+        ] {
+            manager
+                .alter_table(Table::alter().table(Conversion::Table).add_column(column).to_owned())
+                .await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_conversion_archived")
+                    .table(Conversion::Table)
+                    .col(Conversion::WorkspaceId)
+                    .col(Conversion::ArchivedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_conversion_archived")
+                    .table(Conversion::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in [Conversion::ArchivedAt, Conversion::Pinned, Conversion::Tags] {
+            manager
+                .alter_table(Table::alter().table(Conversion::Table).drop_column(column).to_owned())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Define the table and column identifiers
+#[derive(DeriveIden)]
+enum Conversion {
+    Table,
+    WorkspaceId,
+    Tags,
+    Pinned,
+    ArchivedAt,
+}
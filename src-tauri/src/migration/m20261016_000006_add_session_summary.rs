@@ -0,0 +1,45 @@
+//! Migration: Rolling session summaries
+//!
+//! `conversion.summary` condenses the messages from the start of a branch up to
+//! and including `summary_through_id`; it stands in for those messages when a
+//! session continues without the code CLI's own memory of them.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports one column per ALTER TABLE
+        for column in [
+            ColumnDef::new(Conversion::Summary).text().null().to_owned(),
+            ColumnDef::new(Conversion::SummaryThroughId).string().null().to_owned(),
+        ] {
+            manager
+                .alter_table(Table::alter().table(Conversion::Table).add_column(column).to_owned())
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [Conversion::SummaryThroughId, Conversion::Summary] {
+            manager
+                .alter_table(Table::alter().table(Conversion::Table).drop_column(column).to_owned())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Define the table and column identifiers
+#[derive(DeriveIden)]
+enum Conversion {
+    Table,
+    Summary,
+    SummaryThroughId,
+}
@@ -0,0 +1,89 @@
+//! Migration: Saved terminal sessions
+//!
+//! Terminal tabs open when the app quits, with the tail of their scrollback, so
+//! they can be reopened for their workspace on the next launch.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TerminalSession::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(TerminalSession::Id).string().not_null().primary_key())
+                    .col(ColumnDef::new(TerminalSession::WorkspaceId).integer().null())
+                    .col(ColumnDef::new(TerminalSession::Name).string().not_null())
+                    .col(ColumnDef::new(TerminalSession::Cwd).string().not_null())
+                    .col(ColumnDef::new(TerminalSession::Shell).string().null())
+                    .col(
+                        ColumnDef::new(TerminalSession::Scrollback)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(TerminalSession::Position)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(TerminalSession::UpdatedAt).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_terminal_session_workspace")
+                            .from(TerminalSession::Table, TerminalSession::WorkspaceId)
+                            .to(Workspace::Table, Workspace::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_terminal_session_workspace")
+                    .table(TerminalSession::Table)
+                    .col(TerminalSession::WorkspaceId)
+                    .col(TerminalSession::Position)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TerminalSession::Table).if_exists().to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+/// Define the table and column identifiers
+#[derive(DeriveIden)]
+enum TerminalSession {
+    Table,
+    Id,
+    WorkspaceId,
+    Name,
+    Cwd,
+    Shell,
+    Scrollback,
+    Position,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Workspace {
+    Table,
+    Id,
+}
@@ -0,0 +1,85 @@
+//! Migration: Shell command history
+//!
+//! One row per command run through `execute_terminal_command`, keyed by the
+//! workspace of its terminal. `duration_ms` stays null while the command runs.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CommandHistory::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CommandHistory::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(CommandHistory::WorkspaceId).integer().null())
+                    .col(ColumnDef::new(CommandHistory::Command).text().not_null())
+                    .col(ColumnDef::new(CommandHistory::Shell).string().not_null())
+                    .col(ColumnDef::new(CommandHistory::Cwd).string().not_null())
+                    .col(ColumnDef::new(CommandHistory::ExitCode).integer().null())
+                    .col(ColumnDef::new(CommandHistory::DurationMs).big_integer().null())
+                    .col(ColumnDef::new(CommandHistory::StartedAt).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_command_history_workspace")
+                            .from(CommandHistory::Table, CommandHistory::WorkspaceId)
+                            .to(Workspace::Table, Workspace::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_command_history_workspace")
+                    .table(CommandHistory::Table)
+                    .col(CommandHistory::WorkspaceId)
+                    .col(CommandHistory::StartedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CommandHistory::Table).if_exists().to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+/// Define the table and column identifiers
+#[derive(DeriveIden)]
+enum CommandHistory {
+    Table,
+    Id,
+    WorkspaceId,
+    Command,
+    Shell,
+    Cwd,
+    ExitCode,
+    DurationMs,
+    StartedAt,
+}
+
+#[derive(DeriveIden)]
+enum Workspace {
+    Table,
+    Id,
+}
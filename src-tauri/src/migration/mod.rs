@@ -5,6 +5,14 @@ use sea_orm_migration::prelude::*;
 mod m20250101_000001_create_settings_table;
 mod m20251219_132921_create_workspace_table;
 mod m20251221_101715_create_conversion_table;
+mod m20261016_000001_create_chat_session_tables;
+mod m20261016_000002_create_chat_search_index;
+mod m20261016_000003_add_message_branches;
+mod m20261016_000004_create_message_run_table;
+mod m20261016_000005_add_session_lifecycle;
+mod m20261016_000006_add_session_summary;
+mod m20261016_000007_create_terminal_session_table;
+mod m20261016_000008_create_command_history_table;
 
 
 pub struct Migrator;
@@ -16,6 +24,14 @@ impl MigratorTrait for Migrator {
             Box::new(m20250101_000001_create_settings_table::Migration),
             Box::new(m20251219_132921_create_workspace_table::Migration),
             Box::new(m20251221_101715_create_conversion_table::Migration),
+            Box::new(m20261016_000001_create_chat_session_tables::Migration),
+            Box::new(m20261016_000002_create_chat_search_index::Migration),
+            Box::new(m20261016_000003_add_message_branches::Migration),
+            Box::new(m20261016_000004_create_message_run_table::Migration),
+            Box::new(m20261016_000005_add_session_lifecycle::Migration),
+            Box::new(m20261016_000006_add_session_summary::Migration),
+            Box::new(m20261016_000007_create_terminal_session_table::Migration),
+            Box::new(m20261016_000008_create_command_history_table::Migration),
         ]
     }
 }
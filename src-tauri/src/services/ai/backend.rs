@@ -0,0 +1,69 @@
+//! Chat backend abstraction
+//!
+//! Every way of talking to a model (codeagent-wrapper, direct provider HTTP APIs)
+//! implements `ChatBackend`; `AiService` picks one per `AiModel`.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use super::{AiChatOptions, AiMessageResult};
+use crate::utils::error::AppResult;
+
+/// Callback receiving each text delta as it is produced
+pub type DeltaSink<'a> = dyn FnMut(&str) + Send + 'a;
+
+/// A previous turn of the conversation (used by stateless HTTP backends)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatTurn {
+    /// `user` or `assistant`
+    pub role: String,
+    /// Message text
+    pub content: String,
+}
+
+/// System-style instruction carrying the summary of earlier turns
+pub fn summary_instruction(summary: &str) -> String {
+    format!("Summary of the earlier conversation:\n\n{}", summary.trim())
+}
+
+/// Request handed to a chat backend
+#[derive(Debug, Clone)]
+pub struct ChatRequest {
+    /// User message with attached context already inlined
+    pub prompt: String,
+    /// Per-request options (workdir, resume id, history, env, cancellation)
+    pub options: AiChatOptions,
+}
+
+/// A chat backend able to stream a response
+#[async_trait]
+pub trait ChatBackend: Send + Sync {
+    /// Short identifier used in logs (e.g. `codeagent`, `anthropic`)
+    fn name(&self) -> &'static str;
+
+    /// Label recorded with each run; backends serving several tools add which one
+    fn label(&self) -> String {
+        self.name().to_string()
+    }
+
+    /// Send the request, calling `on_delta` for every text chunk as it arrives.
+    ///
+    /// Backends must honour `request.options.cancel` and return the partial output
+    /// with `cancelled: true` instead of an error.
+    async fn send(&self, request: ChatRequest, on_delta: &mut DeltaSink<'_>) -> AppResult<AiMessageResult>;
+}
+
+pub(super) fn is_cancel_requested(cancel: &Option<watch::Receiver<bool>>) -> bool {
+    cancel.as_ref().map(|rx| *rx.borrow()).unwrap_or(false)
+}
+
+/// Resolve once cancellation is requested; never resolves without a signal.
+pub(crate) async fn wait_for_cancel(cancel: &mut Option<watch::Receiver<bool>>) {
+    if let Some(rx) = cancel.as_mut() {
+        if rx.wait_for(|requested| *requested).await.is_ok() {
+            return;
+        }
+    }
+    std::future::pending::<()>().await
+}
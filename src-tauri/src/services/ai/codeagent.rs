@@ -0,0 +1,648 @@
+//! codeagent-wrapper chat backend
+//!
+//! Runs the myclaude `codeagent-wrapper` CLI (which in turn drives claude/codex/gemini)
+//! and streams its stdout back as deltas.
+
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
+
+use super::backend::{
+    ChatBackend, ChatRequest, ChatTurn, DeltaSink, is_cancel_requested, summary_instruction, wait_for_cancel,
+};
+use super::{AiMessageResult, CodeagentWrapperConfig, RunMetadata};
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::process::kill_process_tree;
+
+/// Characters of codeagent-wrapper's stderr kept with each run
+const STDERR_TAIL_CHARS: usize = 2000;
+
+/// Chat backend that shells out to codeagent-wrapper
+#[derive(Debug, Clone)]
+pub struct CodeagentBackend {
+    /// Wrapper configuration (binary path, permissions, timeouts)
+    config: CodeagentWrapperConfig,
+    /// Wrapper backend name: `codex` | `claude` | `gemini`
+    backend: String,
+}
+
+impl CodeagentBackend {
+    pub fn new(config: CodeagentWrapperConfig, backend: String) -> Self {
+        Self { config, backend }
+    }
+
+    fn is_executable_file(path: &Path) -> bool {
+        if !path.is_file() {
+            return false;
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            return path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.eq_ignore_ascii_case("exe"))
+                .unwrap_or(false);
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if let Ok(meta) = std::fs::metadata(path) {
+                return (meta.permissions().mode() & 0o111) != 0;
+            }
+            false
+        }
+    }
+
+    fn which_in_path(program: &str) -> Option<PathBuf> {
+        let path_var = std::env::var_os("PATH")?;
+        for dir in std::env::split_paths(&path_var) {
+            let candidate = dir.join(program);
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
+        None
+    }
+
+    fn find_codeagent_wrapper(explicit_path: Option<String>) -> AppResult<PathBuf> {
+        if let Some(p) = explicit_path {
+            let pb = PathBuf::from(p);
+            if pb.exists() {
+                return Ok(pb);
+            }
+            return Err(AppError::AiServiceError(
+                "指定的 codeagent-wrapper 路径不存在".to_string(),
+            ));
+        }
+
+        #[cfg(target_os = "windows")]
+        let program_name = "codeagent-wrapper.exe";
+        #[cfg(not(target_os = "windows"))]
+        let program_name = "codeagent-wrapper";
+
+        if let Some(p) = Self::which_in_path(program_name) {
+            return Ok(p);
+        }
+
+        if let Some(home) = dirs::home_dir() {
+            // myclaude README: Windows installer places it under %USERPROFILE%\bin
+            let candidates = [
+                home.join("bin").join(program_name),
+                home.join(".claude").join("bin").join(program_name),
+            ];
+            for c in candidates {
+                if c.exists() {
+                    return Ok(c);
+                }
+            }
+        }
+
+        // Dev convenience: `pnpm fetch:codeagent-wrapper` downloads to `src-tauri/bin/`.
+        let dev_candidate = Path::new(env!("CARGO_MANIFEST_DIR")).join("bin").join(program_name);
+        if dev_candidate.exists() {
+            return Ok(dev_candidate);
+        }
+
+        Err(AppError::AiServiceError(
+            "未找到 codeagent-wrapper。请先安装 myclaude（或将 codeagent-wrapper 放入 PATH / $HOME/bin / $HOME/.claude/bin），或在配置中设置 binary_path。\n\n开发模式可运行：pnpm fetch:codeagent-wrapper".to_string(),
+        ))
+    }
+
+    async fn run_codeagent_wrapper(
+        spec: CodeagentRunSpec,
+        on_delta: &mut DeltaSink<'_>,
+    ) -> AppResult<CodeagentRunResult> {
+        let bin = Self::find_codeagent_wrapper(spec.binary_path)?;
+        if !Self::is_executable_file(&bin) {
+            return Err(AppError::AiServiceError(format!(
+                "codeagent-wrapper 不是可执行文件: {}",
+                bin.display()
+            )));
+        }
+
+        let mut args: Vec<String> = Vec::new();
+        if !spec.backend.trim().is_empty() {
+            args.push("--backend".to_string());
+            args.push(spec.backend.trim().to_string());
+        }
+
+        if spec.parallel {
+            args.push("--parallel".to_string());
+        }
+
+        if spec.skip_permissions {
+            // Wrapper supports both --skip-permissions and --dangerously-skip-permissions.
+            args.push("--dangerously-skip-permissions".to_string());
+        }
+
+        if let Some(resume) = spec.resume_session_id.as_deref() {
+            let resume = resume.trim();
+            if !resume.is_empty() && !spec.code_cli_changed.unwrap_or(false) {
+                args.push("resume".to_string());
+                args.push(resume.to_string());
+            }
+        }
+        if spec.parallel {
+            // Parallel mode reads task config from stdin and forbids extra args.
+        } else {
+            // `-` makes the wrapper read the task from stdin: no quoting issues and no
+            // argv size limit for long prompts.
+            args.push("-".to_string());
+            args.push(spec.workdir.clone());
+        }
+
+        let mut cmd = Command::new(&bin);
+        cmd.args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(&spec.workdir)
+            .kill_on_drop(true);
+
+        // Put the wrapper in its own process group so cancellation can reach the
+        // claude/codex/gemini CLIs it spawns, not just the wrapper itself.
+        #[cfg(unix)]
+        cmd.process_group(0);
+
+        if let Some(timeout_ms) = spec.timeout_ms {
+            // myclaude wrapper reads CODEX_TIMEOUT in milliseconds.
+            cmd.env("CODEX_TIMEOUT", timeout_ms.to_string());
+        }
+        if spec.skip_permissions {
+            // Also enable via env for wrapper default.
+            cmd.env("CODEAGENT_SKIP_PERMISSIONS", "1");
+        }
+
+        if let Some(max_workers) = spec.max_parallel_workers {
+            cmd.env("CODEAGENT_MAX_PARALLEL_WORKERS", max_workers.to_string());
+        }
+
+        // Upstream wrapper currently does not accept `--model` for codex.
+        // We pass a best-effort hint via env for codex CLI implementations.
+        if spec.backend.trim().eq_ignore_ascii_case("codex") {
+            if let Some(m) = spec.codex_model.as_deref() {
+                let m = m.trim();
+                if !m.is_empty() {
+                    cmd.env("CODEX_MODEL", m);
+                }
+            }
+        }
+
+        debug!(
+            codeagent_bin = %bin.display(),
+            workdir = %spec.workdir,
+            backend = %spec.backend,
+            parallel = spec.parallel,
+            skip_permissions = spec.skip_permissions,
+            timeout_ms = ?spec.timeout_ms,
+            max_parallel_workers = ?spec.max_parallel_workers,
+            resume_session_id = ?spec.resume_session_id,
+            "Executing codeagent-wrapper"
+        );
+        debug!(args = ?args, "codeagent-wrapper args");
+        if let Some(timeout_ms) = spec.timeout_ms {
+            debug!(CODEX_TIMEOUT = timeout_ms, "codeagent-wrapper env");
+        }
+        if spec.skip_permissions {
+            debug!(CODEAGENT_SKIP_PERMISSIONS = "1", "codeagent-wrapper env");
+        }
+        if let Some(max_workers) = spec.max_parallel_workers {
+            debug!(CODEAGENT_MAX_PARALLEL_WORKERS = max_workers, "codeagent-wrapper env");
+        }
+        if spec.backend.trim().eq_ignore_ascii_case("codex") {
+            if let Some(m) = spec.codex_model.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+                debug!(CODEX_MODEL = %m, "codeagent-wrapper env");
+            }
+        }
+        if !spec.env.is_empty() {
+            for (key, value) in &spec.env {
+                cmd.env(key, value);
+            }
+        }
+
+        let mut cancel_rx = spec.cancel.clone();
+        if is_cancel_requested(&cancel_rx) {
+            info!("Streaming request cancelled before codeagent-wrapper started");
+            return Ok(CodeagentRunResult {
+                message: String::new(),
+                session_id: None,
+                raw_stdout: String::new(),
+                raw_stderr: String::new(),
+                exit_code: -1,
+                cancelled: true,
+            });
+        }
+
+        let mut child = cmd.spawn().map_err(|e| {
+            AppError::AiServiceError(format!("启动 codeagent-wrapper 失败: {} (bin={})", e, bin.display()))
+        })?;
+
+        // Feed the task from its own task while stdout is read below, so a wrapper that
+        // writes before draining stdin cannot deadlock against us on full pipes.
+        let stdin_task = child.stdin.take().map(|mut stdin| {
+            let task = spec.task.clone();
+            tokio::spawn(async move {
+                if let Err(e) = stdin.write_all(task.as_bytes()).await {
+                    warn!(error = %e, "Failed to write task to codeagent-wrapper stdin");
+                }
+                // Dropping stdin closes it so the wrapper sees EOF
+            })
+        });
+
+        // Drain stderr concurrently so a chatty CLI cannot block on a full pipe while we stream stdout.
+        let stderr_task = child.stderr.take().map(|mut pipe| {
+            tokio::spawn(async move {
+                let mut buf = Vec::new();
+                let _ = pipe.read_to_end(&mut buf).await;
+                String::from_utf8_lossy(&buf).into_owned()
+            })
+        });
+
+        let stdout_pipe = child
+            .stdout
+            .take()
+            .ok_or_else(|| AppError::AiServiceError("无法获取 codeagent-wrapper stdout".to_string()))?;
+        let mut reader = BufReader::new(stdout_pipe);
+        let mut parser = CodeagentStreamParser::default();
+        let mut line = Vec::new();
+        let mut cancelled = false;
+        loop {
+            line.clear();
+            let n = tokio::select! {
+                read = reader.read_until(b'\n', &mut line) => read
+                    .map_err(|e| AppError::AiServiceError(format!("读取 codeagent-wrapper stdout 失败: {}", e)))?,
+                _ = wait_for_cancel(&mut cancel_rx) => {
+                    cancelled = true;
+                    break;
+                }
+            };
+            if n == 0 {
+                break;
+            }
+            if let Some(delta) = parser.push_line(&String::from_utf8_lossy(&line)) {
+                on_delta(&delta);
+            }
+        }
+
+        if cancelled {
+            info!(pid = ?child.id(), "Cancelling codeagent-wrapper");
+            kill_process_tree(&mut child).await;
+        } else if let Some(delta) = parser.finish() {
+            on_delta(&delta);
+        }
+
+        let status = child
+            .wait()
+            .await
+            .map_err(|e| AppError::AiServiceError(format!("等待 codeagent-wrapper 退出失败: {}", e)))?;
+
+        if let Some(task) = stdin_task {
+            task.abort();
+        }
+        let stdout = parser.into_raw();
+        let stderr = match stderr_task {
+            Some(task) => task.await.unwrap_or_default(),
+            None => String::new(),
+        };
+        let exit_code = status.code().unwrap_or(-1);
+
+        if cancelled {
+            let (message, session_id) = parse_codeagent_stdout(&stdout);
+            debug!(exit_code, partial_len = message.len(), "codeagent-wrapper cancelled");
+            return Ok(CodeagentRunResult {
+                message,
+                session_id,
+                raw_stdout: stdout,
+                raw_stderr: stderr,
+                exit_code,
+                cancelled: true,
+            });
+        }
+
+        debug!(
+            exit_code,
+            stdout_len = stdout.len(),
+            stderr_len = stderr.len(),
+            "codeagent-wrapper finished"
+        );
+
+        if exit_code != 0 {
+            let stderr_tail = tail_snippet(&stderr, 4000);
+            let stdout_tail = tail_snippet(&stdout, 4000);
+            warn!(
+                exit_code,
+                stderr_tail = %stderr_tail,
+                stdout_tail = %stdout_tail,
+                "codeagent-wrapper failed"
+            );
+            return Err(AppError::AiServiceError(format!(
+                "codeagent-wrapper 退出码 {}。stderr: {}",
+                exit_code,
+                stderr.trim()
+            )));
+        }
+
+        let (message, session_id) = parse_codeagent_stdout(&stdout);
+        debug!(
+            parsed_session_id = ?session_id,
+            message_len = message.len(),
+            stdout_tail = %tail_snippet(&stdout, 1000),
+            "parsed codeagent-wrapper stdout"
+        );
+        if message.trim().is_empty() {
+            return Err(AppError::AiServiceError(format!(
+                "codeagent-wrapper 未返回有效消息。stderr: {}",
+                stderr.trim()
+            )));
+        }
+
+        Ok(CodeagentRunResult {
+            message,
+            session_id,
+            raw_stdout: stdout,
+            raw_stderr: stderr,
+            exit_code,
+            cancelled: false,
+        })
+    }
+}
+
+#[async_trait]
+impl ChatBackend for CodeagentBackend {
+    fn name(&self) -> &'static str {
+        "codeagent"
+    }
+
+    fn label(&self) -> String {
+        format!("codeagent:{}", self.backend)
+    }
+
+    async fn send(&self, request: ChatRequest, on_delta: &mut DeltaSink<'_>) -> AppResult<AiMessageResult> {
+        let options = request.options;
+        let workdir = options
+            .workspace_dir
+            .clone()
+            .or_else(|| self.config.workdir.clone())
+            .unwrap_or_else(|| ".".to_string());
+
+        // A fresh CLI session (new branch, other CLI) does not know the conversation so far
+        let resuming = options
+            .resume_session_id
+            .as_deref()
+            .is_some_and(|id| !id.trim().is_empty())
+            && !options.code_cli_changed.unwrap_or(false);
+        let task = if resuming || (options.history.is_empty() && options.summary.is_none()) {
+            request.prompt
+        } else {
+            with_transcript(options.summary.as_deref(), &options.history, &request.prompt)
+        };
+
+        let model = options
+            .codex_model
+            .as_deref()
+            .map(str::trim)
+            .filter(|m| self.backend == "codex" && !m.is_empty())
+            .map(str::to_string);
+
+        let result = Self::run_codeagent_wrapper(
+            CodeagentRunSpec {
+                task,
+                backend: self.backend.clone(),
+                workdir: workdir.clone(),
+                skip_permissions: self.config.skip_permissions,
+                timeout_ms: self.config.timeout_ms,
+                max_parallel_workers: self.config.max_parallel_workers,
+                binary_path: self.config.binary_path.clone(),
+                resume_session_id: options.resume_session_id,
+                parallel: options.parallel,
+                codex_model: options.codex_model,
+                env: options.env,
+                code_cli_changed: options.code_cli_changed,
+                cancel: options.cancel,
+            },
+            on_delta,
+        )
+        .await?;
+
+        let stderr_tail = tail_snippet(result.raw_stderr.trim(), STDERR_TAIL_CHARS);
+        Ok(AiMessageResult {
+            message: result.message,
+            codeagent_session_id: result.session_id,
+            cancelled: result.cancelled,
+            context: None,
+            run: RunMetadata {
+                model,
+                workdir: Some(workdir),
+                // -1: cancelled before the wrapper started, or killed by a signal
+                exit_code: (result.exit_code >= 0).then_some(result.exit_code),
+                stderr_tail: (!stderr_tail.is_empty()).then_some(stderr_tail),
+                ..RunMetadata::default()
+            },
+        })
+    }
+}
+
+/// Prefix `prompt` with the summary and earlier turns of the conversation
+fn with_transcript(summary: Option<&str>, history: &[ChatTurn], prompt: &str) -> String {
+    let mut task = String::new();
+    if let Some(summary) = summary {
+        task.push_str(&summary_instruction(summary));
+        task.push_str("\n\n");
+    }
+    if !history.is_empty() {
+        task.push_str("Conversation so far:\n\n");
+    }
+    for turn in history {
+        let speaker = if turn.role == "assistant" { "Assistant" } else { "User" };
+        task.push_str(&format!("{}: {}\n\n", speaker, turn.content.trim()));
+    }
+    task.push_str("---\n\n");
+    task.push_str(prompt);
+    task
+}
+
+#[derive(Debug, Clone)]
+struct CodeagentRunSpec {
+    task: String,
+    backend: String,
+    workdir: String,
+    skip_permissions: bool,
+    timeout_ms: Option<u64>,
+    max_parallel_workers: Option<u32>,
+    binary_path: Option<String>,
+    resume_session_id: Option<String>,
+    parallel: bool,
+    codex_model: Option<String>,
+    env: Vec<(String, String)>,
+    code_cli_changed: Option<bool>,
+    cancel: Option<watch::Receiver<bool>>,
+}
+
+#[derive(Debug, Clone)]
+struct CodeagentRunResult {
+    message: String,
+    #[allow(dead_code)]
+    session_id: Option<String>,
+    #[allow(dead_code)]
+    raw_stdout: String,
+    raw_stderr: String,
+    exit_code: i32,
+    cancelled: bool,
+}
+
+/// Incremental line parser for codeagent-wrapper stdout.
+///
+/// Lines are forwarded as soon as they arrive, except for a `---` separator which is held
+/// back until the next line shows whether it starts the `SESSION_ID` trailer or is just
+/// part of the message (e.g. a markdown horizontal rule).
+#[derive(Debug, Default)]
+struct CodeagentStreamParser {
+    /// Full stdout seen so far, used for the final `parse_codeagent_stdout` pass.
+    raw: String,
+    /// Lines held back because they may belong to the trailer.
+    pending: String,
+    /// Whether any non-blank content was emitted (leading blank lines are dropped).
+    started: bool,
+    /// Set once the `SESSION_ID` trailer has been seen; nothing after it is emitted.
+    in_trailer: bool,
+}
+
+impl CodeagentStreamParser {
+    /// Feed one raw stdout line (including its line terminator) and return the delta to emit.
+    fn push_line(&mut self, line: &str) -> Option<String> {
+        let line = line.replace("\r\n", "\n");
+        self.raw.push_str(&line);
+
+        if self.in_trailer {
+            return None;
+        }
+
+        let trimmed = line.trim();
+        if trimmed.starts_with("SESSION_ID:") {
+            self.in_trailer = true;
+            self.pending.clear();
+            return None;
+        }
+        if trimmed == "---" {
+            self.pending.push_str(&line);
+            return None;
+        }
+        if !self.started && trimmed.is_empty() && self.pending.is_empty() {
+            return None;
+        }
+
+        self.started = true;
+        let mut delta = std::mem::take(&mut self.pending);
+        delta.push_str(&line);
+        Some(delta)
+    }
+
+    /// Flush held-back lines once stdout is closed without a trailer.
+    fn finish(&mut self) -> Option<String> {
+        if self.in_trailer || self.pending.is_empty() {
+            return None;
+        }
+        Some(std::mem::take(&mut self.pending))
+    }
+
+    fn into_raw(self) -> String {
+        self.raw
+    }
+}
+
+fn parse_codeagent_stdout(stdout: &str) -> (String, Option<String>) {
+    // Wrapper prints:
+    // <message>\n
+    // ---\n
+    // SESSION_ID: <id>\n
+    // On Windows, output may contain CRLF; normalize to LF for parsing.
+    let normalized = stdout.replace("\r\n", "\n");
+
+    // Preferred exact marker
+    let marker = "\n---\nSESSION_ID:";
+    if let Some(idx) = normalized.rfind(marker) {
+        let message = normalized[..idx].trim().to_string();
+        let tail = &normalized[idx + marker.len()..];
+        let session_id = tail
+            .lines()
+            .next()
+            .map(|s| s.trim().trim_start_matches(':').trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string());
+        return (message, session_id);
+    }
+
+    // Fallback: some versions may omit the leading newline before ---
+    let marker2 = "---\nSESSION_ID:";
+    if let Some(idx) = normalized.rfind(marker2) {
+        let message = normalized[..idx].trim().to_string();
+        let tail = &normalized[idx + marker2.len()..];
+        let session_id = tail
+            .lines()
+            .next()
+            .map(|s| s.trim().trim_start_matches(':').trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string());
+        return (message, session_id);
+    }
+
+    // Fallback: at least try to parse SESSION_ID even if delimiter formatting changes.
+    if let Some(idx) = normalized.rfind("SESSION_ID:") {
+        let before = &normalized[..idx];
+        let after = &normalized[idx + "SESSION_ID:".len()..];
+        let session_id = after
+            .lines()
+            .next()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string());
+        let message = before.trim_end_matches('-').trim().to_string();
+        return (message, session_id);
+    }
+
+    (normalized.trim().to_string(), None)
+}
+
+fn tail_snippet(s: &str, max_chars: usize) -> String {
+    if max_chars == 0 {
+        return String::new();
+    }
+    let normalized = s.replace("\r\n", "\n");
+    if normalized.chars().count() <= max_chars {
+        return normalized;
+    }
+    // Keep the last `max_chars` characters; slicing at a char index stays valid for multi-byte text
+    let start = normalized
+        .char_indices()
+        .rev()
+        .nth(max_chars - 1)
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+    format!("…{}", &normalized[start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tail_snippet_keeps_multibyte_tail() {
+        let stderr = "错误：无法连接到服务器\n".repeat(300);
+        let tail = tail_snippet(&stderr, STDERR_TAIL_CHARS);
+        assert!(tail.starts_with('…'));
+        assert_eq!(tail.chars().count(), STDERR_TAIL_CHARS + 1);
+        assert!(stderr.ends_with(tail.trim_start_matches('…')));
+    }
+
+    #[test]
+    fn tail_snippet_keeps_short_input() {
+        assert_eq!(tail_snippet("a\r\nb", 10), "a\nb");
+        assert_eq!(tail_snippet("abc", 0), "");
+    }
+}
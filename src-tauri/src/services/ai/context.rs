@@ -0,0 +1,373 @@
+//! Chat context builder
+//!
+//! Turns the attached context files into the `@path\ncontent` prefix sent with a
+//! message, keeping the total within a token budget derived from the model's
+//! context window. Binary files are skipped and large files are cut down to their
+//! head and tail with a visible marker. The returned `ContextReport` says what was
+//! included so the UI can show e.g. "3 files, ~18k tokens". Directories and
+//! globs are expanded first (see `context_paths`).
+
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use super::context_paths::{MAX_CONTEXT_FILES, expand_context_paths};
+
+/// Never read more than this many bytes of a single file
+const MAX_READ_BYTES: u64 = 4 * 1024 * 1024;
+/// Bytes inspected for NUL bytes when detecting binary files
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+/// Share of the context window reserved for attached files
+const CONTEXT_SHARE: f64 = 0.5;
+/// Largest share of the budget a single file may take
+const MAX_FILE_SHARE: f64 = 0.5;
+/// Files are skipped rather than truncated below this many tokens
+const MIN_FILE_TOKENS: usize = 200;
+
+/// Context window (in tokens) for a model id, a provider name or a CLI backend name
+pub fn context_window(model: &str) -> usize {
+    let m = model.to_lowercase();
+    if m.contains("claude") {
+        200_000
+    } else if m.contains("gemini-1.5") || m.contains("gemini-2") || m.contains("gemini-3") {
+        1_000_000
+    } else if m.contains("gemini") {
+        32_000
+    } else if m.contains("gpt-3.5") {
+        16_000
+    } else if m.contains("gpt-4o") || m.contains("gpt-4-turbo") || m.contains("gpt-4.1") {
+        128_000
+    } else if m.contains("gpt-4") {
+        8_000
+    } else if m.contains("gpt-5") || m.contains("codex") || ["o1", "o3", "o4"].iter().any(|p| m.starts_with(p)) {
+        200_000
+    } else {
+        // Conservative default for unknown (often local) models
+        32_000
+    }
+}
+
+/// Estimate the token count of `text` for `model`.
+///
+/// Latin text averages ~4 characters per token (~3.5 for Claude's tokenizer);
+/// CJK and other non-ASCII characters are counted as one token each.
+pub fn estimate_tokens(text: &str, model: &str) -> usize {
+    let chars_per_token = chars_per_token(model);
+    let (ascii, other) = text.chars().fold(
+        (0usize, 0usize),
+        |(a, o), c| {
+            if c.is_ascii() { (a + 1, o) } else { (a, o + 1) }
+        },
+    );
+    (ascii as f64 / chars_per_token).ceil() as usize + other
+}
+
+/// Average ASCII characters per token for `model`
+fn chars_per_token(model: &str) -> f64 {
+    if model.to_lowercase().contains("claude") {
+        3.5
+    } else {
+        4.0
+    }
+}
+
+/// What happened to one attached file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextFileStatus {
+    /// Included in full
+    Included,
+    /// Included with the middle cut out
+    Truncated,
+    /// Left out (see `reason`)
+    Skipped,
+}
+
+/// Report entry for one attached file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextFileReport {
+    pub path: String,
+    pub status: ContextFileStatus,
+    /// Estimated tokens sent for this file
+    pub tokens: usize,
+    /// Estimated tokens of the whole file (when it could be read)
+    pub original_tokens: Option<usize>,
+    /// Why the file was skipped or truncated
+    pub reason: Option<String>,
+}
+
+/// Summary of the context attached to a message
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContextReport {
+    /// Model the estimate was made for
+    pub model: String,
+    /// Token budget for attached files
+    pub budget_tokens: usize,
+    /// Estimated tokens of the included files
+    pub total_tokens: usize,
+    /// Number of files included (fully or truncated)
+    pub included_files: usize,
+    /// Files matched by directories or globs but left out by the file cap (lower bound)
+    pub omitted_files: usize,
+    pub files: Vec<ContextFileReport>,
+}
+
+/// The prompt with its context prefix, and the report describing it
+#[derive(Debug, Clone)]
+pub struct BuiltContext {
+    pub prompt: String,
+    pub report: ContextReport,
+}
+
+/// Builds the context prefix for a message within a token budget
+#[derive(Debug, Clone)]
+pub struct ContextBuilder {
+    model: String,
+    budget_tokens: usize,
+    workspace_dir: Option<PathBuf>,
+    max_files: usize,
+}
+
+impl ContextBuilder {
+    /// Budget derived from the context window of `model`
+    pub fn for_model(model: &str) -> Self {
+        let budget = (context_window(model) as f64 * CONTEXT_SHARE) as usize;
+        Self::with_budget(model, budget)
+    }
+
+    /// Explicit budget (in tokens) for attached files
+    pub fn with_budget(model: &str, budget_tokens: usize) -> Self {
+        Self {
+            model: model.to_string(),
+            budget_tokens,
+            workspace_dir: None,
+            max_files: MAX_CONTEXT_FILES,
+        }
+    }
+
+    /// Directory that relative entries and globs are resolved against
+    pub fn workspace_dir(mut self, dir: Option<&str>) -> Self {
+        self.workspace_dir = dir.filter(|d| !d.trim().is_empty()).map(PathBuf::from);
+        self
+    }
+
+    /// Maximum number of files attached after expansion
+    pub fn max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    /// Expand `entries` (files, directories, globs), read them and prepend them to `message`
+    pub fn build(&self, message: &str, entries: &[String]) -> BuiltContext {
+        let expanded = expand_context_paths(entries, self.workspace_dir.as_deref(), self.max_files);
+        let mut report = ContextReport {
+            model: self.model.clone(),
+            budget_tokens: self.budget_tokens,
+            omitted_files: expanded.omitted,
+            ..Default::default()
+        };
+        for entry in &expanded.unmatched {
+            report.files.push(skipped(entry, None, "no files matched".to_string()));
+        }
+        let files = &expanded.files;
+        let max_file_tokens = ((self.budget_tokens as f64 * MAX_FILE_SHARE) as usize).max(MIN_FILE_TOKENS);
+        let mut remaining = self.budget_tokens;
+        let mut segments: Vec<String> = Vec::new();
+
+        for path in files {
+            let content = match read_text_file(path) {
+                Ok(content) => content,
+                Err(reason) => {
+                    warn!(path = %path, reason = %reason, "Skipping context file");
+                    report.files.push(skipped(path, None, reason));
+                    continue;
+                }
+            };
+
+            let original_tokens = estimate_tokens(&content, &self.model);
+            let limit = max_file_tokens.min(remaining);
+            if original_tokens <= limit {
+                segments.push(format!("@{}\n{}", path, content));
+                remaining -= original_tokens;
+                report.files.push(ContextFileReport {
+                    path: path.clone(),
+                    status: ContextFileStatus::Included,
+                    tokens: original_tokens,
+                    original_tokens: Some(original_tokens),
+                    reason: None,
+                });
+                continue;
+            }
+
+            if limit < MIN_FILE_TOKENS {
+                report.files.push(skipped(
+                    path,
+                    Some(original_tokens),
+                    "context budget exhausted".to_string(),
+                ));
+                continue;
+            }
+
+            let truncated = self.truncate(&content, limit);
+            let tokens = estimate_tokens(&truncated, &self.model);
+            segments.push(format!("@{}\n{}", path, truncated));
+            remaining = remaining.saturating_sub(tokens);
+            report.files.push(ContextFileReport {
+                path: path.clone(),
+                status: ContextFileStatus::Truncated,
+                tokens,
+                original_tokens: Some(original_tokens),
+                reason: Some(format!(
+                    "~{} tokens exceeds the per-file limit of ~{}",
+                    original_tokens, limit
+                )),
+            });
+        }
+
+        report.included_files = report
+            .files
+            .iter()
+            .filter(|f| f.status != ContextFileStatus::Skipped)
+            .count();
+        report.total_tokens = report.files.iter().map(|f| f.tokens).sum();
+        debug!(
+            model = %report.model,
+            included_files = report.included_files,
+            omitted_files = report.omitted_files,
+            total_tokens = report.total_tokens,
+            budget_tokens = report.budget_tokens,
+            "Built chat context"
+        );
+
+        let prompt = if segments.is_empty() {
+            message.to_string()
+        } else {
+            format!("{}\n\n{}", segments.join("\n"), message)
+        };
+        BuiltContext { prompt, report }
+    }
+
+    /// Keep the head and tail lines of `content` within `limit` tokens, marking the cut
+    fn truncate(&self, content: &str, limit: usize) -> String {
+        let lines: Vec<&str> = content.lines().collect();
+        // Leave room for the marker line
+        let target = limit.saturating_sub(40);
+        let head_target = target * 2 / 3;
+        let tail_target = target - head_target;
+
+        let mut head = 0;
+        let mut used = 0;
+        while head < lines.len() {
+            let cost = estimate_tokens(lines[head], &self.model) + 1;
+            if used + cost > head_target {
+                break;
+            }
+            used += cost;
+            head += 1;
+        }
+
+        let mut tail = 0;
+        used = 0;
+        while tail < lines.len() - head {
+            let cost = estimate_tokens(lines[lines.len() - 1 - tail], &self.model) + 1;
+            if used + cost > tail_target {
+                break;
+            }
+            used += cost;
+            tail += 1;
+        }
+
+        if head == 0 && tail == 0 {
+            // A single huge line (minified code, data): cut by characters instead
+            let keep = self.token_prefix(content, target);
+            let total = content.chars().count();
+            return format!(
+                "{}\n… [truncated: {} of {} characters omitted to fit the context budget] …",
+                keep,
+                total - keep.chars().count(),
+                total
+            );
+        }
+
+        let omitted = lines.len() - head - tail;
+        let mut out = lines[..head].join("\n");
+        out.push_str(&format!(
+            "\n… [truncated: {} of {} lines omitted to fit the context budget] …\n",
+            omitted,
+            lines.len()
+        ));
+        out.push_str(&lines[lines.len() - tail..].join("\n"));
+        out
+    }
+
+    /// Longest prefix of `text` whose token estimate stays within `limit`
+    fn token_prefix<'a>(&self, text: &'a str, limit: usize) -> &'a str {
+        let chars_per_token = chars_per_token(&self.model);
+        let (mut ascii, mut other) = (0usize, 0usize);
+        for (i, c) in text.char_indices() {
+            if c.is_ascii() {
+                ascii += 1;
+            } else {
+                other += 1;
+            }
+            if (ascii as f64 / chars_per_token).ceil() as usize + other > limit {
+                return &text[..i];
+            }
+        }
+        text
+    }
+}
+
+fn skipped(path: &str, original_tokens: Option<usize>, reason: String) -> ContextFileReport {
+    ContextFileReport {
+        path: path.to_string(),
+        status: ContextFileStatus::Skipped,
+        tokens: 0,
+        original_tokens,
+        reason: Some(reason),
+    }
+}
+
+/// Read a file as UTF-8 text, rejecting directories and binary files
+fn read_text_file(path: &str) -> Result<String, String> {
+    let file = File::open(path).map_err(|e| format!("failed to open: {}", e))?;
+    let meta = file.metadata().map_err(|e| format!("failed to stat: {}", e))?;
+    if meta.is_dir() {
+        return Err("is a directory".to_string());
+    }
+
+    let mut bytes = Vec::new();
+    file.take(MAX_READ_BYTES)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("failed to read: {}", e))?;
+
+    let sniff = &bytes[..bytes.len().min(BINARY_SNIFF_BYTES)];
+    if sniff.contains(&0) {
+        return Err("binary file".to_string());
+    }
+    let mut text = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) => {
+            // A file cut at MAX_READ_BYTES may end inside a multi-byte character
+            let valid = e.utf8_error().valid_up_to();
+            if meta.len() > MAX_READ_BYTES && e.as_bytes().len() - valid < 4 {
+                let mut bytes = e.into_bytes();
+                bytes.truncate(valid);
+                String::from_utf8(bytes).map_err(|_| "not valid UTF-8 text".to_string())?
+            } else {
+                return Err("not valid UTF-8 text".to_string());
+            }
+        }
+    };
+    if meta.len() > MAX_READ_BYTES {
+        text.push_str(&format!(
+            "\n… [truncated: file is {} bytes, only the first {} were read] …",
+            meta.len(),
+            MAX_READ_BYTES
+        ));
+    }
+    Ok(text)
+}
@@ -0,0 +1,182 @@
+//! Context path expansion
+//!
+//! Expands the entries of `context_files` into concrete files. An entry may be a
+//! file, a directory or a glob pattern such as `src/**/*.rs`; an entry naming an
+//! existing path is never read as a glob. Relative entries are resolved against
+//! the workspace directory. Directory walks and globs honour `.gitignore` and
+//! `.ignore` and skip hidden files; explicitly named files are always kept.
+
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+use globset::{GlobBuilder, GlobMatcher};
+use ignore::WalkBuilder;
+use tracing::{debug, warn};
+
+/// Default cap on the number of files a request may attach
+pub const MAX_CONTEXT_FILES: usize = 100;
+
+/// Result of expanding `context_files`
+#[derive(Debug, Clone, Default)]
+pub struct ExpandedPaths {
+    /// Files to attach, in entry order, without duplicates
+    pub files: Vec<String>,
+    /// Entries that matched nothing (missing paths, empty globs)
+    pub unmatched: Vec<String>,
+    /// Files left out because the cap was reached. Walks stop at the first file
+    /// past the cap, so this is a lower bound.
+    pub omitted: usize,
+}
+
+/// Expand files, directories and globs into at most `max_files` files
+pub fn expand_context_paths(entries: &[String], workspace_dir: Option<&Path>, max_files: usize) -> ExpandedPaths {
+    let mut expanded = ExpandedPaths::default();
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+
+    for entry in entries {
+        let entry_trimmed = entry.trim();
+        if entry_trimmed.is_empty() {
+            continue;
+        }
+
+        // Existing paths win over glob syntax: `app/[id]/page.tsx` is a real file
+        let path = resolve(entry_trimmed, workspace_dir);
+        let matches: Box<dyn Iterator<Item = PathBuf>> = if path.is_dir() {
+            Box::new(walk(&path, None))
+        } else if path.exists() {
+            Box::new(std::iter::once(path))
+        } else if is_glob(entry_trimmed) {
+            expand_glob(entry_trimmed, workspace_dir)
+        } else {
+            Box::new(std::iter::empty())
+        };
+
+        let mut matched = false;
+        for path in matches {
+            matched = true;
+            if !seen.insert(path.clone()) {
+                continue;
+            }
+            if expanded.files.len() >= max_files {
+                // Stop walking: the rest of the tree would be dropped anyway
+                expanded.omitted += 1;
+                break;
+            }
+            expanded.files.push(path.to_string_lossy().to_string());
+        }
+
+        if !matched {
+            warn!(entry = %entry_trimmed, "Context entry matched no files");
+            expanded.unmatched.push(entry.clone());
+        }
+    }
+
+    debug!(
+        files = expanded.files.len(),
+        unmatched = expanded.unmatched.len(),
+        omitted = expanded.omitted,
+        "Expanded context paths"
+    );
+    expanded
+}
+
+fn is_glob(entry: &str) -> bool {
+    entry.contains(['*', '?', '[', '{'])
+}
+
+fn resolve(entry: &str, workspace_dir: Option<&Path>) -> PathBuf {
+    let path = Path::new(entry);
+    match workspace_dir {
+        Some(root) if path.is_relative() => root.join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Lazily walk `root` in name order with ignore rules, yielding files accepted by `matcher`
+fn walk(root: &Path, matcher: Option<GlobMatcher>) -> impl Iterator<Item = PathBuf> {
+    WalkBuilder::new(root)
+        // Honour .gitignore even when the workspace is not (yet) a git repository
+        .require_git(false)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build()
+        .filter_map(|entry| match entry {
+            Ok(entry) => Some(entry),
+            Err(err) => {
+                warn!(error = %err, "Failed to walk context directory");
+                None
+            }
+        })
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .filter(move |path| matcher.as_ref().is_none_or(|m| m.is_match(normalize(path))))
+}
+
+/// Expand a glob by walking from its longest literal directory prefix
+fn expand_glob(pattern: &str, workspace_dir: Option<&Path>) -> Box<dyn Iterator<Item = PathBuf>> {
+    let mut full = resolve(pattern, workspace_dir);
+    if full.is_relative() {
+        // Walked paths must share the pattern's form for matching to work
+        if let Ok(cwd) = std::env::current_dir() {
+            full = cwd.join(full);
+        }
+    }
+
+    let mut base = PathBuf::new();
+    for component in full.components() {
+        if let Component::Normal(part) = component {
+            if is_glob(&part.to_string_lossy()) {
+                break;
+            }
+        }
+        base.push(component);
+    }
+    if base.as_os_str().is_empty() {
+        base = PathBuf::from(".");
+    }
+    if !base.is_dir() {
+        return Box::new(std::iter::empty());
+    }
+
+    let matcher = match GlobBuilder::new(&normalize(&full)).literal_separator(true).build() {
+        Ok(glob) => glob.compile_matcher(),
+        Err(err) => {
+            warn!(pattern = %pattern, error = %err, "Invalid context glob");
+            return Box::new(std::iter::empty());
+        }
+    };
+    Box::new(walk(&base, Some(matcher)))
+}
+
+/// Forward-slash form of a path so globs behave the same on every platform
+fn normalize(path: &Path) -> String {
+    crate::utils::fs::normalize_path(&path.to_string_lossy())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn existing_paths_with_glob_characters_are_literal() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for file in ["app/[id]/page.tsx", "app/i/page.tsx", "src/{legacy}/x.rs"] {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, "x").unwrap();
+        }
+
+        let expanded = expand_context_paths(
+            &["app/[id]/page.tsx".to_string(), "src/{legacy}/x.rs".to_string()],
+            Some(root),
+            MAX_CONTEXT_FILES,
+        );
+        assert!(expanded.unmatched.is_empty());
+        assert_eq!(expanded.files.len(), 2);
+        assert!(expanded.files[0].ends_with("[id]/page.tsx"));
+        assert!(expanded.files[1].ends_with("{legacy}/x.rs"));
+
+        let globbed = expand_context_paths(&["app/*/page.tsx".to_string()], Some(root), MAX_CONTEXT_FILES);
+        assert_eq!(globbed.files.len(), 2);
+    }
+}
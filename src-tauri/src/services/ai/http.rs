@@ -0,0 +1,725 @@
+//! Direct provider HTTP backends
+//!
+//! Anthropic Messages, OpenAI Chat Completions and Gemini generateContent,
+//! all streamed over server-sent events.
+
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use tokio::sync::watch;
+use tracing::debug;
+
+use super::backend::{
+    ChatBackend, ChatRequest, ChatTurn, DeltaSink, is_cancel_requested, summary_instruction, wait_for_cancel,
+};
+use super::{AiMessageResult, AiModel, AiProvider, RunMetadata, TokenUsage};
+use crate::utils::error::{AppError, AppResult};
+
+/// `max_tokens` sent to providers that require it (Anthropic)
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+/// Anthropic API version header value
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Anthropic Messages API backend
+#[derive(Debug, Clone)]
+pub struct AnthropicBackend {
+    client: reqwest::Client,
+    model: AiModel,
+}
+
+impl AnthropicBackend {
+    pub fn new(client: reqwest::Client, model: AiModel) -> Self {
+        Self { client, model }
+    }
+}
+
+#[async_trait]
+impl ChatBackend for AnthropicBackend {
+    fn name(&self) -> &'static str {
+        "anthropic"
+    }
+
+    async fn send(&self, request: ChatRequest, on_delta: &mut DeltaSink<'_>) -> AppResult<AiMessageResult> {
+        let mut body = json!({
+            "model": self.model.name,
+            "max_tokens": DEFAULT_MAX_TOKENS,
+            "stream": true,
+            "messages": chat_messages(&request.options.history, &request.prompt),
+        });
+        if let Some(summary) = request.options.summary.as_deref() {
+            body["system"] = json!(summary_instruction(summary));
+        }
+
+        let builder = self
+            .client
+            .post(&self.model.endpoint)
+            .header("x-api-key", &self.model.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(&body);
+
+        stream_sse(
+            self.name(),
+            &self.model.name,
+            builder,
+            request.options.cancel,
+            on_delta,
+            |event, usage| match event.event.as_deref() {
+                Some("content_block_delta") => {
+                    let data: Value = serde_json::from_str(&event.data)?;
+                    Ok(data["delta"]["text"].as_str().map(str::to_string))
+                }
+                Some("message_start") => {
+                    let data: Value = serde_json::from_str(&event.data)?;
+                    let reported = &data["message"]["usage"];
+                    record_usage(
+                        usage,
+                        reported["input_tokens"].as_u64(),
+                        reported["output_tokens"].as_u64(),
+                    );
+                    Ok(None)
+                }
+                Some("message_delta") => {
+                    let data: Value = serde_json::from_str(&event.data)?;
+                    record_usage(usage, None, data["usage"]["output_tokens"].as_u64());
+                    Ok(None)
+                }
+                Some("error") => {
+                    let data: Value = serde_json::from_str(&event.data).unwrap_or(Value::Null);
+                    Err(provider_error("anthropic", &data["error"]["message"], &event.data))
+                }
+                _ => Ok(None),
+            },
+        )
+        .await
+    }
+}
+
+/// OpenAI Chat Completions API backend
+///
+/// Also serves OpenAI-compatible servers (Ollama, llama.cpp, vLLM), whose model
+/// `endpoint` is a base URL and whose API key is optional.
+#[derive(Debug, Clone)]
+pub struct OpenAiBackend {
+    client: reqwest::Client,
+    model: AiModel,
+}
+
+impl OpenAiBackend {
+    pub fn new(client: reqwest::Client, model: AiModel) -> Self {
+        Self { client, model }
+    }
+
+    fn chat_completions_url(&self) -> String {
+        match self.model.provider {
+            AiProvider::OpenAiCompatible => openai_compatible_url(&self.model.endpoint, "chat/completions"),
+            _ => self.model.endpoint.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl ChatBackend for OpenAiBackend {
+    fn name(&self) -> &'static str {
+        match self.model.provider {
+            AiProvider::OpenAiCompatible => "openai_compatible",
+            _ => "openai",
+        }
+    }
+
+    async fn send(&self, request: ChatRequest, on_delta: &mut DeltaSink<'_>) -> AppResult<AiMessageResult> {
+        let mut messages = chat_messages(&request.options.history, &request.prompt);
+        if let Some(summary) = request.options.summary.as_deref() {
+            messages.insert(0, json!({ "role": "system", "content": summary_instruction(summary) }));
+        }
+        let mut body = json!({
+            "model": self.model.name,
+            "stream": true,
+            "messages": messages,
+        });
+        // Compatible servers may reject unknown options; only OpenAI is asked for usage
+        if self.model.provider == AiProvider::OpenAi {
+            body["stream_options"] = json!({ "include_usage": true });
+        }
+
+        let mut builder = self.client.post(self.chat_completions_url()).json(&body);
+        if !self.model.api_key.trim().is_empty() {
+            builder = builder.bearer_auth(&self.model.api_key);
+        }
+
+        let provider = self.name();
+        stream_sse(
+            provider,
+            &self.model.name,
+            builder,
+            request.options.cancel,
+            on_delta,
+            |event, usage| {
+                if event.data.trim() == "[DONE]" {
+                    return Ok(None);
+                }
+                let data: Value = serde_json::from_str(&event.data)?;
+                if !data["error"].is_null() {
+                    return Err(provider_error(provider, &data["error"]["message"], &event.data));
+                }
+                let reported = &data["usage"];
+                record_usage(
+                    usage,
+                    reported["prompt_tokens"].as_u64(),
+                    reported["completion_tokens"].as_u64(),
+                );
+                Ok(data["choices"][0]["delta"]["content"].as_str().map(str::to_string))
+            },
+        )
+        .await
+    }
+}
+
+/// List the model ids served by an OpenAI-compatible endpoint (`GET <base>/v1/models`).
+pub async fn list_openai_compatible_models(
+    client: &reqwest::Client,
+    base_url: &str,
+    api_key: Option<&str>,
+) -> AppResult<Vec<String>> {
+    let url = openai_compatible_url(base_url, "models");
+    debug!(url = %url, "Discovering OpenAI-compatible models");
+
+    let mut builder = client.get(&url);
+    if let Some(key) = api_key.map(str::trim).filter(|k| !k.is_empty()) {
+        builder = builder.bearer_auth(key);
+    }
+    let response = builder.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(AppError::AiServiceError(format!(
+            "获取模型列表失败 ({}): {}",
+            status,
+            body.trim()
+        )));
+    }
+
+    let data: Value = response.json().await?;
+    let models = data["data"]
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|m| m["id"].as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(models)
+}
+
+/// Build `<base>/v1/<path>` from a user-supplied OpenAI-compatible base URL.
+///
+/// Accepts `http://host:11434`, `http://host:11434/v1` or a full `…/v1/chat/completions` URL.
+fn openai_compatible_url(base_url: &str, path: &str) -> String {
+    let mut base = base_url.trim().trim_end_matches('/');
+    for suffix in ["/chat/completions", "/models"] {
+        if let Some(stripped) = base.strip_suffix(suffix) {
+            base = stripped;
+            break;
+        }
+    }
+    if base.ends_with("/v1") {
+        format!("{}/{}", base, path)
+    } else {
+        format!("{}/v1/{}", base, path)
+    }
+}
+
+/// Google Gemini generateContent API backend
+#[derive(Debug, Clone)]
+pub struct GeminiBackend {
+    client: reqwest::Client,
+    model: AiModel,
+}
+
+impl GeminiBackend {
+    pub fn new(client: reqwest::Client, model: AiModel) -> Self {
+        Self { client, model }
+    }
+
+    /// `…/models/<m>:generateContent` → `…/models/<m>:streamGenerateContent`
+    fn stream_endpoint(&self) -> String {
+        if self.model.endpoint.contains(":streamGenerateContent") {
+            self.model.endpoint.clone()
+        } else {
+            self.model
+                .endpoint
+                .replace(":generateContent", ":streamGenerateContent")
+        }
+    }
+}
+
+#[async_trait]
+impl ChatBackend for GeminiBackend {
+    fn name(&self) -> &'static str {
+        "gemini"
+    }
+
+    async fn send(&self, request: ChatRequest, on_delta: &mut DeltaSink<'_>) -> AppResult<AiMessageResult> {
+        let mut contents: Vec<Value> = request
+            .options
+            .history
+            .iter()
+            .map(|turn| {
+                let role = if turn.role == "assistant" { "model" } else { "user" };
+                json!({ "role": role, "parts": [{ "text": turn.content }] })
+            })
+            .collect();
+        contents.push(json!({ "role": "user", "parts": [{ "text": request.prompt }] }));
+        let mut body = json!({ "contents": contents });
+        if let Some(summary) = request.options.summary.as_deref() {
+            body["systemInstruction"] = json!({ "parts": [{ "text": summary_instruction(summary) }] });
+        }
+
+        let builder = self
+            .client
+            .post(self.stream_endpoint())
+            .query(&[("alt", "sse")])
+            .header("x-goog-api-key", &self.model.api_key)
+            .json(&body);
+
+        stream_sse(
+            self.name(),
+            &self.model.name,
+            builder,
+            request.options.cancel,
+            on_delta,
+            |event, usage| {
+                let data: Value = serde_json::from_str(&event.data)?;
+                if !data["error"].is_null() {
+                    return Err(provider_error("gemini", &data["error"]["message"], &event.data));
+                }
+                let reported = &data["usageMetadata"];
+                record_usage(
+                    usage,
+                    reported["promptTokenCount"].as_u64(),
+                    reported["candidatesTokenCount"].as_u64(),
+                );
+                let text: String = data["candidates"][0]["content"]["parts"]
+                    .as_array()
+                    .map(|parts| parts.iter().filter_map(|p| p["text"].as_str()).collect())
+                    .unwrap_or_default();
+                Ok(Some(text))
+            },
+        )
+        .await
+    }
+}
+
+/// OpenAI/Anthropic style `messages` array: history followed by the new user prompt
+fn chat_messages(history: &[ChatTurn], prompt: &str) -> Vec<Value> {
+    let mut messages: Vec<Value> = history
+        .iter()
+        .map(|turn| json!({ "role": turn.role, "content": turn.content }))
+        .collect();
+    messages.push(json!({ "role": "user", "content": prompt }));
+    messages
+}
+
+fn provider_error(provider: &str, message: &Value, raw: &str) -> AppError {
+    let message = message.as_str().unwrap_or(raw);
+    AppError::AiServiceError(format!("{} 返回错误: {}", provider, message))
+}
+
+/// Merge token counts reported by a provider; later events overwrite earlier ones
+fn record_usage(usage: &mut Option<TokenUsage>, input_tokens: Option<u64>, output_tokens: Option<u64>) {
+    if input_tokens.is_none() && output_tokens.is_none() {
+        return;
+    }
+    let usage = usage.get_or_insert_with(TokenUsage::default);
+    if let Some(tokens) = input_tokens {
+        usage.input_tokens = tokens;
+    }
+    if let Some(tokens) = output_tokens {
+        usage.output_tokens = tokens;
+    }
+}
+
+/// Send `builder`, decode the SSE body and feed the text extracted by `extract` to `on_delta`.
+///
+/// `extract` also records the token usage the provider reports.
+async fn stream_sse<E>(
+    provider: &str,
+    model: &str,
+    builder: reqwest::RequestBuilder,
+    mut cancel: Option<watch::Receiver<bool>>,
+    on_delta: &mut DeltaSink<'_>,
+    mut extract: E,
+) -> AppResult<AiMessageResult>
+where
+    E: FnMut(&SseEvent, &mut Option<TokenUsage>) -> AppResult<Option<String>> + Send,
+{
+    let mut message = String::new();
+    let mut usage = None;
+    if is_cancel_requested(&cancel) {
+        return Ok(cancelled_result(message, model, usage));
+    }
+
+    debug!(provider, "Sending streaming chat request");
+    let mut response = tokio::select! {
+        response = builder.send() => response?,
+        _ = wait_for_cancel(&mut cancel) => return Ok(cancelled_result(message, model, usage)),
+    };
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(AppError::AiServiceError(format!(
+            "{} 请求失败 ({}): {}",
+            provider,
+            status,
+            body.trim()
+        )));
+    }
+
+    let mut decoder = SseDecoder::default();
+    loop {
+        let chunk = tokio::select! {
+            chunk = response.chunk() => chunk?,
+            _ = wait_for_cancel(&mut cancel) => return Ok(cancelled_result(message, model, usage)),
+        };
+        let Some(chunk) = chunk else {
+            break;
+        };
+        for event in decoder.push(&chunk) {
+            if let Some(text) = extract(&event, &mut usage)? {
+                if !text.is_empty() {
+                    on_delta(&text);
+                    message.push_str(&text);
+                }
+            }
+        }
+    }
+    if let Some(event) = decoder.finish() {
+        if let Some(text) = extract(&event, &mut usage)? {
+            on_delta(&text);
+            message.push_str(&text);
+        }
+    }
+
+    debug!(provider, message_len = message.len(), "Streaming chat request finished");
+    if message.trim().is_empty() {
+        return Err(AppError::AiServiceError(format!("{} 未返回有效消息", provider)));
+    }
+
+    Ok(AiMessageResult {
+        message,
+        codeagent_session_id: None,
+        cancelled: false,
+        context: None,
+        run: http_run(model, usage),
+    })
+}
+
+fn cancelled_result(message: String, model: &str, usage: Option<TokenUsage>) -> AiMessageResult {
+    AiMessageResult {
+        message,
+        codeagent_session_id: None,
+        cancelled: true,
+        context: None,
+        run: http_run(model, usage),
+    }
+}
+
+fn http_run(model: &str, usage: Option<TokenUsage>) -> RunMetadata {
+    RunMetadata {
+        model: Some(model.to_string()),
+        usage,
+        ..RunMetadata::default()
+    }
+}
+
+/// A decoded server-sent event
+#[derive(Debug, Default, Clone)]
+pub(super) struct SseEvent {
+    /// `event:` field, if any
+    pub event: Option<String>,
+    /// `data:` lines joined with `\n`
+    pub data: String,
+}
+
+/// Incremental `text/event-stream` decoder
+#[derive(Debug, Default)]
+pub(super) struct SseDecoder {
+    buf: Vec<u8>,
+}
+
+impl SseDecoder {
+    /// Feed raw body bytes and return every event completed by them.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        // CR only appears as part of line endings; JSON payloads escape it.
+        self.buf.extend(chunk.iter().copied().filter(|b| *b != b'\r'));
+
+        let mut events = Vec::new();
+        while let Some(pos) = self.buf.windows(2).position(|w| w == b"\n\n") {
+            let block: Vec<u8> = self.buf.drain(..pos + 2).collect();
+            if let Some(event) = parse_sse_block(&String::from_utf8_lossy(&block)) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    /// Flush a trailing event that was not terminated by a blank line.
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        let block = std::mem::take(&mut self.buf);
+        parse_sse_block(&String::from_utf8_lossy(&block))
+    }
+}
+
+fn parse_sse_block(block: &str) -> Option<SseEvent> {
+    let mut event = None;
+    let mut data: Vec<&str> = Vec::new();
+    for line in block.lines() {
+        if line.is_empty() || line.starts_with(':') {
+            continue;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => event = Some(value.to_string()),
+            "data" => data.push(value),
+            _ => {}
+        }
+    }
+
+    if data.is_empty() {
+        return None;
+    }
+    Some(SseEvent {
+        event,
+        data: data.join("\n"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::ai::AiChatOptions;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    /// Serve a single request with `status` and a chunked body written one piece at a
+    /// time; the handle yields the raw request (head and body).
+    async fn mock_server(status: &str, chunks: Vec<&'static str>) -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let status = status.to_string();
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(head_end) = text.find("\r\n\r\n") {
+                    let length = text[..head_end]
+                        .lines()
+                        .find_map(|line| {
+                            let (name, value) = line.split_once(':')?;
+                            name.eq_ignore_ascii_case("content-length")
+                                .then(|| value.trim().parse::<usize>().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= head_end + 4 + length {
+                        break;
+                    }
+                }
+                if n == 0 {
+                    break;
+                }
+            }
+
+            let head = format!(
+                "HTTP/1.1 {}\r\nContent-Type: text/event-stream\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n",
+                status
+            );
+            socket.write_all(head.as_bytes()).await.unwrap();
+            for chunk in chunks {
+                let frame = format!("{:x}\r\n{}\r\n", chunk.len(), chunk);
+                socket.write_all(frame.as_bytes()).await.unwrap();
+                socket.flush().await.unwrap();
+                // Separate reads on the client side
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+            socket.write_all(b"0\r\n\r\n").await.unwrap();
+            String::from_utf8_lossy(&request).into_owned()
+        });
+        (url, handle)
+    }
+
+    fn model(provider: AiProvider, endpoint: String, api_key: &str) -> AiModel {
+        AiModel {
+            id: "test-model".to_string(),
+            name: "test-model".to_string(),
+            provider,
+            endpoint,
+            api_key: api_key.to_string(),
+            is_active: true,
+        }
+    }
+
+    async fn send(backend: &dyn ChatBackend) -> (AppResult<AiMessageResult>, Vec<String>) {
+        let request = ChatRequest {
+            prompt: "hello".to_string(),
+            options: AiChatOptions::default(),
+        };
+        let mut deltas = Vec::new();
+        let result = backend
+            .send(request, &mut |delta: &str| deltas.push(delta.to_string()))
+            .await;
+        (result, deltas)
+    }
+
+    #[test]
+    fn sse_decoder_handles_split_events() {
+        let mut decoder = SseDecoder::default();
+        assert!(decoder.push(b"event: a\r\nda").is_empty());
+        let events = decoder.push(b"ta: one\r\n\r\n: comment\n\ndata: two\ndata:three\n\ndata: tail");
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event.as_deref(), Some("a"));
+        assert_eq!(events[0].data, "one");
+        assert_eq!(events[1].event, None);
+        assert_eq!(events[1].data, "two\nthree");
+        assert_eq!(decoder.finish().unwrap().data, "tail");
+        assert!(decoder.finish().is_none());
+    }
+
+    #[tokio::test]
+    async fn anthropic_streams_deltas_and_usage() {
+        let (url, server) = mock_server(
+            "200 OK",
+            vec![
+                "event: message_start\ndata: {\"message\":{\"usage\":{\"input_tokens\":12,\"output_tokens\":1}}}\n\n",
+                "event: content_block_delta\ndata: {\"delta\":{\"text\":\"Hel",
+                "lo\"}}\n\nevent: content_block_delta\ndata: {\"delta\":{\"text\":\" world\"}}\n",
+                "\nevent: message_delta\ndata: {\"usage\":{\"output_tokens\":7}}\n\n",
+            ],
+        )
+        .await;
+        let backend = AnthropicBackend::new(reqwest::Client::new(), model(AiProvider::Anthropic, url, "sk-test"));
+
+        let (result, deltas) = send(&backend).await;
+        let result = result.unwrap();
+        assert_eq!(deltas, ["Hello", " world"]);
+        assert_eq!(result.message, "Hello world");
+        let usage = result.run.usage.unwrap();
+        assert_eq!((usage.input_tokens, usage.output_tokens), (12, 7));
+
+        let request = server.await.unwrap().to_lowercase();
+        assert!(request.contains("x-api-key: sk-test"));
+        assert!(request.contains("anthropic-version: 2023-06-01"));
+    }
+
+    #[tokio::test]
+    async fn anthropic_error_event_fails() {
+        let (url, _server) = mock_server(
+            "200 OK",
+            vec!["event: error\ndata: {\"error\":{\"message\":\"Overloaded\"}}\n\n"],
+        )
+        .await;
+        let backend = AnthropicBackend::new(reqwest::Client::new(), model(AiProvider::Anthropic, url, "sk-test"));
+
+        let (result, _) = send(&backend).await;
+        assert!(result.unwrap_err().to_string().contains("Overloaded"));
+    }
+
+    #[tokio::test]
+    async fn openai_streams_deltas_and_usage() {
+        let (url, server) = mock_server(
+            "200 OK",
+            vec![
+                "data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\ndata: {\"choi",
+                "ces\":[{\"delta\":{\"content\":\" there\"}}]}\n\n",
+                "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":5,\"completion_tokens\":2}}\n\ndata: [DONE]\n\n",
+            ],
+        )
+        .await;
+        let backend = OpenAiBackend::new(reqwest::Client::new(), model(AiProvider::OpenAi, url, "sk-test"));
+
+        let (result, deltas) = send(&backend).await;
+        let result = result.unwrap();
+        assert_eq!(deltas, ["Hi", " there"]);
+        assert_eq!(result.message, "Hi there");
+        let usage = result.run.usage.unwrap();
+        assert_eq!((usage.input_tokens, usage.output_tokens), (5, 2));
+
+        let request = server.await.unwrap();
+        assert!(request.to_lowercase().contains("authorization: bearer sk-test"));
+        assert!(request.contains("\"include_usage\":true"));
+    }
+
+    #[tokio::test]
+    async fn openai_compatible_uses_base_url_without_key() {
+        let (url, server) = mock_server(
+            "200 OK",
+            vec!["data: {\"choices\":[{\"delta\":{\"content\":\"ok\"}}]}\n\ndata: [DONE]\n\n"],
+        )
+        .await;
+        let backend = OpenAiBackend::new(reqwest::Client::new(), model(AiProvider::OpenAiCompatible, url, ""));
+
+        let (result, _) = send(&backend).await;
+        assert_eq!(result.unwrap().message, "ok");
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /v1/chat/completions "));
+        assert!(!request.to_lowercase().contains("authorization:"));
+        assert!(!request.contains("stream_options"));
+    }
+
+    #[tokio::test]
+    async fn openai_http_error_fails_with_status() {
+        let (url, _server) = mock_server("401 Unauthorized", vec!["{\"error\":{\"message\":\"bad key\"}}"]).await;
+        let backend = OpenAiBackend::new(reqwest::Client::new(), model(AiProvider::OpenAi, url, "sk-bad"));
+
+        let (result, deltas) = send(&backend).await;
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("401"));
+        assert!(error.contains("bad key"));
+        assert!(deltas.is_empty());
+    }
+
+    #[tokio::test]
+    async fn gemini_streams_deltas_and_usage() {
+        let (url, server) = mock_server(
+            "200 OK",
+            vec![
+                "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"Bon\"}]}}]}\n\r\n",
+                "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"jour\"}]}}],",
+                "\"usageMetadata\":{\"promptTokenCount\":3,\"candidatesTokenCount\":4}}\n\n",
+            ],
+        )
+        .await;
+        let endpoint = format!("{}/v1beta/models/test-model:generateContent", url);
+        let backend = GeminiBackend::new(reqwest::Client::new(), model(AiProvider::Gemini, endpoint, "g-key"));
+
+        let (result, deltas) = send(&backend).await;
+        let result = result.unwrap();
+        assert_eq!(deltas, ["Bon", "jour"]);
+        assert_eq!(result.message, "Bonjour");
+        let usage = result.run.usage.unwrap();
+        assert_eq!((usage.input_tokens, usage.output_tokens), (3, 4));
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /v1beta/models/test-model:streamGenerateContent?alt=sse "));
+        assert!(request.to_lowercase().contains("x-goog-api-key: g-key"));
+    }
+
+    #[tokio::test]
+    async fn gemini_error_payload_fails() {
+        let (url, _server) =
+            mock_server("200 OK", vec!["data: {\"error\":{\"message\":\"quota exceeded\"}}\n\n"]).await;
+        let endpoint = format!("{}/v1beta/models/test-model:generateContent", url);
+        let backend = GeminiBackend::new(reqwest::Client::new(), model(AiProvider::Gemini, endpoint, "g-key"));
+
+        let (result, _) = send(&backend).await;
+        assert!(result.unwrap_err().to_string().contains("quota exceeded"));
+    }
+}
@@ -0,0 +1,670 @@
+//! AI Service module
+//!
+//! This module handles communication with AI models and CLI tools.
+
+pub mod backend;
+pub mod codeagent;
+pub mod context;
+pub mod context_paths;
+pub mod http;
+pub mod usage;
+
+use std::time::Instant;
+
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
+
+use crate::database::repositories::settings_repository::SettingsRepository;
+use crate::services::secret_vault::{self, SharedVault};
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::redact;
+use backend::{ChatBackend, ChatRequest, ChatTurn};
+use codeagent::CodeagentBackend;
+use context::{ContextBuilder, ContextReport};
+use http::{AnthropicBackend, GeminiBackend, OpenAiBackend};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CodeagentWrapperConfig {
+    /// Optional explicit path to `codeagent-wrapper` binary.
+    pub binary_path: Option<String>,
+    /// Backend name: `codex` | `claude` | `gemini`.
+    /// If omitted, will be derived from current model.
+    pub backend: Option<String>,
+    /// Working directory passed to wrapper (and used as process cwd).
+    pub workdir: Option<String>,
+    /// Skip CLI permission prompts (dangerous).
+    pub skip_permissions: bool,
+    /// Timeout in milliseconds (mapped to CODEX_TIMEOUT).
+    pub timeout_ms: Option<u64>,
+
+    /// Limit parallel workers (mapped to CODEAGENT_MAX_PARALLEL_WORKERS).
+    pub max_parallel_workers: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AiChatOptions {
+    /// Selected code CLI name from UI (e.g. claude-cli/codex-cli/gemini-cli).
+    pub code_cli: Option<String>,
+    /// Resume session id for codeagent-wrapper.
+    pub resume_session_id: Option<String>,
+    /// Enable wrapper parallel mode (reads task config from stdin).
+    pub parallel: bool,
+    /// When using codex backend, optional model hint.
+    /// Note: upstream `codeagent-wrapper` does not accept `--model`; we pass via env.
+    pub codex_model: Option<String>,
+    /// Workspace ID (used for context files).
+    pub workspace_dir: Option<String>,
+    /// Whether the code CLI has changed (used for session resuming).
+    pub code_cli_changed: Option<bool>,
+    /// Environment variables (mapped to CODEAGENT_ENV).
+    pub env: Vec<(String, String)>,
+    /// Cancellation signal; flipping it to `true` kills the wrapper and its descendants.
+    pub cancel: Option<watch::Receiver<bool>>,
+    /// Explicit model name; overrides `code_cli` when it names a direct HTTP model.
+    pub model: Option<String>,
+    /// Earlier turns of the session, replayed to stateless HTTP backends.
+    pub history: Vec<ChatTurn>,
+    /// Summary of the conversation before `history`.
+    pub summary: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AiMessageResult {
+    pub message: String,
+    pub codeagent_session_id: Option<String>,
+    /// The request was cancelled; `message` holds the partial output received so far.
+    pub cancelled: bool,
+    /// What was attached from the context files (set by `AiService`, not the backends)
+    pub context: Option<ContextReport>,
+    /// How the response was produced
+    pub run: RunMetadata,
+}
+
+/// How a response was produced; stored with the assistant message
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunMetadata {
+    /// Backend that served the request, e.g. `codeagent:codex` or `anthropic`
+    pub backend: String,
+    pub model: Option<String>,
+    pub workdir: Option<String>,
+    pub duration_ms: u64,
+    /// Exit code of codeagent-wrapper
+    pub exit_code: Option<i32>,
+    /// End of codeagent-wrapper's stderr
+    pub stderr_tail: Option<String>,
+    /// Token counts, when the backend reports them
+    pub usage: Option<TokenUsage>,
+    #[serde(default)]
+    pub cancelled: bool,
+    /// Error of a failed run; the message then holds the error text
+    pub error: Option<String>,
+}
+
+/// Token counts reported by a provider
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// How requests for a model are delivered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AiProvider {
+    /// Through codeagent-wrapper (claude/codex/gemini CLIs)
+    #[default]
+    Codeagent,
+    /// Anthropic Messages API
+    #[serde(alias = "Anthropic")]
+    Anthropic,
+    /// OpenAI Chat Completions API
+    #[serde(rename = "openai", alias = "OpenAI")]
+    OpenAi,
+    /// Google Gemini generateContent API
+    #[serde(alias = "Gemini", alias = "Google")]
+    Gemini,
+    /// Any OpenAI-compatible server (Ollama, llama.cpp, vLLM); `endpoint` is its base URL
+    #[serde(rename = "openai_compatible", alias = "ollama", alias = "vllm", alias = "llama.cpp")]
+    OpenAiCompatible,
+}
+
+impl AiProvider {
+    /// Whether this provider is called directly over HTTP
+    pub fn is_http(&self) -> bool {
+        !matches!(self, AiProvider::Codeagent)
+    }
+
+    /// Whether requests are rejected without an API key
+    pub fn requires_api_key(&self) -> bool {
+        matches!(self, AiProvider::Anthropic | AiProvider::OpenAi | AiProvider::Gemini)
+    }
+
+    /// Environment variable consulted when the model has no API key configured
+    pub fn api_key_env(&self) -> Option<&'static str> {
+        match self {
+            AiProvider::Codeagent => None,
+            AiProvider::Anthropic => Some("ANTHROPIC_API_KEY"),
+            AiProvider::OpenAi => Some("OPENAI_API_KEY"),
+            AiProvider::Gemini => Some("GEMINI_API_KEY"),
+            AiProvider::OpenAiCompatible => Some("OPENAI_COMPATIBLE_API_KEY"),
+        }
+    }
+}
+
+/// Settings category holding the AI model registry
+pub const AI_SETTINGS_CATEGORY: &str = "ai";
+/// Settings key of the model list (JSON array of `AiModel`)
+pub const AI_MODELS_KEY: &str = "ai.models";
+/// Settings key of the current model id (JSON string)
+pub const AI_CURRENT_MODEL_KEY: &str = "ai.current_model";
+
+/// AI Model configuration
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AiModel {
+    /// Registry id (defaults to `name`)
+    #[serde(default)]
+    pub id: String,
+    /// Model name sent to the provider
+    pub name: String,
+    /// Provider used to reach the model
+    #[serde(default)]
+    pub provider: AiProvider,
+    /// API endpoint
+    #[serde(default)]
+    pub endpoint: String,
+    /// API key, or a `vault:<name>` reference into the secret vault
+    #[serde(default)]
+    pub api_key: String,
+    /// Is active
+    #[serde(default = "default_true")]
+    pub is_active: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Stands in for a plaintext API key in models handed to the frontend
+pub const MASKED_API_KEY: &str = redact::REDACTED;
+
+/// Vault entry holding the API key of the model with registry id `id`
+pub fn api_key_secret_name(id: &str) -> String {
+    let id: String = id
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("ai.{}.api_key", id)
+}
+
+impl AiModel {
+    /// Copy safe to hand out: a plaintext API key is replaced by `MASKED_API_KEY`,
+    /// an empty key or a vault reference is kept
+    pub fn masked(&self) -> AiModel {
+        AiModel {
+            api_key: self.displayed_api_key().to_string(),
+            ..self.clone()
+        }
+    }
+
+    fn displayed_api_key(&self) -> &str {
+        if self.api_key.is_empty() || secret_vault::is_vault_ref(&self.api_key) {
+            self.api_key.as_str()
+        } else {
+            MASKED_API_KEY
+        }
+    }
+}
+
+impl std::fmt::Debug for AiModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let api_key = self.displayed_api_key();
+        f.debug_struct("AiModel")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("provider", &self.provider)
+            .field("endpoint", &self.endpoint)
+            .field("api_key", &api_key)
+            .field("is_active", &self.is_active)
+            .finish()
+    }
+}
+
+/// AI Service for managing AI models and sending messages
+#[derive(Debug, Clone)]
+pub struct AiService {
+    /// Available AI models
+    models: Vec<AiModel>,
+    /// Current model
+    current_model: Option<String>,
+    /// HTTP client shared by the direct provider backends
+    client: reqwest::Client,
+    /// Secret vault resolving `vault:<name>` API keys
+    vault: SharedVault,
+
+    /// codeagent-wrapper integration
+    codeagent: CodeagentWrapperConfig,
+}
+
+impl AiService {
+    /// Create a new AI service
+    pub fn new() -> Self {
+        Self {
+            models: vec![
+                AiModel {
+                    id: "claude-3-5-sonnet".to_string(),
+                    name: "claude-3-5-sonnet".to_string(),
+                    provider: AiProvider::Anthropic,
+                    endpoint: "https://api.anthropic.com/v1/messages".to_string(),
+                    api_key: String::new(),
+                    is_active: true,
+                },
+                AiModel {
+                    id: "gpt-4".to_string(),
+                    name: "gpt-4".to_string(),
+                    provider: AiProvider::OpenAi,
+                    endpoint: "https://api.openai.com/v1/chat/completions".to_string(),
+                    api_key: String::new(),
+                    is_active: true,
+                },
+                AiModel {
+                    id: "gpt-3.5-turbo".to_string(),
+                    name: "gpt-3.5-turbo".to_string(),
+                    provider: AiProvider::OpenAi,
+                    endpoint: "https://api.openai.com/v1/chat/completions".to_string(),
+                    api_key: String::new(),
+                    is_active: true,
+                },
+                AiModel {
+                    id: "gemini-pro".to_string(),
+                    name: "gemini-pro".to_string(),
+                    provider: AiProvider::Gemini,
+                    endpoint: "https://generativelanguage.googleapis.com/v1beta/models/gemini-pro:generateContent"
+                        .to_string(),
+                    api_key: String::new(),
+                    is_active: true,
+                },
+            ],
+            current_model: Some("claude-3-5-sonnet".to_string()),
+            client: reqwest::Client::new(),
+            vault: SharedVault::default(),
+            codeagent: CodeagentWrapperConfig {
+                binary_path: None,
+                backend: None,
+                workdir: None,
+                skip_permissions: false,
+                timeout_ms: None,
+                max_parallel_workers: None,
+            },
+        }
+    }
+
+    /// Resolve `vault:<name>` API keys through the app's shared vault
+    pub fn set_vault(&mut self, vault: SharedVault) {
+        self.vault = vault;
+    }
+
+    pub fn set_codeagent_config(&mut self, config: CodeagentWrapperConfig) {
+        self.codeagent = config;
+    }
+
+    pub fn get_codeagent_config(&self) -> CodeagentWrapperConfig {
+        self.codeagent.clone()
+    }
+
+    /// Get available models
+    pub fn get_models(&self) -> Vec<String> {
+        self.models
+            .iter()
+            .filter(|m| m.is_active)
+            .map(|m| m.name.clone())
+            .collect()
+    }
+
+    /// Get every registered model, including inactive ones
+    pub fn list_models(&self) -> Vec<AiModel> {
+        self.models.clone()
+    }
+
+    /// Get the current model id
+    pub fn current_model(&self) -> Option<String> {
+        self.current_model.clone()
+    }
+
+    /// Set current model
+    pub fn set_current_model(&mut self, model: String) -> AppResult<()> {
+        match self.models.iter().find(|m| m.id == model || m.name == model) {
+            Some(found) => {
+                self.current_model = Some(found.id.clone());
+                Ok(())
+            }
+            None => Err(AppError::ConfigError(format!("Model not found: {}", model))),
+        }
+    }
+
+    /// Load the model registry and current model from the settings table.
+    ///
+    /// Keeps the built-in models when nothing has been saved yet.
+    pub async fn load_settings(&mut self, db: &DatabaseConnection) -> AppResult<()> {
+        if let Some(setting) = SettingsRepository::get_by_key(db, AI_MODELS_KEY).await? {
+            let models: Vec<AiModel> = serde_json::from_str(&setting.value)?;
+            self.models = Vec::new();
+            for model in models {
+                if !secret_vault::is_vault_ref(&model.api_key) {
+                    redact::register_secret(&model.api_key);
+                }
+                self.add_model(model);
+            }
+        }
+        if let Some(setting) = SettingsRepository::get_by_key(db, AI_CURRENT_MODEL_KEY).await? {
+            self.current_model = serde_json::from_str(&setting.value)?;
+        }
+        info!(
+            model_count = self.models.len(),
+            current_model = ?self.current_model,
+            "Loaded AI model registry"
+        );
+        Ok(())
+    }
+
+    /// Persist the model registry and current model to the settings table
+    pub async fn save_settings(&self, db: &DatabaseConnection) -> AppResult<()> {
+        SettingsRepository::upsert(
+            db,
+            AI_MODELS_KEY,
+            &serde_json::to_string(&self.models)?,
+            AI_SETTINGS_CATEGORY,
+            Some("AI model registry"),
+        )
+        .await?;
+        SettingsRepository::upsert(
+            db,
+            AI_CURRENT_MODEL_KEY,
+            &serde_json::to_string(&self.current_model)?,
+            AI_SETTINGS_CATEGORY,
+            Some("Current AI model"),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Send message to AI (placeholder)
+    pub async fn send_message(&self, message: &str, _context_files: Option<Vec<String>>) -> AppResult<String> {
+        Ok(self
+            .send_message_with_options(message, _context_files, AiChatOptions::default())
+            .await?
+            .message)
+    }
+
+    pub async fn send_message_with_options(
+        &self,
+        message: &str,
+        _context_files: Option<Vec<String>>,
+        options: AiChatOptions,
+    ) -> AppResult<AiMessageResult> {
+        self.send_message_streaming(message, _context_files, options, |_| {})
+            .await
+    }
+
+    /// Send message to AI and forward deltas to `on_delta` while the response is produced.
+    ///
+    /// The returned `AiMessageResult` still carries the full parsed message (without the
+    /// `SESSION_ID` trailer), so callers can persist it once the stream is finished.
+    pub async fn send_message_streaming<F>(
+        &self,
+        message: &str,
+        _context_files: Option<Vec<String>>,
+        options: AiChatOptions,
+        mut on_delta: F,
+    ) -> AppResult<AiMessageResult>
+    where
+        F: FnMut(&str) + Send,
+    {
+        // Never log the prompt or env values: they may hold secrets
+        debug!(
+            model = ?options.model,
+            code_cli = ?options.code_cli,
+            message_len = message.len(),
+            history_turns = options.history.len(),
+            env_vars = options.env.len(),
+            "Sending message to AI"
+        );
+
+        // Directory walks and file reads stay off the async workers
+        let builder = self.context_builder(&options);
+        let context_message = message.to_string();
+        let context_files = _context_files.unwrap_or_default();
+        let built = tokio::task::spawn_blocking(move || builder.build(&context_message, &context_files))
+            .await
+            .map_err(|e| AppError::GenericError(format!("Context task failed: {}", e)))?;
+
+        let backend = self.select_backend(&options)?;
+        info!(backend = backend.name(), "Dispatching chat request");
+        let workdir = options.workspace_dir.clone();
+        let started = Instant::now();
+        let mut result = backend
+            .send(
+                ChatRequest {
+                    prompt: built.prompt,
+                    options,
+                },
+                &mut on_delta,
+            )
+            .await?;
+        result.context = Some(built.report);
+        result.run.backend = backend.label();
+        result.run.workdir = result.run.workdir.take().or(workdir);
+        result.run.duration_ms = started.elapsed().as_millis() as u64;
+        result.run.cancelled = result.cancelled;
+        Ok(result)
+    }
+
+    /// Label of the backend `options` would be sent to, for recording failed runs
+    pub fn backend_label(&self, options: &AiChatOptions) -> Option<String> {
+        self.select_backend(options).ok().map(|backend| backend.label())
+    }
+
+    /// Report what `send_message_streaming` would attach for `context_files`, without sending
+    pub fn preview_context(&self, message: &str, context_files: &[String], options: &AiChatOptions) -> ContextReport {
+        self.context_builder(options).build(message, context_files).report
+    }
+
+    /// Context builder sized for the model the request will most likely use
+    fn context_builder(&self, options: &AiChatOptions) -> ContextBuilder {
+        let model = options
+            .model
+            .clone()
+            .filter(|m| !m.trim().is_empty())
+            .or_else(|| options.code_cli.as_deref().and_then(Self::derive_backend_from_code_cli))
+            .or_else(|| self.current_model.clone())
+            .unwrap_or_default();
+        ContextBuilder::for_model(&model).workspace_dir(options.workspace_dir.as_deref())
+    }
+
+    /// Pick the backend for a request.
+    ///
+    /// 1. `options.model` naming a direct HTTP model uses that provider
+    ///    (its API key is required, except for OpenAI-compatible endpoints).
+    /// 2. A UI-selected `code_cli` goes through codeagent-wrapper.
+    /// 3. Otherwise the current model decides; HTTP models without an API key fall back to the wrapper.
+    ///
+    /// API keys come from the model itself or, when empty, from the provider's env var
+    /// (e.g. `ANTHROPIC_API_KEY`) in `options.env`.
+    fn select_backend(&self, options: &AiChatOptions) -> AppResult<Box<dyn ChatBackend>> {
+        if let Some(name) = options.model.as_deref().filter(|n| !n.trim().is_empty()) {
+            let model = self
+                .find_model(name)
+                .ok_or_else(|| AppError::ConfigError(format!("Model not found: {}", name)))?;
+            if model.provider.is_http() {
+                let model = self
+                    .with_api_key(model, &options.env)
+                    .ok_or_else(|| AppError::ConfigError(format!("Model {} has no API key configured", model.name)))?;
+                return Ok(self.http_backend(model));
+            }
+        }
+
+        if options.code_cli.is_none() {
+            if let Some(model) = self.current_model.as_deref().and_then(|n| self.find_model(n)) {
+                if model.provider.is_http() {
+                    if let Some(model) = self.with_api_key(model, &options.env) {
+                        return Ok(self.http_backend(model));
+                    }
+                }
+            }
+        }
+
+        // IMPORTANT: UI-selected `code_cli` should take precedence over any persisted config,
+        // otherwise users see a mismatch (e.g. code_cli=gemini-cli but backend=claude).
+        let backend = options
+            .code_cli
+            .as_deref()
+            .and_then(Self::derive_backend_from_code_cli)
+            .or_else(|| self.codeagent.backend.clone())
+            .unwrap_or_else(|| self.derive_backend_from_current_model());
+        Ok(Box::new(CodeagentBackend::new(self.codeagent.clone(), backend)))
+    }
+
+    /// Clone `model` with its API key resolved, or `None` if a required key is missing.
+    fn with_api_key(&self, model: &AiModel, env: &[(String, String)]) -> Option<AiModel> {
+        let mut model = model.clone();
+        if secret_vault::is_vault_ref(&model.api_key) {
+            match self.vault.with(|vault| vault.resolve(&model.api_key)) {
+                Ok(key) => model.api_key = key,
+                Err(e) => {
+                    warn!(model = %model.name, error = %e, "Failed to resolve API key from vault");
+                    model.api_key.clear();
+                }
+            }
+        }
+        if model.api_key.trim().is_empty() {
+            let key = model.provider.api_key_env().and_then(|var| {
+                env.iter()
+                    .find(|(k, v)| k == var && !v.trim().is_empty())
+                    .map(|(_, v)| v.clone())
+                    .or_else(|| std::env::var(var).ok().filter(|v| !v.trim().is_empty()))
+            });
+            match key {
+                Some(key) => model.api_key = key,
+                None if model.provider.requires_api_key() => return None,
+                None => {}
+            }
+        }
+        redact::register_secret(&model.api_key);
+        Some(model)
+    }
+
+    fn http_backend(&self, model: AiModel) -> Box<dyn ChatBackend> {
+        let client = self.client.clone();
+        match model.provider {
+            AiProvider::Anthropic => Box::new(AnthropicBackend::new(client, model)),
+            AiProvider::OpenAi | AiProvider::OpenAiCompatible => Box::new(OpenAiBackend::new(client, model)),
+            AiProvider::Gemini => Box::new(GeminiBackend::new(client, model)),
+            AiProvider::Codeagent => unreachable!("codeagent models are not HTTP backends"),
+        }
+    }
+
+    /// List the models served by an OpenAI-compatible endpoint (Ollama, llama.cpp, vLLM).
+    pub async fn discover_models(&self, base_url: &str, api_key: Option<&str>) -> AppResult<Vec<String>> {
+        http::list_openai_compatible_models(&self.client, base_url, api_key).await
+    }
+
+    fn find_model(&self, id: &str) -> Option<&AiModel> {
+        self.models.iter().find(|m| (m.id == id || m.name == id) && m.is_active)
+    }
+
+    /// Add a new model, replacing any model with the same id
+    pub fn add_model(&mut self, mut model: AiModel) {
+        if model.id.trim().is_empty() {
+            model.id = model.name.clone();
+        }
+        match self.models.iter_mut().find(|m| m.id == model.id) {
+            Some(existing) => *existing = model,
+            None => self.models.push(model),
+        }
+    }
+
+    /// Remove a model; clears the current model if it was the one removed
+    pub fn remove_model(&mut self, id: &str) -> bool {
+        let before = self.models.len();
+        self.models.retain(|m| m.id != id);
+        let removed = self.models.len() != before;
+        if removed && self.current_model.as_deref() == Some(id) {
+            self.current_model = None;
+        }
+        removed
+    }
+
+    /// Move plaintext API keys into the vault, leaving `vault:` references in the
+    /// registry; returns how many keys were moved
+    pub fn seal_api_keys(&mut self) -> AppResult<usize> {
+        let mut sealed = 0;
+        for model in &mut self.models {
+            if model.api_key.trim().is_empty() || secret_vault::is_vault_ref(&model.api_key) {
+                continue;
+            }
+            let name = api_key_secret_name(&model.id);
+            self.vault.with(|vault| vault.set(&name, &model.api_key))?;
+            model.api_key = format!("{}{}", secret_vault::VAULT_REF_PREFIX, name);
+            sealed += 1;
+        }
+        if sealed > 0 {
+            debug!(sealed, "Moved model API keys into the vault");
+        }
+        Ok(sealed)
+    }
+
+    /// Delete the vault entry `seal_api_keys` created for a removed model
+    pub fn forget_api_key(&self, model: &AiModel) {
+        let name = api_key_secret_name(&model.id);
+        if model.api_key.trim() != format!("{}{}", secret_vault::VAULT_REF_PREFIX, name) {
+            return;
+        }
+        if let Err(e) = self.vault.with(|vault| vault.delete(&name)) {
+            warn!(model = %model.id, error = %e, "Failed to delete model API key from vault");
+        }
+    }
+
+    fn derive_backend_from_current_model(&self) -> String {
+        let model = self.current_model.as_deref().unwrap_or("");
+        let m = model.to_lowercase();
+        if m.contains("claude") {
+            "claude".to_string()
+        } else if m.contains("gemini") {
+            "gemini".to_string()
+        } else {
+            // Fallback: treat OpenAI-like model ids as codex CLI backend.
+            "codex".to_string()
+        }
+    }
+
+    fn derive_backend_from_code_cli(code_cli: &str) -> Option<String> {
+        let v = code_cli.trim().to_lowercase();
+        if v.is_empty() {
+            return None;
+        }
+        if v.contains("claude") {
+            Some("claude".to_string())
+        } else if v.contains("gemini") {
+            Some("gemini".to_string())
+        } else if v.contains("codex") {
+            Some("codex".to_string())
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for AiService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,239 @@
+//! Usage and cost reports
+//!
+//! Aggregates the run metadata stored with assistant messages. Costs are estimates
+//! from a user-maintained price table (`ai.model_prices`); runs of models without a
+//! price count towards requests and tokens but not towards cost.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+
+use super::AI_SETTINGS_CATEGORY;
+use crate::database::repositories::message_run_repository::{MessageRunRepository, RunUsageRow};
+use crate::database::repositories::settings_repository::SettingsRepository;
+use crate::utils::error::{AppError, AppResult};
+
+/// Settings key of the price table (JSON array of `ModelPrice`)
+pub const AI_MODEL_PRICES_KEY: &str = "ai.model_prices";
+
+/// Price of a model in USD per million tokens
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    /// Model name; a trailing `*` matches every model starting with the rest
+    pub model: String,
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
+/// Dimension a usage report is grouped by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageDimension {
+    Day,
+    Workspace,
+    Backend,
+    Model,
+}
+
+/// Which runs to report and how to group them
+#[derive(Debug, Clone, Default)]
+pub struct UsageQuery {
+    pub workspace_id: Option<i32>,
+    /// First day (`YYYY-MM-DD`, local) or instant (RFC 3339) to include
+    pub since: Option<String>,
+    /// Last day (inclusive) or instant (exclusive) to include
+    pub until: Option<String>,
+    /// Defaults to all dimensions
+    pub group_by: Vec<UsageDimension>,
+}
+
+/// Totals of one group; dimensions not grouped by are `None`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageRow {
+    pub day: Option<String>,
+    pub workspace_id: Option<String>,
+    pub workspace_name: Option<String>,
+    pub backend: Option<String>,
+    pub model: Option<String>,
+    pub requests: u64,
+    pub failed: u64,
+    pub cancelled: u64,
+    /// `failed / requests`
+    pub failure_rate: f64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Time spent waiting for the backend
+    pub duration_ms: u64,
+    pub estimated_cost_usd: f64,
+    /// Requests that reported tokens for a model without a price
+    pub unpriced_requests: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageReport {
+    /// Newest day first, then by time spent
+    pub rows: Vec<UsageRow>,
+    pub totals: UsageRow,
+}
+
+/// Load the price table from the settings table (empty when never saved)
+pub async fn load_prices(db: &DatabaseConnection) -> AppResult<Vec<ModelPrice>> {
+    match SettingsRepository::get_by_key(db, AI_MODEL_PRICES_KEY).await? {
+        Some(setting) => Ok(serde_json::from_str(&setting.value)?),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Validate and persist the price table
+pub async fn save_prices(db: &DatabaseConnection, prices: &[ModelPrice]) -> AppResult<()> {
+    for price in prices {
+        if price.model.trim().is_empty() {
+            return Err(AppError::ValidationError("Model name is required".to_string()));
+        }
+        if ![price.input_per_mtok, price.output_per_mtok]
+            .iter()
+            .all(|p| p.is_finite() && *p >= 0.0)
+        {
+            return Err(AppError::ValidationError(format!(
+                "Invalid price for model {}",
+                price.model
+            )));
+        }
+    }
+    SettingsRepository::upsert(
+        db,
+        AI_MODEL_PRICES_KEY,
+        &serde_json::to_string(prices)?,
+        AI_SETTINGS_CATEGORY,
+        Some("AI model prices (USD per million tokens)"),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Aggregate the runs matching `query`
+pub async fn usage_report(db: &DatabaseConnection, query: &UsageQuery) -> AppResult<UsageReport> {
+    let since = query.since.as_deref().map(|s| parse_bound(s, false)).transpose()?;
+    let until = query.until.as_deref().map(|s| parse_bound(s, true)).transpose()?;
+    let prices = load_prices(db).await?;
+    let rows = MessageRunRepository::usage(db, query.workspace_id, since, until).await?;
+
+    let group_by = if query.group_by.is_empty() {
+        vec![
+            UsageDimension::Day,
+            UsageDimension::Workspace,
+            UsageDimension::Backend,
+            UsageDimension::Model,
+        ]
+    } else {
+        query.group_by.clone()
+    };
+    let mut groups: BTreeMap<[Option<String>; 4], UsageRow> = BTreeMap::new();
+    let mut totals = UsageRow::default();
+    for row in rows {
+        let row = to_usage_row(row, &prices);
+        add(&mut totals, &row);
+        let key = [
+            group_by
+                .contains(&UsageDimension::Day)
+                .then(|| row.day.clone())
+                .flatten(),
+            group_by
+                .contains(&UsageDimension::Workspace)
+                .then(|| row.workspace_id.clone())
+                .flatten(),
+            group_by
+                .contains(&UsageDimension::Backend)
+                .then(|| row.backend.clone())
+                .flatten(),
+            group_by
+                .contains(&UsageDimension::Model)
+                .then(|| row.model.clone())
+                .flatten(),
+        ];
+        let group = groups.entry(key.clone()).or_insert_with(|| UsageRow {
+            day: key[0].clone(),
+            workspace_id: key[1].clone(),
+            workspace_name: key[1].as_ref().and(row.workspace_name.clone()),
+            backend: key[2].clone(),
+            model: key[3].clone(),
+            ..UsageRow::default()
+        });
+        add(group, &row);
+    }
+
+    let mut rows: Vec<UsageRow> = groups.into_values().collect();
+    rows.sort_by(|a, b| b.day.cmp(&a.day).then(b.duration_ms.cmp(&a.duration_ms)));
+    Ok(UsageReport { rows, totals })
+}
+
+/// Price of `model`: an exact entry, else the longest matching `prefix*` entry
+pub fn find_price<'a>(prices: &'a [ModelPrice], model: &str) -> Option<&'a ModelPrice> {
+    prices.iter().find(|p| p.model == model).or_else(|| {
+        prices
+            .iter()
+            .filter_map(|p| p.model.strip_suffix('*').map(|prefix| (prefix, p)))
+            .filter(|(prefix, _)| model.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, p)| p)
+    })
+}
+
+fn to_usage_row(row: RunUsageRow, prices: &[ModelPrice]) -> UsageRow {
+    let input_tokens = row.input_tokens.max(0) as u64;
+    let output_tokens = row.output_tokens.max(0) as u64;
+    let price = row.model.as_deref().and_then(|m| find_price(prices, m));
+    let estimated_cost_usd = price
+        .map(|p| (input_tokens as f64 * p.input_per_mtok + output_tokens as f64 * p.output_per_mtok) / 1_000_000.0)
+        .unwrap_or_default();
+    let has_tokens = input_tokens + output_tokens > 0;
+    UsageRow {
+        day: Some(row.day),
+        workspace_id: row.workspace_id.map(|id| id.to_string()),
+        workspace_name: row.workspace_name,
+        backend: Some(row.backend),
+        model: row.model,
+        requests: row.requests as u64,
+        failed: row.failed as u64,
+        cancelled: row.cancelled as u64,
+        failure_rate: 0.0,
+        input_tokens,
+        output_tokens,
+        duration_ms: row.duration_ms.max(0) as u64,
+        estimated_cost_usd,
+        unpriced_requests: if price.is_none() && has_tokens {
+            row.requests as u64
+        } else {
+            0
+        },
+    }
+}
+
+fn add(total: &mut UsageRow, row: &UsageRow) {
+    total.requests += row.requests;
+    total.failed += row.failed;
+    total.cancelled += row.cancelled;
+    total.input_tokens += row.input_tokens;
+    total.output_tokens += row.output_tokens;
+    total.duration_ms += row.duration_ms;
+    total.estimated_cost_usd += row.estimated_cost_usd;
+    total.unpriced_requests += row.unpriced_requests;
+    total.failure_rate = total.failed as f64 / total.requests.max(1) as f64;
+}
+
+/// A date is a local day: its start, or for an inclusive `until` the next day's start
+fn parse_bound(value: &str, end_of_day: bool) -> AppResult<DateTime<Utc>> {
+    if let Ok(instant) = DateTime::parse_from_rfc3339(value) {
+        return Ok(instant.with_timezone(&Utc));
+    }
+    let day = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| AppError::ValidationError(format!("Invalid date: {}", value)))?;
+    let day = if end_of_day { day + Duration::days(1) } else { day };
+    Local
+        .from_local_datetime(&day.and_hms_opt(0, 0, 0).unwrap_or_default())
+        .earliest()
+        .map(|start| start.with_timezone(&Utc))
+        .ok_or_else(|| AppError::ValidationError(format!("Invalid date: {}", value)))
+}
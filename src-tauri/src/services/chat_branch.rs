@@ -0,0 +1,173 @@
+//! Conversation branches
+//!
+//! Messages of a session form a tree through `parent_id`; editing an earlier user
+//! message adds a sibling and so starts a new branch while keeping the original
+//! path. A branch is identified by its leaf message. These helpers work on all
+//! messages of a session in insertion order.
+//!
+//! Resume ids are tracked per message: a code CLI session can only be resumed on
+//! a branch if every message produced in that CLI session lies on the branch.
+//! After a fork the CLI session contains messages of the other branch, so the new
+//! branch starts a fresh CLI session instead.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::chat_session::ChatMessage;
+
+/// Characters of the fork message shown in a branch list
+const PREVIEW_CHARS: usize = 80;
+
+/// One branch of a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatBranch {
+    /// Last message of the branch; pass it to `switch_chat_branch`
+    pub leaf_id: String,
+    /// First message that differs from its sibling branches (`None` without forks)
+    pub fork_message_id: Option<String>,
+    /// Start of the fork message
+    pub preview: String,
+    /// Messages from the root to the leaf
+    pub message_count: usize,
+    /// Timestamp of the leaf message
+    pub updated_at: String,
+    pub is_active: bool,
+}
+
+/// Indices of the messages from the root to `leaf`.
+///
+/// Falls back to the most recent message when `leaf` is unknown.
+pub fn path_to(messages: &[ChatMessage], leaf: Option<&str>) -> Vec<usize> {
+    let index = id_index(messages);
+    let Some(mut current) = leaf
+        .and_then(|id| index.get(id).copied())
+        .or_else(|| messages.len().checked_sub(1))
+    else {
+        return Vec::new();
+    };
+
+    let mut path = vec![current];
+    let mut seen = HashSet::from([current]);
+    while let Some(parent) = messages[current]
+        .parent_id
+        .as_deref()
+        .and_then(|id| index.get(id).copied())
+    {
+        // Guard against cycles in corrupted data
+        if !seen.insert(parent) {
+            break;
+        }
+        path.push(parent);
+        current = parent;
+    }
+    path.reverse();
+    path
+}
+
+/// Most recent leaf below (or at) the message `id`
+pub fn latest_leaf_under(messages: &[ChatMessage], id: &str) -> Option<usize> {
+    let children = children(messages);
+    let mut current = *id_index(messages).get(id)?;
+    let mut seen = HashSet::from([current]);
+    while let Some(&next) = children.get(messages[current].id.as_str()).and_then(|c| c.iter().max()) {
+        if !seen.insert(next) {
+            break;
+        }
+        current = next;
+    }
+    Some(current)
+}
+
+/// All branches, oldest first
+pub fn branches(messages: &[ChatMessage], active_leaf: Option<&str>) -> Vec<ChatBranch> {
+    let children = children(messages);
+    let roots = messages.iter().filter(|m| m.parent_id.is_none()).count();
+    let active = path_to(messages, active_leaf).last().copied();
+
+    (0..messages.len())
+        .filter(|&i| !children.contains_key(messages[i].id.as_str()))
+        .map(|leaf| {
+            let path = path_to(messages, Some(&messages[leaf].id));
+            let mut fork = (roots > 1).then(|| path[0]);
+            for pair in path.windows(2) {
+                if children.get(messages[pair[0]].id.as_str()).is_some_and(|c| c.len() > 1) {
+                    fork = Some(pair[1]);
+                }
+            }
+            let preview_source = &messages[fork.unwrap_or(path[0])].content;
+            ChatBranch {
+                leaf_id: messages[leaf].id.clone(),
+                fork_message_id: fork.map(|i| messages[i].id.clone()),
+                preview: preview_source.chars().take(PREVIEW_CHARS).collect(),
+                message_count: path.len(),
+                updated_at: messages[leaf].timestamp.clone(),
+                is_active: Some(leaf) == active,
+            }
+        })
+        .collect()
+}
+
+/// Code CLI resume ids valid for the branch `path`.
+///
+/// `session_task_ids` (the per-session map of older data, which has no per-message
+/// ids) is used only while the session has a single branch.
+pub fn resume_task_ids(
+    messages: &[ChatMessage],
+    path: &[usize],
+    session_task_ids: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let on_path: HashSet<usize> = path.iter().copied().collect();
+    let mut decided: HashSet<&str> = HashSet::new();
+    let mut task_ids = HashMap::new();
+
+    for &i in path.iter().rev() {
+        let (Some(cli), Some(task_id)) = (messages[i].code_cli.as_deref(), messages[i].code_cli_task_id.as_deref())
+        else {
+            continue;
+        };
+        if !decided.insert(cli) {
+            continue;
+        }
+        let continued_elsewhere = messages
+            .iter()
+            .enumerate()
+            .any(|(j, m)| m.code_cli_task_id.as_deref() == Some(task_id) && !on_path.contains(&j));
+        if !continued_elsewhere {
+            task_ids.insert(cli.to_string(), task_id.to_string());
+        }
+    }
+
+    if leaf_count(messages) <= 1 {
+        for (cli, task_id) in session_task_ids {
+            if !decided.contains(cli.as_str()) {
+                task_ids.insert(cli.clone(), task_id.clone());
+            }
+        }
+    }
+    task_ids
+}
+
+/// Number of branches
+pub fn leaf_count(messages: &[ChatMessage]) -> usize {
+    let children = children(messages);
+    messages
+        .iter()
+        .filter(|m| !children.contains_key(m.id.as_str()))
+        .count()
+}
+
+fn id_index(messages: &[ChatMessage]) -> HashMap<&str, usize> {
+    messages.iter().enumerate().map(|(i, m)| (m.id.as_str(), i)).collect()
+}
+
+/// Child indices by parent message id
+fn children(messages: &[ChatMessage]) -> HashMap<&str, Vec<usize>> {
+    let mut children: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, m) in messages.iter().enumerate() {
+        if let Some(parent) = m.parent_id.as_deref() {
+            children.entry(parent).or_default().push(i);
+        }
+    }
+    children
+}
@@ -0,0 +1,336 @@
+//! Chat session export
+//!
+//! Renders `ChatSession`s to Markdown (for pasting into PRs and reviews), to a
+//! standalone HTML page, or to JSONL with one `{"messages": [...]}` record per
+//! session for fine-tuning and evaluation datasets. Every format carries the
+//! model, timestamps, attached files and code CLI task ids.
+
+use std::fs;
+use std::path::Path;
+
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+
+use super::chat_schema;
+use super::chat_session::{self, ChatMessage, ChatSession, SessionFilter};
+
+/// Sessions loaded per page when exporting a whole workspace
+const EXPORT_PAGE_SIZE: usize = 200;
+
+/// Export format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Jsonl,
+}
+
+/// Render one session; `output_path` additionally writes it to that file
+pub async fn export_session(
+    db: &DatabaseConnection,
+    session_id: &str,
+    format: ExportFormat,
+    output_path: Option<&str>,
+) -> Result<String, String> {
+    let session = chat_session::load_session_by_id(db, session_id).await?;
+    let rendered = render_sessions(std::slice::from_ref(&session), format);
+    if let Some(path) = output_path {
+        write_export(path, &rendered)?;
+    }
+    Ok(rendered)
+}
+
+/// Render every session of a workspace, archived ones last; `output_path` additionally writes it to that file
+pub async fn export_workspace_sessions(
+    db: &DatabaseConnection,
+    workspace_id: &str,
+    format: ExportFormat,
+    output_path: Option<&str>,
+) -> Result<String, String> {
+    let mut sessions = Vec::new();
+    for archived in [false, true] {
+        let filter = SessionFilter {
+            archived,
+            ..SessionFilter::default()
+        };
+        let mut offset = 0;
+        loop {
+            let page = chat_session::load_all_sessions(
+                db,
+                workspace_id.to_string(),
+                &filter,
+                Some(EXPORT_PAGE_SIZE),
+                Some(offset),
+            )
+            .await?;
+            let done = page.len() < EXPORT_PAGE_SIZE;
+            offset += page.len();
+            for summary in page {
+                sessions.push(chat_session::load_session_by_id(db, &summary.id).await?);
+            }
+            if done {
+                break;
+            }
+        }
+    }
+
+    let rendered = render_sessions(&sessions, format);
+    if let Some(path) = output_path {
+        write_export(path, &rendered)?;
+    }
+    info!("Exported {} sessions of workspace {}", sessions.len(), workspace_id);
+    Ok(rendered)
+}
+
+/// Render sessions in `format`
+pub fn render_sessions(sessions: &[ChatSession], format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Markdown => sessions
+            .iter()
+            .map(render_markdown)
+            .collect::<Vec<_>>()
+            .join("\n---\n\n"),
+        ExportFormat::Html => render_html(sessions),
+        ExportFormat::Jsonl => sessions.iter().map(|s| render_jsonl(s) + "\n").collect(),
+    }
+}
+
+fn write_export(path: &str, content: &str) -> Result<(), String> {
+    let path = Path::new(path);
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create export directory: {}", e))?;
+    }
+    crate::utils::fs::write_atomic(path, content.as_bytes())
+        .map_err(|e| format!("Failed to write export file: {}", e))?;
+    info!("Chat export written to {:?}", path);
+    Ok(())
+}
+
+fn session_title(session: &ChatSession) -> String {
+    session
+        .name
+        .clone()
+        .filter(|n| !n.trim().is_empty())
+        .unwrap_or_else(|| format!("Chat session {}", session.id))
+}
+
+fn role_label(role: &str) -> String {
+    match role {
+        "user" => "User".to_string(),
+        "assistant" => "Assistant".to_string(),
+        "system" => "System".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// `key: value` header lines shared by Markdown and HTML
+fn session_metadata(session: &ChatSession) -> Vec<(&'static str, String)> {
+    let mut rows = vec![("Session", session.id.clone())];
+    if let Some(workspace) = &session.workspace_id {
+        rows.push(("Workspace", workspace.clone()));
+    }
+    rows.push(("Created", session.created_at.clone()));
+    rows.push(("Updated", session.updated_at.clone()));
+    rows.push(("Messages", session.message_count.to_string()));
+    let mut task_ids: Vec<_> = session.code_cli_task_ids.iter().collect();
+    task_ids.sort();
+    for (cli, task_id) in task_ids {
+        rows.push(("Code CLI task", format!("{}: {}", cli, task_id)));
+    }
+    rows
+}
+
+/// `timestamp · model` line under a message heading
+fn message_meta(message: &ChatMessage) -> String {
+    let mut parts = vec![message.timestamp.clone()];
+    if let Some(model) = message.model.as_deref().filter(|m| !m.is_empty()) {
+        parts.push(model.to_string());
+    }
+    parts.join(" · ")
+}
+
+fn render_markdown(session: &ChatSession) -> String {
+    let mut out = format!("# {}\n\n", session_title(session));
+    for (key, value) in session_metadata(session) {
+        out.push_str(&format!("- **{}:** `{}`\n", key, value));
+    }
+
+    for message in &session.messages {
+        out.push_str(&format!(
+            "\n## {}\n\n_{}_\n\n",
+            role_label(&message.role),
+            message_meta(message)
+        ));
+        let content = message.content.trim_end();
+        out.push_str(content);
+        // An unclosed fence would swallow the rest of the transcript
+        if count_fences(content) % 2 == 1 {
+            out.push_str("\n```");
+        }
+        out.push('\n');
+        if let Some(files) = message.files.as_ref().filter(|f| !f.is_empty()) {
+            out.push_str("\nAttached files:\n\n");
+            for file in files {
+                out.push_str(&format!("- `{}`\n", file));
+            }
+        }
+    }
+    out
+}
+
+fn count_fences(content: &str) -> usize {
+    content
+        .lines()
+        .filter(|line| line.trim_start().starts_with("```"))
+        .count()
+}
+
+const HTML_STYLE: &str = "body{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',sans-serif;max-width:860px;margin:2rem auto;padding:0 1rem;color:#1f2328;line-height:1.5}\
+h1{border-bottom:1px solid #d0d7de;padding-bottom:.3rem}\
+dl{display:grid;grid-template-columns:max-content auto;gap:.2rem 1rem;font-size:.9rem;color:#57606a}\
+dt{font-weight:600}dd{margin:0;font-family:monospace}\
+.message{border:1px solid #d0d7de;border-radius:6px;margin:1rem 0;padding:.5rem 1rem}\
+.message.user{background:#f6f8fa}\
+.meta{font-size:.8rem;color:#57606a}\
+pre{background:#0d1117;color:#e6edf3;padding:.75rem;border-radius:6px;overflow-x:auto}\
+.files{font-size:.85rem}";
+
+fn render_html(sessions: &[ChatSession]) -> String {
+    let title = match sessions {
+        [session] => session_title(session),
+        _ => format!("{} chat sessions", sessions.len()),
+    };
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n",
+        escape_html(&title),
+        HTML_STYLE
+    );
+    for session in sessions {
+        out.push_str(&format!(
+            "<section>\n<h1>{}</h1>\n<dl>\n",
+            escape_html(&session_title(session))
+        ));
+        for (key, value) in session_metadata(session) {
+            out.push_str(&format!("<dt>{}</dt><dd>{}</dd>\n", key, escape_html(&value)));
+        }
+        out.push_str("</dl>\n");
+        for message in &session.messages {
+            out.push_str(&format!(
+                "<div class=\"message {}\">\n<h3>{}</h3>\n<div class=\"meta\">{}</div>\n{}",
+                escape_html(&message.role),
+                escape_html(&role_label(&message.role)),
+                escape_html(&message_meta(message)),
+                content_to_html(&message.content)
+            ));
+            if let Some(files) = message.files.as_ref().filter(|f| !f.is_empty()) {
+                out.push_str("<div class=\"files\">Attached files:<ul>\n");
+                for file in files {
+                    out.push_str(&format!("<li><code>{}</code></li>\n", escape_html(file)));
+                }
+                out.push_str("</ul></div>\n");
+            }
+            out.push_str("</div>\n");
+        }
+        out.push_str("</section>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Fenced code blocks become `<pre>`, everything else escaped paragraphs
+fn content_to_html(content: &str) -> String {
+    let mut out = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut code: Option<(String, Vec<&str>)> = None;
+
+    let flush_paragraph = |out: &mut String, paragraph: &mut Vec<&str>| {
+        if !paragraph.is_empty() {
+            let lines: Vec<String> = paragraph.iter().map(|l| escape_html(l)).collect();
+            out.push_str(&format!("<p>{}</p>\n", lines.join("<br>\n")));
+            paragraph.clear();
+        }
+    };
+
+    for line in content.lines() {
+        let fence = line.trim_start().strip_prefix("```");
+        match (&mut code, fence) {
+            (Some((lang, lines)), Some(_)) => {
+                out.push_str(&code_block(lang, lines));
+                code = None;
+            }
+            (Some((_, lines)), None) => lines.push(line),
+            (None, Some(lang)) => {
+                flush_paragraph(&mut out, &mut paragraph);
+                code = Some((lang.trim().to_string(), Vec::new()));
+            }
+            (None, None) if line.trim().is_empty() => flush_paragraph(&mut out, &mut paragraph),
+            (None, None) => paragraph.push(line),
+        }
+    }
+    if let Some((lang, lines)) = &code {
+        out.push_str(&code_block(lang, lines));
+    }
+    flush_paragraph(&mut out, &mut paragraph);
+    out
+}
+
+fn code_block(lang: &str, lines: &[&str]) -> String {
+    let class = if lang.is_empty() {
+        String::new()
+    } else {
+        format!(" class=\"language-{}\"", escape_html(lang))
+    };
+    format!("<pre><code{}>{}</code></pre>\n", class, escape_html(&lines.join("\n")))
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// One dataset record: chat-format `messages` plus per-message details in `metadata`
+fn render_jsonl(session: &ChatSession) -> String {
+    let messages: Vec<&ChatMessage> = session
+        .messages
+        .iter()
+        .filter(|m| matches!(m.role.as_str(), "system" | "user" | "assistant"))
+        .collect();
+    let record = json!({
+        "messages": messages
+            .iter()
+            .map(|m| json!({ "role": m.role, "content": m.content }))
+            .collect::<Vec<_>>(),
+        "metadata": {
+            "schema_version": chat_schema::SCHEMA_VERSION,
+            "session_id": session.id,
+            "name": session.name,
+            "workspace_id": session.workspace_id,
+            "created_at": session.created_at,
+            "updated_at": session.updated_at,
+            "code_cli_task_ids": session.code_cli_task_ids,
+            "messages": messages
+                .iter()
+                .map(|m| json!({
+                    "id": m.id,
+                    "timestamp": m.timestamp,
+                    "model": m.model,
+                    "files": m.files,
+                }))
+                .collect::<Vec<_>>(),
+        },
+    });
+    record.to_string()
+}
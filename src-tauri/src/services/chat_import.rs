@@ -0,0 +1,582 @@
+//! Chat session import
+//!
+//! Imports transcripts written by other agent tools into `ChatSession` records of
+//! a chosen workspace:
+//! - Claude Code: `~/.claude/projects/<project>/<session>.jsonl`
+//! - Codex CLI: `~/.codex/sessions/YYYY/MM/DD/rollout-*.jsonl`
+//! - Gemini CLI: `~/.gemini/tmp/<project>/chats/session-*.json` and `logs.json`
+//! - our own JSONL export (see `chat_export`)
+//!
+//! The source's resumable session id becomes the session id and is stored in
+//! `code_cli_task_ids` under the code CLI name, so the conversation can be
+//! continued from this app. Importing a session already in the workspace is a
+//! no-op, except that a full transcript replaces one holding only user prompts
+//! (Gemini's `logs.json`).
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use ignore::WalkBuilder;
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{debug, info, warn};
+
+use super::chat_schema;
+use super::chat_session::{self, ChatMessage, ChatSession, RunStats};
+use crate::database::repositories::workspace_repository::WorkspaceRepository;
+
+/// Transcript format of an import
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportSource {
+    ClaudeCode,
+    Codex,
+    Gemini,
+    /// Our own JSONL export, or any `{"messages": [...]}` per line
+    Jsonl,
+}
+
+impl ImportSource {
+    /// Code CLI name the resumable session id is stored under by default
+    fn default_code_cli(&self) -> Option<&'static str> {
+        match self {
+            ImportSource::ClaudeCode => Some("claude-cli"),
+            ImportSource::Codex => Some("codex-cli"),
+            ImportSource::Gemini => Some("gemini-cli"),
+            ImportSource::Jsonl => None,
+        }
+    }
+}
+
+/// A file that could not be imported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportFailure {
+    pub path: String,
+    pub message: String,
+}
+
+/// Outcome of an import
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    /// Ids of the sessions that were created
+    pub imported: Vec<String>,
+    /// Sessions already in the workspace
+    pub skipped: usize,
+    pub failed: Vec<ImportFailure>,
+}
+
+/// A transcript parsed from a source file
+#[derive(Debug, Clone, Default)]
+struct ParsedSession {
+    /// Resumable session id of the source tool
+    source_id: Option<String>,
+    name: Option<String>,
+    code_cli_task_ids: HashMap<String, String>,
+    messages: Vec<ParsedMessage>,
+}
+
+#[derive(Debug, Clone)]
+struct ParsedMessage {
+    id: Option<String>,
+    role: String,
+    content: String,
+    timestamp: Option<String>,
+    model: Option<String>,
+    files: Option<Vec<String>>,
+}
+
+/// Import transcripts from `paths` (files or directories) into a workspace.
+///
+/// `source` is detected per file when omitted; `code_cli` overrides the name the
+/// resumable session id is stored under.
+pub async fn import_sessions(
+    db: &DatabaseConnection,
+    paths: &[String],
+    source: Option<ImportSource>,
+    workspace_id: &str,
+    code_cli: Option<&str>,
+) -> Result<ImportReport, String> {
+    let workspace = workspace_id
+        .trim()
+        .parse::<i32>()
+        .map_err(|_| format!("Invalid workspace id: {}", workspace_id))?;
+    if !matches!(WorkspaceRepository::get_by_id(db, &workspace).await, Ok(Some(_))) {
+        return Err(format!("Workspace not found: {}", workspace_id));
+    }
+
+    let mut report = ImportReport::default();
+    for path in collect_files(paths) {
+        let display = path.to_string_lossy().to_string();
+        let parsed = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read file: {}", e))
+            .and_then(|content| parse_file(&path, &content, source, code_cli));
+        let sessions = match parsed {
+            Ok(sessions) => sessions,
+            Err(message) => {
+                warn!("Failed to import {:?}: {}", path, message);
+                report.failed.push(ImportFailure { path: display, message });
+                continue;
+            }
+        };
+
+        for parsed in sessions {
+            if parsed.messages.is_empty() {
+                continue;
+            }
+            let mut session = to_chat_session(parsed, workspace_id);
+            match import_target(db, workspace, &session).await {
+                Ok(Some(id)) => session.id = id,
+                Ok(None) => {
+                    report.skipped += 1;
+                    continue;
+                }
+                Err(message) => {
+                    report.failed.push(ImportFailure {
+                        path: display.clone(),
+                        message,
+                    });
+                    continue;
+                }
+            }
+            match chat_session::store_session(db, &session, Some(workspace)).await {
+                Ok(_) => {
+                    debug!("Imported session {} from {:?}", session.id, path);
+                    report.imported.push(session.id);
+                }
+                Err(message) => report.failed.push(ImportFailure {
+                    path: display.clone(),
+                    message,
+                }),
+            }
+        }
+    }
+
+    info!(
+        "Imported {} sessions ({} already present, {} failures)",
+        report.imported.len(),
+        report.skipped,
+        report.failed.len()
+    );
+    Ok(report)
+}
+
+/// Id to store an imported session under, `None` to skip it.
+///
+/// Duplicates are found by source session id within the workspace, so the same
+/// transcript can be imported into another workspace under a fresh id.
+async fn import_target(
+    db: &DatabaseConnection,
+    workspace: i32,
+    session: &ChatSession,
+) -> Result<Option<String>, String> {
+    let has_replies = |s: &ChatSession| s.messages.iter().any(|m| m.role != "user");
+    if let Some(source_id) = session.session_id.as_deref() {
+        if let Some(existing) = chat_session::find_by_session_id(db, workspace, source_id).await? {
+            // A prompts-only import gives way to the full transcript
+            return Ok((!has_replies(&existing) && has_replies(session)).then_some(existing.id));
+        }
+    }
+    if chat_session::session_exists(db, &session.id).await? {
+        return Ok(Some(uuid::Uuid::new_v4().to_string()));
+    }
+    Ok(Some(session.id.clone()))
+}
+
+/// Files to import: explicit files, plus `.json`/`.jsonl` files under directories
+fn collect_files(paths: &[String]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for path in paths {
+        let path = PathBuf::from(path);
+        if !path.is_dir() {
+            files.push(path);
+            continue;
+        }
+        let mut found: Vec<PathBuf> = WalkBuilder::new(&path)
+            // Tool data lives in dot-directories such as ~/.claude
+            .standard_filters(false)
+            .build()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+            .map(|entry| entry.into_path())
+            .filter(|p| matches!(p.extension().and_then(|e| e.to_str()), Some("json" | "jsonl")))
+            .collect();
+        found.sort();
+        files.extend(found);
+    }
+    files
+}
+
+fn parse_file(
+    path: &Path,
+    content: &str,
+    source: Option<ImportSource>,
+    code_cli: Option<&str>,
+) -> Result<Vec<ParsedSession>, String> {
+    let source = match source {
+        Some(source) => source,
+        None => detect_source(path, content).ok_or("Unrecognized transcript format")?,
+    };
+    let mut sessions = match source {
+        ImportSource::ClaudeCode => vec![parse_claude_code(content)],
+        ImportSource::Codex => vec![parse_codex(content)],
+        ImportSource::Gemini => parse_gemini(content)?,
+        ImportSource::Jsonl => parse_jsonl_export(content)?,
+    };
+
+    if let Some(cli) = code_cli.or(source.default_code_cli()) {
+        for session in &mut sessions {
+            if let Some(id) = &session.source_id {
+                session.code_cli_task_ids.insert(cli.to_string(), id.clone());
+            }
+        }
+    }
+    Ok(sessions)
+}
+
+/// Guess the format from the extension and the first records
+fn detect_source(path: &Path, content: &str) -> Option<ImportSource> {
+    if path.extension().and_then(|e| e.to_str()) == Some("json") {
+        return Some(ImportSource::Gemini);
+    }
+    for line in content.lines().take(20) {
+        let Ok(value) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        if value.get("messages").is_some_and(Value::is_array) {
+            return Some(ImportSource::Jsonl);
+        }
+        if value.get("sessionId").is_some() || value.get("type").and_then(Value::as_str) == Some("summary") {
+            return Some(ImportSource::ClaudeCode);
+        }
+        let kind = value.get("type").and_then(Value::as_str);
+        if value.get("payload").is_some()
+            || matches!(kind, Some("session_meta" | "response_item" | "message"))
+            || value.get("instructions").is_some()
+        {
+            return Some(ImportSource::Codex);
+        }
+    }
+    None
+}
+
+fn str_field(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+/// Text of a message `content`: a string, or the text blocks of an array
+fn content_text(content: &Value, block_types: &[&str]) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter(|b| {
+                b.get("type")
+                    .and_then(Value::as_str)
+                    .is_some_and(|t| block_types.contains(&t))
+            })
+            .filter_map(|b| b.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        _ => String::new(),
+    }
+}
+
+/// Append a message, merging consecutive assistant chunks into one message
+fn push_message(messages: &mut Vec<ParsedMessage>, message: ParsedMessage) {
+    if let Some(last) = messages.last_mut() {
+        if last.role == "assistant" && message.role == "assistant" {
+            last.content.push_str("\n\n");
+            last.content.push_str(&message.content);
+            if last.model.is_none() {
+                last.model = message.model;
+            }
+            return;
+        }
+    }
+    messages.push(message);
+}
+
+fn parse_claude_code(content: &str) -> ParsedSession {
+    let mut session = ParsedSession::default();
+    for line in content.lines() {
+        let Ok(entry) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        if session.source_id.is_none() {
+            session.source_id = str_field(&entry, "sessionId");
+        }
+        let kind = entry.get("type").and_then(Value::as_str).unwrap_or_default();
+        if kind == "summary" {
+            session.name = session.name.or_else(|| str_field(&entry, "summary"));
+            continue;
+        }
+        if !matches!(kind, "user" | "assistant")
+            || entry.get("isMeta").and_then(Value::as_bool) == Some(true)
+            || entry.get("isSidechain").and_then(Value::as_bool) == Some(true)
+        {
+            continue;
+        }
+        let Some(message) = entry.get("message") else {
+            continue;
+        };
+        // Tool calls and results are not part of the conversation text
+        let text = content_text(message.get("content").unwrap_or(&Value::Null), &["text"]);
+        let text = text.trim();
+        if text.is_empty() || text.starts_with("<command-") || text.starts_with("<local-command-") {
+            continue;
+        }
+        push_message(
+            &mut session.messages,
+            ParsedMessage {
+                id: str_field(&entry, "uuid"),
+                role: kind.to_string(),
+                content: text.to_string(),
+                timestamp: str_field(&entry, "timestamp"),
+                model: str_field(message, "model"),
+                files: None,
+            },
+        );
+    }
+    session
+}
+
+fn parse_codex(content: &str) -> ParsedSession {
+    let mut session = ParsedSession::default();
+    let mut model: Option<String> = None;
+    for line in content.lines() {
+        let Ok(entry) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        let kind = entry.get("type").and_then(Value::as_str);
+        // Newer rollouts wrap records in `payload`; older ones are flat
+        let payload = entry.get("payload").unwrap_or(&entry);
+        match kind {
+            Some("session_meta") => {
+                session.source_id = session.source_id.or_else(|| str_field(payload, "id"));
+                continue;
+            }
+            Some("turn_context") => {
+                model = str_field(payload, "model").or(model);
+                continue;
+            }
+            None if entry.get("instructions").is_some() => {
+                session.source_id = session.source_id.or_else(|| str_field(&entry, "id"));
+                continue;
+            }
+            _ => {}
+        }
+        if payload.get("type").and_then(Value::as_str) != Some("message") {
+            continue;
+        }
+        let role = payload.get("role").and_then(Value::as_str).unwrap_or_default();
+        if !matches!(role, "user" | "assistant") {
+            continue;
+        }
+        let text = content_text(
+            payload.get("content").unwrap_or(&Value::Null),
+            &["input_text", "output_text", "text"],
+        );
+        let text = text.trim();
+        // Injected context, not typed by the user
+        if text.is_empty()
+            || text.starts_with("<environment_context>")
+            || text.starts_with("<user_instructions>")
+            || text.starts_with("# AGENTS.md")
+        {
+            continue;
+        }
+        push_message(
+            &mut session.messages,
+            ParsedMessage {
+                id: str_field(payload, "id"),
+                role: role.to_string(),
+                content: text.to_string(),
+                timestamp: str_field(&entry, "timestamp"),
+                model: if role == "assistant" { model.clone() } else { None },
+                files: None,
+            },
+        );
+    }
+    session
+}
+
+fn parse_gemini(content: &str) -> Result<Vec<ParsedSession>, String> {
+    let value: Value = serde_json::from_str(content).map_err(|e| format!("Invalid JSON: {}", e))?;
+
+    // `logs.json`: user prompts of every session in the project
+    if let Value::Array(entries) = &value {
+        let mut sessions: Vec<ParsedSession> = Vec::new();
+        for entry in entries {
+            let text = str_field(entry, "message").unwrap_or_default();
+            if text.trim().is_empty() || entry.get("type").and_then(Value::as_str) != Some("user") {
+                continue;
+            }
+            let id = str_field(entry, "sessionId");
+            let index = match sessions.iter().position(|s| s.source_id == id) {
+                Some(index) => index,
+                None => {
+                    sessions.push(ParsedSession {
+                        source_id: id,
+                        ..Default::default()
+                    });
+                    sessions.len() - 1
+                }
+            };
+            sessions[index].messages.push(ParsedMessage {
+                id: None,
+                role: "user".to_string(),
+                content: text,
+                timestamp: str_field(entry, "timestamp"),
+                model: None,
+                files: None,
+            });
+        }
+        return Ok(sessions);
+    }
+
+    // `chats/session-*.json`: the full conversation
+    let mut session = ParsedSession {
+        source_id: str_field(&value, "sessionId"),
+        ..Default::default()
+    };
+    for message in value.get("messages").and_then(Value::as_array).into_iter().flatten() {
+        let role = match message.get("type").and_then(Value::as_str) {
+            Some("user") => "user",
+            Some("gemini" | "model" | "assistant") => "assistant",
+            _ => continue,
+        };
+        let text = match message.get("content") {
+            Some(Value::Array(parts)) => parts
+                .iter()
+                .filter_map(|p| p.get("text").and_then(Value::as_str))
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+            Some(other) => content_text(other, &["text"]),
+            None => String::new(),
+        };
+        if text.trim().is_empty() {
+            continue;
+        }
+        push_message(
+            &mut session.messages,
+            ParsedMessage {
+                id: str_field(message, "id"),
+                role: role.to_string(),
+                content: text,
+                timestamp: str_field(message, "timestamp"),
+                model: str_field(message, "model"),
+                files: None,
+            },
+        );
+    }
+    Ok(vec![session])
+}
+
+fn parse_jsonl_export(content: &str) -> Result<Vec<ParsedSession>, String> {
+    let mut sessions = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: Value =
+            serde_json::from_str(line).map_err(|e| format!("line {}: invalid JSON: {}", index + 1, e))?;
+        let metadata = record.get("metadata").cloned().unwrap_or(Value::Null);
+        if let Some(metadata) = metadata.as_object() {
+            chat_schema::document_version(metadata).map_err(|e| format!("line {}: {}", index + 1, e))?;
+        }
+        let details = metadata.get("messages").and_then(Value::as_array);
+
+        let mut session = ParsedSession {
+            source_id: str_field(&metadata, "session_id"),
+            name: str_field(&metadata, "name"),
+            code_cli_task_ids: metadata
+                .get("code_cli_task_ids")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+        for (i, message) in record
+            .get("messages")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            let detail = details.and_then(|d| d.get(i)).unwrap_or(&Value::Null);
+            session.messages.push(ParsedMessage {
+                id: str_field(detail, "id"),
+                role: str_field(message, "role").unwrap_or_else(|| "user".to_string()),
+                content: content_text(message.get("content").unwrap_or(&Value::Null), &["text"]),
+                timestamp: str_field(detail, "timestamp"),
+                model: str_field(detail, "model"),
+                files: detail.get("files").and_then(|v| serde_json::from_value(v.clone()).ok()),
+            });
+        }
+        sessions.push(session);
+    }
+    Ok(sessions)
+}
+
+fn to_chat_session(parsed: ParsedSession, workspace_id: &str) -> ChatSession {
+    let id = parsed
+        .source_id
+        .clone()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let now = Local::now().to_rfc3339();
+    // With a single CLI session its replies can be resumed per branch
+    let source_task = match parsed.code_cli_task_ids.iter().collect::<Vec<_>>()[..] {
+        [(cli, task_id)] => Some((cli.clone(), task_id.clone())),
+        _ => None,
+    };
+    let messages: Vec<ChatMessage> = parsed
+        .messages
+        .into_iter()
+        .map(|m| {
+            let task = source_task.clone().filter(|_| m.role == "assistant");
+            ChatMessage {
+                id: m.id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+                role: m.role,
+                content: m.content,
+                timestamp: m.timestamp.unwrap_or_else(|| now.clone()),
+                files: m.files,
+                model: m.model,
+                session_id: Some(id.clone()),
+                workspace_id: Some(workspace_id.to_string()),
+                parent_id: None,
+                code_cli: task.as_ref().map(|(cli, _)| cli.clone()),
+                code_cli_task_id: task.map(|(_, task_id)| task_id),
+                run: None,
+            }
+        })
+        .collect();
+    let created_at = messages
+        .first()
+        .map(|m| m.timestamp.clone())
+        .unwrap_or_else(|| now.clone());
+    let updated_at = messages.last().map(|m| m.timestamp.clone()).unwrap_or(now);
+
+    ChatSession {
+        session_id: Some(id.clone()),
+        id,
+        name: parsed.name,
+        workspace_id: Some(workspace_id.to_string()),
+        message_count: messages.len(),
+        first_message_preview: String::new(),
+        messages,
+        created_at,
+        updated_at,
+        code_cli_task_ids: parsed.code_cli_task_ids,
+        active_leaf_id: None,
+        branch_count: 1,
+        run_stats: RunStats::default(),
+        tags: Vec::new(),
+        pinned: false,
+        archived_at: None,
+        summary: None,
+        summary_through_id: None,
+        schema_version: chat_schema::SCHEMA_VERSION,
+    }
+}
@@ -0,0 +1,121 @@
+//! Chat session retention
+//!
+//! An optional policy archives sessions nobody touched for a while and deletes
+//! sessions that stayed archived for a while. Pinned sessions are never archived
+//! automatically. The policy is stored in the settings table and applied at
+//! startup and then periodically in the background.
+
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info};
+
+use super::chat_session;
+use crate::database::repositories::conversion_repository::ConversionRepository;
+use crate::database::repositories::settings_repository::SettingsRepository;
+
+/// Settings key of the retention policy (JSON `RetentionPolicy`)
+pub const RETENTION_POLICY_KEY: &str = "chat.retention_policy";
+/// Settings category of chat settings
+pub const CHAT_SETTINGS_CATEGORY: &str = "chat";
+
+/// How often the background task applies the policy
+const RETENTION_INTERVAL: StdDuration = StdDuration::from_secs(6 * 60 * 60);
+
+/// Retention policy; each rule is off when `None`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Archive sessions not updated for this many days
+    pub archive_after_days: Option<u32>,
+    /// Delete sessions archived this many days ago
+    pub delete_archived_after_days: Option<u32>,
+}
+
+/// What one application of the policy changed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RetentionReport {
+    pub archived: u64,
+    pub deleted: usize,
+}
+
+/// Load the retention policy (off when never saved)
+pub async fn load_policy(db: &DatabaseConnection) -> Result<RetentionPolicy, String> {
+    let setting = SettingsRepository::get_by_key(db, RETENTION_POLICY_KEY)
+        .await
+        .map_err(|e| format!("Failed to load retention policy: {}", e))?;
+    match setting {
+        Some(setting) => serde_json::from_str(&setting.value).map_err(|e| format!("Invalid retention policy: {}", e)),
+        None => Ok(RetentionPolicy::default()),
+    }
+}
+
+/// Validate and persist the retention policy
+pub async fn save_policy(db: &DatabaseConnection, policy: &RetentionPolicy) -> Result<(), String> {
+    if policy.archive_after_days == Some(0) || policy.delete_archived_after_days == Some(0) {
+        return Err("Retention periods must be at least one day".to_string());
+    }
+    let value = serde_json::to_string(policy).map_err(|e| e.to_string())?;
+    SettingsRepository::upsert(
+        db,
+        RETENTION_POLICY_KEY,
+        &value,
+        CHAT_SETTINGS_CATEGORY,
+        Some("Chat session retention policy"),
+    )
+    .await
+    .map_err(|e| format!("Failed to save retention policy: {}", e))?;
+    Ok(())
+}
+
+/// Archive and delete sessions as of `now`
+pub async fn apply_policy(
+    db: &DatabaseConnection,
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+) -> Result<RetentionReport, String> {
+    let mut report = RetentionReport::default();
+
+    if let Some(days) = policy.archive_after_days {
+        report.archived = ConversionRepository::archive_inactive(db, now - Duration::days(days.into()), now)
+            .await
+            .map_err(|e| format!("Failed to archive sessions: {}", e))?;
+    }
+
+    if let Some(days) = policy.delete_archived_after_days {
+        let expired = ConversionRepository::list_archived_before(db, now - Duration::days(days.into()))
+            .await
+            .map_err(|e| format!("Failed to list archived sessions: {}", e))?;
+        for session_id in expired {
+            // Through the session service so a concurrent write finishes first
+            chat_session::delete_session(db, &session_id).await?;
+            report.deleted += 1;
+        }
+    }
+
+    if report.archived > 0 || report.deleted > 0 {
+        info!(
+            archived = report.archived,
+            deleted = report.deleted,
+            "Applied chat retention policy"
+        );
+    }
+    Ok(report)
+}
+
+/// Apply the stored policy now and then every `RETENTION_INTERVAL`; never returns
+pub async fn run(db: DatabaseConnection) {
+    let mut interval = tokio::time::interval(RETENTION_INTERVAL);
+    loop {
+        interval.tick().await;
+        debug!("Running chat retention");
+        let result = match load_policy(&db).await {
+            Ok(policy) => apply_policy(&db, &policy, Utc::now()).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            error!("Chat retention failed: {}", e);
+        }
+    }
+}
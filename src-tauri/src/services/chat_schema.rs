@@ -0,0 +1,131 @@
+//! Versioned chat session documents
+//!
+//! Sessions leave the database as `ChatSession` JSON: the session files of older
+//! versions, `save_chat_session` payloads and the metadata of JSONL exports. Each
+//! document carries a `schema_version`; older documents are upgraded one version
+//! at a time before they are deserialized, so renamed or added fields are carried
+//! over instead of failing to parse. Documents without a version are version 0.
+//! The database tables themselves are versioned by `crate::migration`.
+
+use chrono::Local;
+use serde_json::{Map, Value};
+
+use super::chat_session::ChatSession;
+
+/// Version written by this build
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Characters of the first message kept as preview
+const PREVIEW_CHARS: usize = 100;
+
+type Upgrade = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+/// `UPGRADES[n]` turns a version `n` document into version `n + 1`
+const UPGRADES: [Upgrade; SCHEMA_VERSION as usize] = [upgrade_v0, upgrade_v1];
+
+/// Parse a session document of any supported version.
+///
+/// The flag tells whether the document was upgraded (and is worth writing back).
+pub fn parse_session(json: &str) -> Result<(ChatSession, bool), String> {
+    let value: Value = serde_json::from_str(json).map_err(|e| format!("Invalid session JSON: {}", e))?;
+    let (value, upgraded) = upgrade(value)?;
+    let session = serde_json::from_value(value).map_err(|e| format!("Invalid session: {}", e))?;
+    Ok((session, upgraded))
+}
+
+/// Upgrade a session document to `SCHEMA_VERSION`
+pub fn upgrade(value: Value) -> Result<(Value, bool), String> {
+    let Value::Object(mut session) = value else {
+        return Err("Session is not a JSON object".to_string());
+    };
+    let version = document_version(&session)?;
+    for step in &UPGRADES[version as usize..] {
+        step(&mut session)?;
+    }
+    session.insert("schema_version".to_string(), SCHEMA_VERSION.into());
+    Ok((Value::Object(session), version < SCHEMA_VERSION))
+}
+
+/// `schema_version` of a document, rejecting versions newer than this build
+pub fn document_version(document: &Map<String, Value>) -> Result<u32, String> {
+    let version = match document.get("schema_version") {
+        None | Some(Value::Null) => 0,
+        Some(value) => value
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| format!("Invalid schema_version: {}", value))?,
+    };
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "Session schema version {} is newer than the supported version {}",
+            version, SCHEMA_VERSION
+        ));
+    }
+    Ok(version)
+}
+
+/// v0: the CLI session id was stored as `codeagent_session_id`, and summary
+/// fields and message ids were not always written
+fn upgrade_v0(session: &mut Map<String, Value>) -> Result<(), String> {
+    if let Some(old) = session.remove("codeagent_session_id") {
+        if session.get("session_id").is_none_or(Value::is_null) {
+            session.insert("session_id".to_string(), old);
+        }
+    }
+
+    let now = Local::now().to_rfc3339();
+    let messages = messages_mut(session)?;
+    for message in messages.iter_mut() {
+        let message = message.as_object_mut().ok_or("Message is not a JSON object")?;
+        if message.get("id").is_none_or(Value::is_null) {
+            message.insert("id".to_string(), uuid::Uuid::new_v4().to_string().into());
+        }
+        message.entry("timestamp").or_insert_with(|| now.clone().into());
+    }
+    let message_count = messages.len();
+    let first_timestamp = messages.first().and_then(|m| m.get("timestamp")).cloned();
+    let last_timestamp = messages.last().and_then(|m| m.get("timestamp")).cloned();
+    let preview: String = messages
+        .first()
+        .and_then(|m| m.get("content"))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .chars()
+        .take(PREVIEW_CHARS)
+        .collect();
+
+    session.entry("message_count").or_insert(message_count.into());
+    session.entry("first_message_preview").or_insert(preview.into());
+    session
+        .entry("created_at")
+        .or_insert_with(|| first_timestamp.unwrap_or_else(|| now.clone().into()));
+    session
+        .entry("updated_at")
+        .or_insert_with(|| last_timestamp.unwrap_or_else(|| now.into()));
+    Ok(())
+}
+
+/// v1: messages were a flat list; they now form a tree through `parent_id`
+fn upgrade_v1(session: &mut Map<String, Value>) -> Result<(), String> {
+    let mut previous: Option<Value> = None;
+    for message in messages_mut(session)?.iter_mut() {
+        let message = message.as_object_mut().ok_or("Message is not a JSON object")?;
+        let id = message.get("id").cloned();
+        message
+            .entry("parent_id")
+            .or_insert(previous.take().unwrap_or(Value::Null));
+        previous = id;
+    }
+    if session.get("active_leaf_id").is_none_or(Value::is_null) {
+        session.insert("active_leaf_id".to_string(), previous.unwrap_or(Value::Null));
+    }
+    Ok(())
+}
+
+fn messages_mut(session: &mut Map<String, Value>) -> Result<&mut Vec<Value>, String> {
+    session
+        .entry("messages")
+        .or_insert_with(|| Value::Array(Vec::new()))
+        .as_array_mut()
+        .ok_or_else(|| "Session messages are not a JSON array".to_string())
+}
@@ -0,0 +1,224 @@
+//! Chat history search
+//!
+//! Searches message content, attached file paths and session names. Messages are
+//! matched through the `conversion_message_fts` trigram index and ranked by bm25;
+//! terms shorter than three characters (which the trigram index cannot match) fall
+//! back to a substring scan ordered by recency. Snippets are cut around the first
+//! match and carry the character ranges to highlight.
+
+use chrono::Local;
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::database::repositories::conversion_repository::{ConversionRepository, MessageSearchRow};
+
+/// Default number of hits returned
+const DEFAULT_LIMIT: usize = 50;
+/// Characters shown around a match
+const SNIPPET_CHARS: usize = 160;
+/// Characters kept before the first match
+const SNIPPET_LEAD: usize = 40;
+/// Shortest term the trigram index can match
+const MIN_TRIGRAM_CHARS: usize = 3;
+
+/// Where a hit was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchField {
+    Name,
+    Content,
+    Files,
+}
+
+/// One search result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatSearchHit {
+    pub session_id: String,
+    pub session_name: Option<String>,
+    pub workspace_id: Option<String>,
+    /// Message to jump to; `None` for session name hits
+    pub message_id: Option<String>,
+    pub role: Option<String>,
+    pub field: SearchField,
+    pub snippet: String,
+    /// `[start, end)` character offsets into `snippet` to highlight
+    pub highlights: Vec<(usize, usize)>,
+    /// Message timestamp, or the session's last update for name hits
+    pub timestamp: String,
+}
+
+/// Search chat history, optionally scoped to a workspace.
+///
+/// Session name hits come first, then message hits from best to worst match.
+pub async fn search_sessions(
+    db: &DatabaseConnection,
+    query: &str,
+    workspace_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<ChatSearchHit>, String> {
+    let terms = parse_terms(query);
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+    let workspace = match workspace_id.as_deref().map(str::trim).filter(|id| !id.is_empty()) {
+        Some(id) => Some(id.parse::<i32>().map_err(|_| format!("Invalid workspace id: {}", id))?),
+        None => None,
+    };
+    let limit = limit.unwrap_or(DEFAULT_LIMIT) as u64;
+
+    let mut hits = Vec::new();
+
+    let names = ConversionRepository::search_names(db, &terms, workspace, limit)
+        .await
+        .map_err(|e| format!("Failed to search sessions: {}", e))?;
+    for conversion in names {
+        let name = conversion.name.clone().unwrap_or_default();
+        let (snippet, highlights) = snippet(&name, &terms);
+        hits.push(ChatSearchHit {
+            session_id: conversion.conversion_id,
+            session_name: conversion.name,
+            workspace_id: conversion.workspace_id.map(|id| id.to_string()),
+            message_id: None,
+            role: None,
+            field: SearchField::Name,
+            snippet,
+            highlights,
+            timestamp: conversion.updated_at.with_timezone(&Local).to_rfc3339(),
+        });
+    }
+
+    let rows = if terms.iter().all(|t| t.chars().count() >= MIN_TRIGRAM_CHARS) {
+        ConversionRepository::search_messages(db, &fts_query(&terms), workspace, limit).await
+    } else {
+        ConversionRepository::search_messages_like(db, &terms, workspace, limit).await
+    }
+    .map_err(|e| format!("Failed to search messages: {}", e))?;
+    hits.extend(rows.into_iter().map(|row| message_hit(row, &terms)));
+    hits.truncate(limit as usize);
+
+    debug!("Search {:?} returned {} hits", query, hits.len());
+    Ok(hits)
+}
+
+/// Split a query into lowercase terms; `"quoted phrases"` stay together
+fn parse_terms(query: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    for (i, part) in query.split('"').enumerate() {
+        if i % 2 == 1 {
+            let phrase = part.trim();
+            if !phrase.is_empty() {
+                terms.push(phrase.to_lowercase());
+            }
+        } else {
+            terms.extend(part.split_whitespace().map(str::to_lowercase));
+        }
+    }
+    terms
+}
+
+/// FTS5 expression requiring every term, each quoted so operators are not interpreted
+fn fts_query(terms: &[String]) -> String {
+    terms
+        .iter()
+        .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn message_hit(row: MessageSearchRow, terms: &[String]) -> ChatSearchHit {
+    let files = row
+        .files
+        .as_deref()
+        .and_then(|json| serde_json::from_str::<Vec<String>>(json).ok())
+        .map(|files| files.join(", "))
+        .unwrap_or_default();
+    let in_content = contains_any(&row.content, terms);
+    let (field, text) = if in_content || !contains_any(&files, terms) {
+        (SearchField::Content, row.content.as_str())
+    } else {
+        (SearchField::Files, files.as_str())
+    };
+    let (snippet, highlights) = snippet(text, terms);
+
+    ChatSearchHit {
+        session_id: row.conversion_id,
+        session_name: row.name,
+        workspace_id: row.workspace_id.map(|id| id.to_string()),
+        message_id: Some(row.message_id),
+        role: Some(row.role),
+        field,
+        snippet,
+        highlights,
+        timestamp: row.created_at.with_timezone(&Local).to_rfc3339(),
+    }
+}
+
+/// Lowercase per character, so offsets into the result match offsets into `text`
+fn fold(text: &str) -> Vec<char> {
+    text.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect()
+}
+
+fn contains_any(text: &str, terms: &[String]) -> bool {
+    let folded = fold(text);
+    terms.iter().any(|t| !find_all(&folded, &fold(t)).is_empty())
+}
+
+/// Start offsets of every occurrence of `needle` in `haystack`
+fn find_all(haystack: &[char], needle: &[char]) -> Vec<usize> {
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return Vec::new();
+    }
+    (0..=haystack.len() - needle.len())
+        .filter(|&i| haystack[i..i + needle.len()] == *needle)
+        .collect()
+}
+
+/// Cut `text` around the first match and locate every match inside the cut
+fn snippet(text: &str, terms: &[String]) -> (String, Vec<(usize, usize)>) {
+    let chars: Vec<char> = text.chars().collect();
+    let folded = fold(text);
+
+    let mut matches: Vec<(usize, usize)> = terms
+        .iter()
+        .flat_map(|term| {
+            let needle = fold(term);
+            find_all(&folded, &needle)
+                .into_iter()
+                .map(move |start| (start, start + needle.len()))
+        })
+        .collect();
+    matches.sort();
+
+    let first = matches.first().map(|m| m.0).unwrap_or(0);
+    let start = first.saturating_sub(SNIPPET_LEAD);
+    let end = (start + SNIPPET_CHARS).min(chars.len());
+
+    let mut snippet = String::new();
+    let prefix = if start > 0 { 1 } else { 0 };
+    if start > 0 {
+        snippet.push('…');
+    }
+    // Newlines become spaces one-for-one, keeping offsets intact
+    snippet.extend(
+        chars[start..end]
+            .iter()
+            .map(|c| if c.is_whitespace() { ' ' } else { *c }),
+    );
+    if end < chars.len() {
+        snippet.push('…');
+    }
+
+    let mut highlights: Vec<(usize, usize)> = Vec::new();
+    for (s, e) in matches {
+        if s < start || s >= end {
+            continue;
+        }
+        let range = (s - start + prefix, e.min(end) - start + prefix);
+        match highlights.last_mut() {
+            Some(last) if range.0 <= last.1 => last.1 = last.1.max(range.1),
+            _ => highlights.push(range),
+        }
+    }
+    (snippet, highlights)
+}
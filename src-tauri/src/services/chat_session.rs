@@ -1,14 +1,30 @@
 //! Chat session management service
 //!
-//! This module provides functions for managing chat sessions stored as JSON files.
+//! This module provides functions for managing chat sessions stored in the
+//! `conversion` and `conversion_message` tables. Sessions written by older
+//! versions as JSON files under `chat-sessions/` are imported once at startup.
 
+use chrono::{DateTime, Local, Utc};
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
-use serde::{Deserialize, Serialize};
-use chrono::Local;
-use uuid::Uuid;
-use tracing::{info, warn, debug, error};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+use tracing::{debug, error, info, warn};
+
+use super::ai::{RunMetadata, TokenUsage};
+use super::chat_branch::{self, ChatBranch};
+use super::chat_schema;
+use super::chat_summary;
+use crate::database::models::conversion::Model as ConversionModel;
+use crate::database::models::conversion_message::Model as ConversionMessageModel;
+use crate::database::models::message_run::Model as MessageRunModel;
+use crate::database::repositories::conversion_repository::{
+    ConversionFilter, ConversionRecord, ConversionRepository, MessageRecord, RunRecord,
+};
+use crate::database::repositories::workspace_repository::WorkspaceRepository;
 
 /// Chat message structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +37,17 @@ pub struct ChatMessage {
     pub model: Option<String>,
     pub session_id: Option<String>,
     pub workspace_id: Option<String>,
+    /// Previous message on this message's branch
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    /// Code CLI that produced an assistant message, with its CLI session id
+    #[serde(default)]
+    pub code_cli: Option<String>,
+    #[serde(default)]
+    pub code_cli_task_id: Option<String>,
+    /// How an assistant message was produced
+    #[serde(default)]
+    pub run: Option<RunMetadata>,
 }
 
 /// Chat session structure
@@ -31,207 +58,496 @@ pub struct ChatSession {
     #[serde(default)]
     pub session_id: Option<String>,
     pub workspace_id: Option<String>,
+    /// Messages of the active branch
     pub messages: Vec<ChatMessage>,
     pub created_at: String,
     pub updated_at: String,
     pub message_count: usize,
     pub first_message_preview: String,
+    /// Resume ids valid for the active branch
     #[serde(default)]
     pub code_cli_task_ids: HashMap<String, String>,
+    /// Last message of the active branch
+    #[serde(default)]
+    pub active_leaf_id: Option<String>,
+    #[serde(default)]
+    pub branch_count: usize,
+    /// Runs of all branches
+    #[serde(default)]
+    pub run_stats: RunStats,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Pinned sessions are listed first and never archived automatically
+    #[serde(default)]
+    pub pinned: bool,
+    /// When the session was archived
+    #[serde(default)]
+    pub archived_at: Option<String>,
+    /// Rolling summary of the messages up to `summary_through_id`, see `chat_summary`
+    #[serde(default)]
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub summary_through_id: Option<String>,
+    /// Document version, see `chat_schema`
+    #[serde(default)]
+    pub schema_version: u32,
 }
 
-/// Get the chat sessions directory path
-fn get_sessions_dir() -> Result<PathBuf, String> {
-    let home = crate::config::get_default_data_dir().map_err(|e| format!("Failed to get home directory: {}", e))?;
-    let sessions_dir = PathBuf::from(home).join("chat-sessions");
-    Ok(sessions_dir)
+/// A session as listed, read from its `conversion` row without loading messages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatSessionSummary {
+    pub id: String,
+    pub name: Option<String>,
+    pub session_id: Option<String>,
+    pub workspace_id: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    /// Messages of all branches
+    pub message_count: usize,
+    pub first_message_preview: String,
+    pub tags: Vec<String>,
+    pub pinned: bool,
+    pub archived_at: Option<String>,
 }
 
-/// Ensure the sessions directory exists
-fn ensure_sessions_dir_exists() -> Result<PathBuf, String> {
-    let dir = get_sessions_dir()?;
+/// Run metadata aggregated over the messages of a session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunStats {
+    pub runs: usize,
+    pub failed_runs: usize,
+    pub cancelled_runs: usize,
+    pub total_duration_ms: u64,
+    pub max_duration_ms: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
 
-    if !dir.exists() {
-        info!("Creating chat sessions directory: {:?}", dir);
-        fs::create_dir_all(&dir)
-            .map_err(|e| format!("Failed to create sessions directory: {}", e))?;
+impl RunStats {
+    pub fn from_messages<'a>(messages: impl IntoIterator<Item = &'a ChatMessage>) -> Self {
+        let mut stats = Self::default();
+        for run in messages.into_iter().filter_map(|m| m.run.as_ref()) {
+            stats.runs += 1;
+            stats.failed_runs += usize::from(run.error.is_some());
+            stats.cancelled_runs += usize::from(run.cancelled);
+            stats.total_duration_ms += run.duration_ms;
+            stats.max_duration_ms = stats.max_duration_ms.max(run.duration_ms);
+            if let Some(usage) = run.usage {
+                stats.input_tokens += usage.input_tokens;
+                stats.output_tokens += usage.output_tokens;
+            }
+        }
+        stats
     }
+}
 
-    Ok(dir)
+/// History and resume ids of the branch a new message continues
+#[derive(Debug, Clone, Default)]
+pub struct BranchContext {
+    /// Summary of the branch's earlier messages
+    pub summary: Option<String>,
+    /// Messages after the summary (or from the root) up to the new message's parent
+    pub history: Vec<ChatMessage>,
+    pub code_cli_task_ids: HashMap<String, String>,
 }
 
-/// Load a single session by ID
-fn load_session_by_id(session_id: &str) -> Result<ChatSession, String> {
-    let dir = get_sessions_dir()?;
-    let file_path = dir.join(format!("{}.json", session_id));
+/// Which sessions `load_all_sessions` returns
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionFilter {
+    /// Only archived sessions instead of only unarchived ones
+    #[serde(default)]
+    pub archived: bool,
+    pub pinned: Option<bool>,
+    /// Tags a session must all carry
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
 
-    if !file_path.exists() {
-        return Err(format!("Session not found: {}", session_id));
-    }
+/// Default page size when listing sessions
+const DEFAULT_PAGE_SIZE: u64 = 50;
 
-    debug!("Loading session from: {:?}", file_path);
+fn session_locks() -> &'static Mutex<HashMap<String, Weak<AsyncMutex<()>>>> {
+    static LOCKS: OnceLock<Mutex<HashMap<String, Weak<AsyncMutex<()>>>>> = OnceLock::new();
+    LOCKS.get_or_init(|| Mutex::new(HashMap::new()))
+}
 
-    let content = fs::read_to_string(&file_path)
-        .map_err(|e| format!("Failed to read session file: {}", e))?;
+/// Hold the write lock of a session.
+///
+/// Writes read the session before changing it (an append attaches to the active
+/// leaf), so two streams finishing at once would otherwise overwrite each other.
+async fn lock_session(session_id: &str) -> OwnedMutexGuard<()> {
+    let lock = {
+        let mut locks = session_locks().lock().unwrap();
+        locks.retain(|_, lock| lock.strong_count() > 0);
+        match locks.get(session_id).and_then(Weak::upgrade) {
+            Some(lock) => lock,
+            None => {
+                let lock = Arc::new(AsyncMutex::new(()));
+                locks.insert(session_id.to_string(), Arc::downgrade(&lock));
+                lock
+            }
+        }
+    };
+    lock.lock_owned().await
+}
 
-    serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse session JSON: {}", e))
+/// Directory used by the JSON session store of older versions
+fn get_legacy_sessions_dir() -> Result<PathBuf, String> {
+    let home = crate::config::get_default_data_dir().map_err(|e| format!("Failed to get home directory: {}", e))?;
+    let sessions_dir = PathBuf::from(home).join("chat-sessions");
+    Ok(sessions_dir)
+}
+
+/// Load a single session by ID
+pub async fn load_session_by_id(db: &DatabaseConnection, session_id: &str) -> Result<ChatSession, String> {
+    debug!("Loading session: {}", session_id);
+
+    let conversion = ConversionRepository::get_by_conversion_id(db, session_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    let messages = ConversionRepository::get_messages(db, &[conversion.id])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(to_chat_session(conversion, messages))
 }
 
-/// Save a chat session to file
-pub fn save_session(
+/// Save a chat session, merging `messages` into it by message id.
+///
+/// `messages` is usually the active branch only: messages of other branches, and
+/// the resume ids and runs the frontend does not send back, are kept.
+pub async fn save_session(
+    db: &DatabaseConnection,
     session_id: Option<String>,
     name: Option<String>,
     workspace_id: Option<String>,
     messages: Vec<ChatMessage>,
     code_cli_task_ids: Option<HashMap<String, String>>,
 ) -> Result<ChatSession, String> {
-    let dir = ensure_sessions_dir_exists()?;
-
     let session_id = session_id.as_deref().ok_or("Session ID is required")?;
-    let file_path = dir.join(format!("{}.json", session_id));
-
-    info!("Saving chat session: {} (file: {:?})", session_id, file_path);
-
-    // Build session object
-    let now = Local::now().to_rfc3339();
-
-    // Generate first message preview
-    let first_message_preview = messages
-        .first()
-        .map(|m| {
-            let content = &m.content;
-            let mut chars = content.chars();
-            let preview: String = chars.by_ref().take(100).collect();
-            if chars.next().is_some() {
-                format!("{}...", preview)
-            } else {
-                preview
-            }
-        })
-        .unwrap_or_default();
-
-    // Preserve created_at if updating existing session
-    let (created_at, preserved_session_id) = if file_path.exists() {
-        match load_session_by_id(&session_id) {
-            Ok(existing) => {
-                let preserved = existing
-                    .session_id
-                    .clone()
-                    .or_else(|| Some(session_id.to_string()));
-                (existing.created_at, preserved)
-            }
-            Err(_) => {
-                warn!("Failed to load existing session, using current time as created_at");
-                (now.clone(), Some(session_id.to_string()))
-            }
-        }
-    } else {
-        (now.clone(), Some(session_id.to_string()))
-    };
 
-    let message_count = messages.len();
+    info!("Saving chat session: {}", session_id);
+    let _guard = lock_session(session_id).await;
+
+    // Preserve the CLI session id and workspace if updating an existing session
+    let existing = load_tree(db, session_id).await?;
+    let conversion = existing.as_ref().map(|(conversion, _)| conversion);
+    let preserved_session_id = conversion
+        .and_then(|c| c.session_id.clone())
+        .or_else(|| Some(session_id.to_string()));
+    let workspace_id = parse_workspace_id(workspace_id.as_deref()).or_else(|| conversion.and_then(|c| c.workspace_id));
+    let code_cli_task_ids = match code_cli_task_ids {
+        Some(task_ids) => task_ids_to_json(&task_ids),
+        None => conversion
+            .map(|c| c.code_cli_task_ids.clone())
+            .unwrap_or_else(|| "{}".to_string()),
+    };
 
-    let session = ChatSession {
-        id: session_id.to_string(),
+    let record = ConversionRecord {
+        conversion_id: session_id.to_string(),
         name,
-        session_id: preserved_session_id,
         workspace_id,
-        messages,
-        created_at,
-        updated_at: now,
-        message_count,
-        first_message_preview,
-        code_cli_task_ids: code_cli_task_ids.unwrap_or_default(),
+        session_id: preserved_session_id,
+        active_leaf_id: messages.last().map(|m| m.id.clone()),
+        code_cli_task_ids,
+        ..Default::default()
     };
-
-    // Write to file
-    let json = serde_json::to_string_pretty(&session)
-        .map_err(|e| format!("Failed to serialize session: {}", e))?;
-
-    fs::write(&file_path, json)
-        .map_err(|e| format!("Failed to write session file: {}", e))?;
+    let incoming = to_message_records(&messages);
+    let records = match &existing {
+        Some((_, tree)) => merge_message_records(tree, incoming),
+        None => incoming,
+    };
+    let conversion = ConversionRepository::replace(db, record, &records)
+        .await
+        .map_err(|e| format!("Failed to save session: {}", e))?;
 
     info!("Chat session saved successfully: {}", session_id);
-    Ok(session)
+    load_session_by_id(db, &conversion.conversion_id).await
 }
 
 /// append a message to a chat session
-pub fn append_message_to_session(
+///
+/// The messages continue the active branch. With `branch_from` (the id of an
+/// edited message) they become a sibling of that message instead, starting a new
+/// branch which is then active.
+pub async fn append_message_to_session(
+    db: &DatabaseConnection,
     session_id: &str,
     messages: Vec<ChatMessage>,
     code_cli: Option<String>,
     code_cli_task_id: Option<String>,
+    branch_from: Option<&str>,
 ) -> Result<(), String> {
     info!("Appending message to session: {}", session_id);
+    let _guard = lock_session(session_id).await;
 
-    let mut session = match load_session_by_id(session_id) {
-        Ok(existing) => existing,
-        Err(err) => {
+    let existing = load_tree(db, session_id).await?;
+    let mut task_ids = match &existing {
+        Some((conversion, _)) => task_ids_from_json(&conversion.code_cli_task_ids),
+        None => {
             warn!(
-                "Session {} not found when appending messages ({}), creating a new one",
-                session_id, err
+                "Session {} not found when appending messages, creating a new one",
+                session_id
             );
-            let now = Local::now().to_rfc3339();
-            ChatSession {
-                id: session_id.to_string(),
-                name: None,
-                session_id: Some(session_id.to_string()),
-                workspace_id: messages
-                    .first()
-                    .and_then(|msg| msg.workspace_id.clone()),
-                messages: Vec::new(),
-                created_at: now.clone(),
-                updated_at: now,
-                message_count: 0,
-                first_message_preview: String::new(),
-                code_cli_task_ids: HashMap::new(),
-            }
+            HashMap::new()
         }
     };
-    session.session_id = Some(session_id.to_string());
-    session.messages.extend(messages);
-    session.message_count = session.messages.len();
-    session.updated_at = Local::now().to_rfc3339();
-    if let (Some(cli), Some(task_id)) = (code_cli, code_cli_task_id) {
-        session
-            .code_cli_task_ids
-            .insert(cli, task_id);
+    let mut parent = match &existing {
+        Some((conversion, tree)) => branch_parent(conversion, tree, branch_from)?,
+        None => None,
+    };
+    if let (Some(cli), Some(task_id)) = (&code_cli, &code_cli_task_id) {
+        task_ids.insert(cli.clone(), task_id.clone());
     }
 
-    info!("Session updated with {} total messages", session.message_count);
+    let record = ConversionRecord {
+        conversion_id: session_id.to_string(),
+        workspace_id: parse_workspace_id(messages.first().and_then(|msg| msg.workspace_id.as_deref())),
+        session_id: Some(session_id.to_string()),
+        code_cli_task_ids: task_ids_to_json(&task_ids),
+        ..Default::default()
+    };
+    let records: Vec<MessageRecord> = messages
+        .iter()
+        .map(|message| {
+            let mut record = to_message_record(message);
+            record.parent_id = parent.replace(message.id.clone());
+            if record.role == "assistant" && record.code_cli_task_id.is_none() {
+                record.code_cli = code_cli.clone();
+                record.code_cli_task_id = code_cli_task_id.clone();
+            }
+            record
+        })
+        .collect();
+    let conversion = ConversionRepository::append_messages(db, record, &records)
+        .await
+        .map_err(|e| format!("Failed to append messages: {}", e))?;
+
+    info!("Session updated with {} total messages", conversion.message_count);
+    Ok(())
+}
+
+/// Load one page of chat session summaries for a workspace, pinned first, then newest first.
+///
+/// Messages are not loaded; open a session with `load_session_by_id`.
+pub async fn load_all_sessions(
+    db: &DatabaseConnection,
+    workspace_id: String,
+    filter: &SessionFilter,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<ChatSessionSummary>, String> {
+    let Some(workspace) = parse_workspace_id(Some(&workspace_id)) else {
+        warn!("Invalid workspace id {}, returning empty list", workspace_id);
+        return Ok(Vec::new());
+    };
+    let limit = limit.map(|l| l as u64).unwrap_or(DEFAULT_PAGE_SIZE);
+    let offset = offset.unwrap_or(0) as u64;
+
+    let filter = ConversionFilter {
+        archived: filter.archived,
+        pinned: filter.pinned,
+        tags: filter.tags.clone(),
+    };
+    let conversions = ConversionRepository::list_by_workspace(db, Some(workspace), &filter, offset, limit)
+        .await
+        .map_err(|e| format!("Failed to load sessions: {}", e))?;
+    let sessions: Vec<ChatSessionSummary> = conversions.into_iter().map(to_session_summary).collect();
+
+    debug!(
+        "Loaded {} sessions (offset {}, limit {})",
+        sessions.len(),
+        offset,
+        limit
+    );
+    Ok(sessions)
+}
+
+/// Delete a chat session
+pub async fn delete_session(db: &DatabaseConnection, session_id: &str) -> Result<(), String> {
+    debug!("Deleting chat session: {}", session_id);
+    let _guard = lock_session(session_id).await;
+
+    let deleted = ConversionRepository::delete(db, session_id)
+        .await
+        .map_err(|e| format!("Failed to delete session: {}", e))?;
+    if !deleted {
+        return Err(format!("Session not found: {}", session_id));
+    }
 
-    save_session(
-        Some(session_id.to_string()),
-        session.name,
-        session.workspace_id,
-        session.messages,
-        Some(session.code_cli_task_ids.clone()),
-    )
-    .map_err(|e| e.to_string())?;
+    debug!("Chat session deleted successfully: {}", session_id);
     Ok(())
 }
 
-/// Load all chat sessions
-pub fn load_all_sessions(workspace_id: String, limit: Option<usize>) -> Result<Vec<ChatSession>, String> {
-    let dir = get_sessions_dir()?;
+/// Update a chat session name
+pub async fn update_session_name(
+    db: &DatabaseConnection,
+    session_id: &str,
+    name: String,
+) -> Result<ChatSession, String> {
+    debug!("Updating session name: {} -> {}", session_id, name);
+
+    let _guard = lock_session(session_id).await;
+    ConversionRepository::rename(db, session_id, &name)
+        .await
+        .map_err(|e| format!("Failed to rename session: {}", e))?
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+
+    debug!("Session name updated successfully: {}", session_id);
+    load_session_by_id(db, session_id).await
+}
+
+/// Replace the tags of a session (trimmed, without duplicates)
+pub async fn set_session_tags(
+    db: &DatabaseConnection,
+    session_id: &str,
+    tags: Vec<String>,
+) -> Result<ChatSession, String> {
+    debug!("Setting tags of session {}: {:?}", session_id, tags);
+
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if !normalized.iter().any(|t| t == tag) {
+            normalized.push(tag.to_string());
+        }
+    }
+    let _guard = lock_session(session_id).await;
+    ConversionRepository::set_tags(db, session_id, &tags_to_json(&normalized))
+        .await
+        .map_err(|e| format!("Failed to set session tags: {}", e))?
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    load_session_by_id(db, session_id).await
+}
+
+/// Pin or unpin a session
+pub async fn set_session_pinned(
+    db: &DatabaseConnection,
+    session_id: &str,
+    pinned: bool,
+) -> Result<ChatSession, String> {
+    debug!("Setting pinned of session {}: {}", session_id, pinned);
+
+    let _guard = lock_session(session_id).await;
+    ConversionRepository::set_pinned(db, session_id, pinned)
+        .await
+        .map_err(|e| format!("Failed to pin session: {}", e))?
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    load_session_by_id(db, session_id).await
+}
 
+/// Archive or unarchive a session
+pub async fn set_session_archived(
+    db: &DatabaseConnection,
+    session_id: &str,
+    archived: bool,
+) -> Result<ChatSession, String> {
+    debug!("Setting archived of session {}: {}", session_id, archived);
+
+    let _guard = lock_session(session_id).await;
+    ConversionRepository::set_archived_at(db, session_id, archived.then(Utc::now))
+        .await
+        .map_err(|e| format!("Failed to archive session: {}", e))?
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    load_session_by_id(db, session_id).await
+}
+
+/// List the branches of a session, oldest first
+pub async fn list_branches(db: &DatabaseConnection, session_id: &str) -> Result<Vec<ChatBranch>, String> {
+    let (conversion, tree) = load_tree(db, session_id)
+        .await?
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    Ok(chat_branch::branches(&tree, conversion.active_leaf_id.as_deref()))
+}
+
+/// Make the branch containing `message_id` active.
+///
+/// `message_id` may be any message of the branch; below a fork the most recent
+/// continuation is chosen.
+pub async fn switch_branch(db: &DatabaseConnection, session_id: &str, message_id: &str) -> Result<ChatSession, String> {
+    debug!("Switching session {} to branch of message {}", session_id, message_id);
+
+    let _guard = lock_session(session_id).await;
+    let (_, tree) = load_tree(db, session_id)
+        .await?
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    let leaf = chat_branch::latest_leaf_under(&tree, message_id)
+        .ok_or_else(|| format!("Message not found: {}", message_id))?;
+    ConversionRepository::set_active_leaf(db, session_id, &tree[leaf].id)
+        .await
+        .map_err(|e| format!("Failed to switch branch: {}", e))?;
+
+    load_session_by_id(db, session_id).await
+}
+
+/// History and resume ids for a new message, see `append_message_to_session`.
+///
+/// A missing session yields an empty context.
+pub async fn load_branch_context(
+    db: &DatabaseConnection,
+    session_id: &str,
+    branch_from: Option<&str>,
+) -> Result<BranchContext, String> {
+    let Some((conversion, tree)) = load_tree(db, session_id).await? else {
+        return Ok(BranchContext::default());
+    };
+    let parent = branch_parent(&conversion, &tree, branch_from)?;
+    let path = match parent {
+        Some(parent) => chat_branch::path_to(&tree, Some(&parent)),
+        None => Vec::new(),
+    };
+    let code_cli_task_ids =
+        chat_branch::resume_task_ids(&tree, &path, &task_ids_from_json(&conversion.code_cli_task_ids));
+    let (summary, history) = chat_summary::split_history(
+        path.into_iter().map(|i| tree[i].clone()).collect(),
+        conversion.summary.as_deref(),
+        conversion.summary_through_id.as_deref(),
+    );
+
+    Ok(BranchContext {
+        summary,
+        history,
+        code_cli_task_ids,
+    })
+}
+
+/// Store the rolling summary of a session's messages up to `through_id`
+pub async fn set_session_summary(
+    db: &DatabaseConnection,
+    session_id: &str,
+    summary: &str,
+    through_id: &str,
+) -> Result<(), String> {
+    let _guard = lock_session(session_id).await;
+    ConversionRepository::set_summary(db, session_id, summary, through_id)
+        .await
+        .map_err(|e| format!("Failed to store session summary: {}", e))?
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    Ok(())
+}
+
+/// Import the JSON session files written by older versions.
+///
+/// Sessions already in the database are skipped, so an interrupted import can be
+/// rerun. Once every file was imported the directory is renamed to
+/// `chat-sessions.imported`, which keeps the originals as a backup.
+pub async fn import_legacy_sessions(db: &DatabaseConnection) -> Result<usize, String> {
+    let dir = get_legacy_sessions_dir()?;
     if !dir.exists() {
-        info!("Sessions directory does not exist, returning empty list");
-        return Ok(Vec::new());
+        return Ok(0);
     }
 
-    info!("Loading chat sessions from: {:?}", dir);
+    info!("Importing chat sessions from: {:?}", dir);
 
     let entries = fs::read_dir(&dir)
         .map_err(|e| format!("Failed to read sessions directory: {}", e))?;
 
-    let mut sessions: Vec<ChatSession> = Vec::new();
+    let mut imported = 0;
     let mut error_count = 0;
+    let mut known_workspaces: HashMap<i32, bool> = HashMap::new();
 
     for entry in entries {
-        let entry = match entry {
-            Ok(e) => e,
+        let path = match entry {
+            Ok(e) => e.path(),
             Err(e) => {
                 error!("Failed to read directory entry: {}", e);
                 error_count += 1;
@@ -239,92 +555,401 @@ pub fn load_all_sessions(workspace_id: String, limit: Option<usize>) -> Result<V
             }
         };
 
-        let path = entry.path();
-
         // Only process .json files
         if path.extension().and_then(|s| s.to_str()) != Some("json") {
             continue;
         }
 
-        match fs::read_to_string(&path) {
-            Ok(content) => match serde_json::from_str::<ChatSession>(&content) {
-                Ok(session) => {
-                    if session.workspace_id.as_deref() != Some(&workspace_id) {
-                        continue;
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                error!("Failed to read session file {:?}: {}", path, e);
+                error_count += 1;
+                continue;
+            }
+        };
+        let session = match chat_schema::parse_session(&content) {
+            Ok((session, upgraded)) => {
+                // Keep the file readable by this version should the import be retried
+                if upgraded {
+                    if let Err(e) = write_legacy_file(&path, &session) {
+                        warn!("Failed to upgrade session file {:?}: {}", path, e);
                     }
-                    // 自动迁移已禁用：session_id 字段已标准化，不再需要 codeagent_session_id 迁移
-                    // 注：此注释保留以说明数据模型已演变
-
-                    debug!("Loaded session: {} from {:?}", session.id, path);
-                    sessions.push(session);
                 }
-                Err(e) => {
-                    error!("Failed to parse session file {:?}: {}", path, e);
+                session
+            }
+            Err(e) => {
+                // A truncated file will not parse on the next start either
+                error!("Failed to parse session file {:?}: {}", path, e);
+                if let Err(e) = quarantine_legacy_file(&path) {
+                    error!("Failed to quarantine session file {:?}: {}", path, e);
                     error_count += 1;
                 }
-            },
+                continue;
+            }
+        };
+
+        match session_exists(db, &session.id).await {
+            Ok(true) => continue,
+            Ok(false) => {}
             Err(e) => {
-                error!("Failed to read session file {:?}: {}", path, e);
+                error!("Failed to look up session {}: {}", session.id, e);
+                error_count += 1;
+                continue;
+            }
+        }
+
+        // Sessions of deleted workspaces are kept without a workspace
+        let mut workspace_id = parse_workspace_id(session.workspace_id.as_deref());
+        if let Some(id) = workspace_id {
+            let exists = match known_workspaces.get(&id) {
+                Some(exists) => *exists,
+                None => {
+                    let exists = matches!(WorkspaceRepository::get_by_id(db, &id).await, Ok(Some(_)));
+                    known_workspaces.insert(id, exists);
+                    exists
+                }
+            };
+            if !exists {
+                workspace_id = None;
+            }
+        }
+
+        match store_session(db, &session, workspace_id).await {
+            Ok(_) => {
+                debug!("Imported session: {} from {:?}", session.id, path);
+                imported += 1;
+            }
+            Err(e) => {
+                error!("Failed to import session file {:?}: {}", path, e);
                 error_count += 1;
             }
         }
     }
 
     if error_count > 0 {
-        warn!("Encountered {} errors while loading sessions", error_count);
+        warn!(
+            "Encountered {} errors while importing sessions, will retry on next start",
+            error_count
+        );
+    } else {
+        let backup = dir.with_file_name("chat-sessions.imported");
+        if let Err(e) = fs::rename(&dir, &backup) {
+            warn!("Failed to move imported sessions to {:?}: {}", backup, e);
+        }
     }
 
-    // Sort by updated_at in descending order (newest first)
-    sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    info!("Imported {} chat sessions", imported);
+    Ok(imported)
+}
 
-    // Apply limit if specified
-    let result_count = sessions.len();
-    if let Some(limit) = limit {
-        sessions.truncate(limit);
-        debug!("Loaded {} sessions (limited to {} from {})", sessions.len(), limit, result_count);
-    } else {
-        debug!("Loaded {} sessions", sessions.len());
-    }
+fn write_legacy_file(path: &Path, session: &ChatSession) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(session).map_err(|e| e.to_string())?;
+    crate::utils::fs::write_atomic(path, json.as_bytes()).map_err(|e| e.to_string())?;
+    debug!(
+        "Upgraded session file {:?} to schema version {}",
+        path,
+        chat_schema::SCHEMA_VERSION
+    );
+    Ok(())
+}
 
-    Ok(sessions)
+/// Move an unreadable session file to `chat-sessions.corrupt/` so the import can complete
+fn quarantine_legacy_file(path: &Path) -> Result<(), String> {
+    let dir = get_legacy_sessions_dir()?.with_file_name("chat-sessions.corrupt");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    let target = dir.join(path.file_name().unwrap_or_default());
+    fs::rename(path, &target).map_err(|e| e.to_string())?;
+    warn!("Moved unreadable session file {:?} to {:?}", path, target);
+    Ok(())
 }
 
-/// Delete a chat session
-pub fn delete_session(session_id: &str) -> Result<(), String> {
-    let dir = get_sessions_dir()?;
-    let file_path = dir.join(format!("{}.json", session_id));
+/// Store a complete session as-is, keeping its timestamps (used by importers)
+pub async fn store_session(
+    db: &DatabaseConnection,
+    session: &ChatSession,
+    workspace_id: Option<i32>,
+) -> Result<ChatSession, String> {
+    let _guard = lock_session(&session.id).await;
+    let record = ConversionRecord {
+        conversion_id: session.id.clone(),
+        name: session.name.clone(),
+        workspace_id,
+        session_id: session.session_id.clone(),
+        active_leaf_id: session
+            .active_leaf_id
+            .clone()
+            .or_else(|| session.messages.last().map(|m| m.id.clone())),
+        code_cli_task_ids: task_ids_to_json(&session.code_cli_task_ids),
+        tags: Some(tags_to_json(&session.tags)),
+        pinned: Some(session.pinned),
+        created_at: parse_timestamp(&session.created_at),
+        updated_at: parse_timestamp(&session.updated_at),
+    };
+    let records = to_message_records(&session.messages);
+    let conversion = ConversionRepository::replace(db, record, &records)
+        .await
+        .map_err(|e| format!("Failed to store session: {}", e))?;
+    load_session_by_id(db, &conversion.conversion_id).await
+}
 
-    if !file_path.exists() {
-        return Err(format!("Session not found: {}", session_id));
+/// Whether a session with this id exists
+pub async fn session_exists(db: &DatabaseConnection, session_id: &str) -> Result<bool, String> {
+    ConversionRepository::get_by_conversion_id(db, session_id)
+        .await
+        .map(|c| c.is_some())
+        .map_err(|e| e.to_string())
+}
+
+/// The session of a workspace with this CLI session id (as set by imports)
+pub async fn find_by_session_id(
+    db: &DatabaseConnection,
+    workspace_id: i32,
+    session_id: &str,
+) -> Result<Option<ChatSession>, String> {
+    let Some(conversion) = ConversionRepository::get_by_session_id(db, workspace_id, session_id)
+        .await
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(None);
+    };
+    load_session_by_id(db, &conversion.conversion_id).await.map(Some)
+}
+
+/// A conversion with all of its messages, in insertion order
+async fn load_tree(
+    db: &DatabaseConnection,
+    session_id: &str,
+) -> Result<Option<(ConversionModel, Vec<ChatMessage>)>, String> {
+    let Some(conversion) = ConversionRepository::get_by_conversion_id(db, session_id)
+        .await
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(None);
+    };
+    let messages = ConversionRepository::get_messages(db, &[conversion.id])
+        .await
+        .map_err(|e| e.to_string())?;
+    let tree = to_chat_messages(&conversion, messages);
+    Ok(Some((conversion, tree)))
+}
+
+/// Parent of a new message: the edited message's parent, or the active leaf
+fn branch_parent(
+    conversion: &ConversionModel,
+    tree: &[ChatMessage],
+    branch_from: Option<&str>,
+) -> Result<Option<String>, String> {
+    match branch_from {
+        Some(edited) => tree
+            .iter()
+            .find(|m| m.id == edited)
+            .map(|m| m.parent_id.clone())
+            .ok_or_else(|| format!("Message not found: {}", edited)),
+        None => Ok(chat_branch::path_to(tree, conversion.active_leaf_id.as_deref())
+            .last()
+            .map(|&i| tree[i].id.clone())),
     }
+}
 
-    debug!("Deleting chat session: {} (file: {:?})", session_id, file_path);
+fn parse_workspace_id(workspace_id: Option<&str>) -> Option<i32> {
+    workspace_id.and_then(|id| id.trim().parse::<i32>().ok())
+}
 
-    fs::remove_file(&file_path)
-        .map_err(|e| format!("Failed to delete session file: {}", e))?;
+fn parse_timestamp(timestamp: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
 
-    debug!("Chat session deleted successfully: {}", session_id);
-    Ok(())
+fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.with_timezone(&Local).to_rfc3339()
 }
 
-/// Update a chat session name
-pub fn update_session_name(session_id: &str, name: String) -> Result<ChatSession, String> {
-    debug!("Updating session name: {} -> {}", session_id, name);
+fn task_ids_to_json(task_ids: &HashMap<String, String>) -> String {
+    serde_json::to_string(task_ids).unwrap_or_else(|_| "{}".to_string())
+}
 
-    let mut session = load_session_by_id(session_id)?;
-    session.name = Some(name);
-    session.updated_at = Local::now().to_rfc3339();
+fn task_ids_from_json(json: &str) -> HashMap<String, String> {
+    serde_json::from_str(json).unwrap_or_default()
+}
 
-    let dir = get_sessions_dir()?;
-    let file_path = dir.join(format!("{}.json", session_id));
+fn tags_to_json(tags: &[String]) -> String {
+    serde_json::to_string(tags).unwrap_or_else(|_| "[]".to_string())
+}
 
-    // Write updated session to file
-    let json = serde_json::to_string_pretty(&session)
-        .map_err(|e| format!("Failed to serialize session: {}", e))?;
+fn tags_from_json(json: &str) -> Vec<String> {
+    serde_json::from_str(json).unwrap_or_default()
+}
 
-    fs::write(&file_path, json)
-        .map_err(|e| format!("Failed to write session file: {}", e))?;
+fn to_message_record(message: &ChatMessage) -> MessageRecord {
+    MessageRecord {
+        message_id: message.id.clone(),
+        parent_id: message.parent_id.clone(),
+        role: message.role.clone(),
+        content: message.content.clone(),
+        files: message
+            .files
+            .as_ref()
+            .and_then(|files| serde_json::to_string(files).ok()),
+        model: message.model.clone(),
+        code_cli: message.code_cli.clone(),
+        code_cli_task_id: message.code_cli_task_id.clone(),
+        created_at: parse_timestamp(&message.timestamp).unwrap_or_else(Utc::now),
+        run: message.run.as_ref().map(|run| RunRecord {
+            backend: run.backend.clone(),
+            model: run.model.clone(),
+            workdir: run.workdir.clone(),
+            duration_ms: run.duration_ms as i64,
+            exit_code: run.exit_code,
+            stderr_tail: run.stderr_tail.clone(),
+            input_tokens: run.usage.map(|u| u.input_tokens as i64),
+            output_tokens: run.usage.map(|u| u.output_tokens as i64),
+            cancelled: run.cancelled,
+            error: run.error.clone(),
+        }),
+    }
+}
 
-    debug!("Session name updated successfully: {}", session_id);
-    Ok(session)
+/// Records for a full message list; messages without a parent (older data) follow the previous one
+fn to_message_records(messages: &[ChatMessage]) -> Vec<MessageRecord> {
+    let mut previous: Option<&str> = None;
+    messages
+        .iter()
+        .map(|message| {
+            let mut record = to_message_record(message);
+            if record.parent_id.is_none() {
+                record.parent_id = previous.map(str::to_string);
+            }
+            previous = Some(&message.id);
+            record
+        })
+        .collect()
+}
+
+/// The stored messages of a session updated by `incoming`, followed by the new ones.
+///
+/// A stored message keeps its place in the tree, and its resume id and run when
+/// the incoming copy has none.
+fn merge_message_records(tree: &[ChatMessage], incoming: Vec<MessageRecord>) -> Vec<MessageRecord> {
+    let mut incoming_by_id: HashMap<String, MessageRecord> = incoming
+        .iter()
+        .map(|record| (record.message_id.clone(), record.clone()))
+        .collect();
+    let mut records: Vec<MessageRecord> = tree
+        .iter()
+        .map(|message| {
+            let stored = to_message_record(message);
+            match incoming_by_id.remove(&message.id) {
+                Some(update) => MessageRecord {
+                    parent_id: stored.parent_id,
+                    code_cli: update.code_cli.or(stored.code_cli),
+                    code_cli_task_id: update.code_cli_task_id.or(stored.code_cli_task_id),
+                    run: update.run.or(stored.run),
+                    ..update
+                },
+                None => stored,
+            }
+        })
+        .collect();
+    records.extend(
+        incoming
+            .into_iter()
+            .filter(|record| incoming_by_id.contains_key(&record.message_id)),
+    );
+    records
+}
+
+fn to_chat_messages(
+    conversion: &ConversionModel,
+    messages: Vec<(ConversionMessageModel, Option<MessageRunModel>)>,
+) -> Vec<ChatMessage> {
+    let workspace_id = conversion.workspace_id.map(|id| id.to_string());
+    messages
+        .into_iter()
+        .map(|(m, run)| ChatMessage {
+            id: m.message_id,
+            role: m.role,
+            content: m.content,
+            timestamp: format_timestamp(m.created_at),
+            files: m.files.and_then(|files| serde_json::from_str(&files).ok()),
+            model: m.model,
+            session_id: Some(conversion.conversion_id.clone()),
+            workspace_id: workspace_id.clone(),
+            parent_id: m.parent_id,
+            code_cli: m.code_cli,
+            code_cli_task_id: m.code_cli_task_id,
+            run: run.map(to_run_metadata),
+        })
+        .collect()
+}
+
+fn to_run_metadata(run: MessageRunModel) -> RunMetadata {
+    let usage = (run.input_tokens.is_some() || run.output_tokens.is_some()).then(|| TokenUsage {
+        input_tokens: run.input_tokens.unwrap_or_default() as u64,
+        output_tokens: run.output_tokens.unwrap_or_default() as u64,
+    });
+    RunMetadata {
+        backend: run.backend,
+        model: run.model,
+        workdir: run.workdir,
+        duration_ms: run.duration_ms as u64,
+        exit_code: run.exit_code,
+        stderr_tail: run.stderr_tail,
+        usage,
+        cancelled: run.cancelled,
+        error: run.error,
+    }
+}
+
+fn to_session_summary(conversion: ConversionModel) -> ChatSessionSummary {
+    ChatSessionSummary {
+        id: conversion.conversion_id,
+        name: conversion.name,
+        session_id: conversion.session_id,
+        workspace_id: conversion.workspace_id.map(|id| id.to_string()),
+        created_at: format_timestamp(conversion.created_at),
+        updated_at: format_timestamp(conversion.updated_at),
+        message_count: conversion.message_count.max(0) as usize,
+        first_message_preview: conversion.first_message_preview,
+        tags: tags_from_json(&conversion.tags),
+        pinned: conversion.pinned,
+        archived_at: conversion.archived_at.map(format_timestamp),
+    }
+}
+
+/// Session showing its active branch
+fn to_chat_session(
+    conversion: ConversionModel,
+    messages: Vec<(ConversionMessageModel, Option<MessageRunModel>)>,
+) -> ChatSession {
+    let tree = to_chat_messages(&conversion, messages);
+    let run_stats = RunStats::from_messages(&tree);
+    let path = chat_branch::path_to(&tree, conversion.active_leaf_id.as_deref());
+    let code_cli_task_ids =
+        chat_branch::resume_task_ids(&tree, &path, &task_ids_from_json(&conversion.code_cli_task_ids));
+    let branch_count = chat_branch::leaf_count(&tree);
+    let messages: Vec<ChatMessage> = path.into_iter().map(|i| tree[i].clone()).collect();
+
+    ChatSession {
+        id: conversion.conversion_id,
+        name: conversion.name,
+        session_id: conversion.session_id,
+        workspace_id: conversion.workspace_id.map(|id| id.to_string()),
+        message_count: messages.len(),
+        active_leaf_id: messages.last().map(|m| m.id.clone()),
+        messages,
+        created_at: format_timestamp(conversion.created_at),
+        updated_at: format_timestamp(conversion.updated_at),
+        first_message_preview: conversion.first_message_preview,
+        code_cli_task_ids,
+        branch_count,
+        run_stats,
+        tags: tags_from_json(&conversion.tags),
+        pinned: conversion.pinned,
+        archived_at: conversion.archived_at.map(format_timestamp),
+        summary: conversion.summary,
+        summary_through_id: conversion.summary_through_id,
+        schema_version: chat_schema::SCHEMA_VERSION,
+    }
 }
@@ -0,0 +1,121 @@
+//! Rolling session summaries
+//!
+//! Once the messages of a branch grow long, everything but the most recent
+//! messages is condensed by the current backend into a summary stored on the
+//! session; later runs fold the previous summary and the messages since into a
+//! new one. A code CLI resuming its own session does not need it, but a fresh CLI
+//! session (after `code_cli_changed` or on a new branch) and the stateless HTTP
+//! backends get the summary plus the messages after it instead of the full history.
+
+use std::ops::Range;
+
+use sea_orm::DatabaseConnection;
+use tracing::{debug, info};
+
+use super::ai::{AiChatOptions, AiService};
+use super::chat_session::{self, ChatMessage};
+
+/// Characters of unsummarized messages that trigger a new summary
+const SUMMARY_TRIGGER_CHARS: usize = 24_000;
+/// Most recent messages kept verbatim instead of being summarized
+const KEEP_RECENT_MESSAGES: usize = 6;
+
+/// The summary valid for `path` and the messages after it.
+///
+/// A summary through a message that is not on `path` (another branch) is ignored.
+pub fn split_history(
+    path: Vec<ChatMessage>,
+    summary: Option<&str>,
+    through_id: Option<&str>,
+) -> (Option<String>, Vec<ChatMessage>) {
+    let (Some(summary), Some(through)) = (summary, through_id) else {
+        return (None, path);
+    };
+    match path.iter().position(|m| m.id == through) {
+        Some(index) => (Some(summary.to_string()), path.into_iter().skip(index + 1).collect()),
+        None => (None, path),
+    }
+}
+
+/// Messages of `path` to fold into the summary, once the unsummarized part is long enough
+pub fn pending(path: &[ChatMessage], through_id: Option<&str>) -> Option<Range<usize>> {
+    let start = through_id
+        .and_then(|through| path.iter().position(|m| m.id == through))
+        .map_or(0, |index| index + 1);
+    let end = path.len().saturating_sub(KEEP_RECENT_MESSAGES);
+    if end <= start {
+        return None;
+    }
+    let unsummarized: usize = path[start..].iter().map(|m| m.content.chars().count()).sum();
+    (unsummarized >= SUMMARY_TRIGGER_CHARS).then_some(start..end)
+}
+
+/// Instruction asking the backend to extend `previous` with `messages`
+pub fn summary_prompt(previous: Option<&str>, messages: &[ChatMessage]) -> String {
+    let mut prompt = String::from(
+        "Summarize the conversation below so that it can be continued without it. \
+         Keep the goals, decisions, file paths, commands, open questions and anything the user asked to remember; \
+         leave out pleasantries. Reply with the summary only and do not run any tools.\n\n",
+    );
+    if let Some(previous) = previous {
+        prompt.push_str("Summary of the conversation before these messages:\n\n");
+        prompt.push_str(previous.trim());
+        prompt.push_str("\n\n");
+    }
+    prompt.push_str("Messages:\n\n");
+    for message in messages {
+        let speaker = if message.role == "assistant" {
+            "Assistant"
+        } else {
+            "User"
+        };
+        prompt.push_str(&format!("{}: {}\n\n", speaker, message.content.trim()));
+    }
+    prompt
+}
+
+/// Extend the summary of the session's active branch when its history got long.
+///
+/// `options` select the backend (the one the session just used). Returns whether
+/// a new summary was stored.
+pub async fn summarize_if_needed(
+    db: &DatabaseConnection,
+    ai: &AiService,
+    session_id: &str,
+    options: AiChatOptions,
+) -> Result<bool, String> {
+    let session = chat_session::load_session_by_id(db, session_id).await?;
+    let (previous, _) = split_history(
+        session.messages.clone(),
+        session.summary.as_deref(),
+        session.summary_through_id.as_deref(),
+    );
+    // A summary of another branch does not count for this one
+    let through_id = previous.as_ref().and(session.summary_through_id.as_deref());
+    let Some(range) = pending(&session.messages, through_id) else {
+        return Ok(false);
+    };
+
+    let through = session.messages[range.end - 1].id.clone();
+    // Failed runs hold error text, not conversation
+    let messages: Vec<ChatMessage> = session.messages[range]
+        .iter()
+        .filter(|m| m.run.as_ref().is_none_or(|run| run.error.is_none()))
+        .cloned()
+        .collect();
+    debug!(session_id, messages = messages.len(), "Summarizing chat session");
+
+    let prompt = summary_prompt(previous.as_deref(), &messages);
+    let result = ai
+        .send_message_streaming(&prompt, None, options, |_| {})
+        .await
+        .map_err(|e| format!("Failed to summarize session: {}", e))?;
+    let summary = result.message.trim();
+    if result.cancelled || summary.is_empty() {
+        return Err("Failed to summarize session: empty summary".to_string());
+    }
+
+    chat_session::set_session_summary(db, session_id, summary, &through).await?;
+    info!(session_id, through = %through, "Updated chat session summary");
+    Ok(true)
+}
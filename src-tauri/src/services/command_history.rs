@@ -0,0 +1,92 @@
+//! Shell command history search
+//!
+//! Commands run through `execute_terminal_command` are recorded per workspace
+//! by `CommandHistoryRepository`. Searches return each distinct command line
+//! once, as its most recent run, so a build or test invocation can be found and
+//! run again.
+
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+
+use crate::database::models::command_history::Model as CommandHistoryEntry;
+use crate::database::repositories::command_history_repository::CommandHistoryRepository;
+use crate::utils::error::AppResult;
+
+/// Recent runs looked at by a search; older ones only show up through newer runs of the same command
+const SEARCH_SCAN_LIMIT: u64 = 5000;
+
+/// How the query is matched against command lines
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistorySearchMode {
+    /// Command starts with the query (case-sensitive)
+    #[default]
+    Prefix,
+    /// Query characters other than whitespace appear in order (case-insensitive), best matches first
+    Fuzzy,
+}
+
+/// Distinct commands of a workspace matching `query`.
+///
+/// Prefix results are ordered by recency; fuzzy results by match quality, then
+/// recency. An empty query lists the most recent commands.
+pub async fn search(
+    db: &DatabaseConnection,
+    workspace_id: Option<i32>,
+    query: &str,
+    mode: HistorySearchMode,
+    limit: usize,
+) -> AppResult<Vec<CommandHistoryEntry>> {
+    let prefix = (mode == HistorySearchMode::Prefix).then_some(query);
+    let entries = CommandHistoryRepository::list_recent(db, workspace_id, prefix, SEARCH_SCAN_LIMIT).await?;
+
+    // Newest first, so the first run seen of each command is its latest
+    let mut seen = std::collections::HashSet::new();
+    let entries = entries.into_iter().filter(|entry| seen.insert(entry.command.clone()));
+    if mode == HistorySearchMode::Prefix || query.is_empty() {
+        return Ok(entries.take(limit).collect());
+    }
+
+    let mut scored: Vec<(i64, usize, CommandHistoryEntry)> = entries
+        .enumerate()
+        .filter_map(|(recency, entry)| fuzzy_score(query, &entry.command).map(|score| (score, recency, entry)))
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    Ok(scored.into_iter().take(limit).map(|(_, _, entry)| entry).collect())
+}
+
+/// Score of `text` containing the characters of `query` in order, higher is better.
+///
+/// Consecutive matches and matches at the start of a word score extra, skipped
+/// characters cost a little. `None` when `query` is not a subsequence of `text`.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let mut score = 0;
+    let mut query_chars = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .peekable();
+    let mut previous: Option<char> = None;
+    let mut previous_matched = false;
+    for c in text.chars() {
+        let Some(&wanted) = query_chars.peek() else {
+            break;
+        };
+        let matched = c.to_lowercase().eq(std::iter::once(wanted));
+        if matched {
+            query_chars.next();
+            score += 1;
+            if previous_matched {
+                score += 5;
+            }
+            if previous.is_none_or(|p| !p.is_alphanumeric()) {
+                score += 3;
+            }
+        } else {
+            score -= 1;
+        }
+        previous = Some(c);
+        previous_matched = matched;
+    }
+    query_chars.peek().is_none().then_some(score)
+}
@@ -0,0 +1,208 @@
+//! Non-interactive command execution
+//!
+//! Runs a command with piped stdout and stderr and passes their output on as it
+//! arrives, tagged by stream. A run ends with a `CommandExit` carrying the exit
+//! code or signal, how long it took and whether output was dropped. A timeout or
+//! cancellation kills the command together with everything it spawned.
+
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::{Child, Command};
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
+
+use super::ai::backend::wait_for_cancel;
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::process::kill_process_tree;
+
+/// Output passed on per command; the rest is read but dropped
+const MAX_OUTPUT_BYTES: usize = 4 * 1024 * 1024;
+
+/// Stream a chunk of output was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// How a command ended
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CommandExit {
+    /// `None` when killed by a signal or when waiting failed
+    pub exit_code: Option<i32>,
+    /// Terminating signal (Unix)
+    pub signal: Option<i32>,
+    pub duration_ms: u64,
+    /// Output beyond `MAX_OUTPUT_BYTES` was dropped
+    pub truncated: bool,
+    pub timed_out: bool,
+    pub cancelled: bool,
+}
+
+/// A spawned command whose output has not been read yet
+#[derive(Debug)]
+pub struct RunningCommand {
+    child: Child,
+    started: Instant,
+}
+
+/// Spawn `cmd` with piped output, in its own process group so it can be killed as a whole
+pub fn spawn(mut cmd: Command) -> AppResult<RunningCommand> {
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    let child = cmd
+        .spawn()
+        .map_err(|e| AppError::ProcessError(format!("Failed to start command: {}", e)))?;
+    debug!(pid = ?child.id(), "Spawned command");
+    Ok(RunningCommand {
+        child,
+        started: Instant::now(),
+    })
+}
+
+impl RunningCommand {
+    /// Pass the output on until the command exits, times out or is cancelled
+    pub async fn stream<F>(
+        mut self,
+        timeout: Option<Duration>,
+        mut cancel: Option<watch::Receiver<bool>>,
+        mut on_output: F,
+    ) -> CommandExit
+    where
+        F: FnMut(OutputStream, &str),
+    {
+        let mut stdout = self.child.stdout.take();
+        let mut stderr = self.child.stderr.take();
+        let mut stdout_buf = [0u8; 8192];
+        let mut stderr_buf = [0u8; 8192];
+        let mut stdout_pending = Vec::new();
+        let mut stderr_pending = Vec::new();
+        let deadline = timeout.map(|timeout| tokio::time::Instant::from_std(self.started + timeout));
+        let mut exit = CommandExit::default();
+        let mut emitted = 0;
+
+        while stdout.is_some() || stderr.is_some() {
+            let (stream, read) = tokio::select! {
+                read = read_pipe(&mut stdout, &mut stdout_buf) => (OutputStream::Stdout, read),
+                read = read_pipe(&mut stderr, &mut stderr_buf) => (OutputStream::Stderr, read),
+                _ = sleep_until(deadline) => {
+                    exit.timed_out = true;
+                    break;
+                }
+                _ = wait_for_cancel(&mut cancel) => {
+                    exit.cancelled = true;
+                    break;
+                }
+            };
+            let (buf, pending) = match stream {
+                OutputStream::Stdout => (&stdout_buf, &mut stdout_pending),
+                OutputStream::Stderr => (&stderr_buf, &mut stderr_pending),
+            };
+            let text = match read {
+                Ok(n) if n > 0 => {
+                    pending.extend_from_slice(&buf[..n]);
+                    drain_utf8(pending)
+                }
+                // EOF or a broken pipe: flush what is left of a split character
+                _ => {
+                    match stream {
+                        OutputStream::Stdout => stdout = None,
+                        OutputStream::Stderr => stderr = None,
+                    }
+                    let rest = String::from_utf8_lossy(pending).into_owned();
+                    pending.clear();
+                    rest
+                }
+            };
+            if text.is_empty() || exit.truncated {
+                continue;
+            }
+            if emitted + text.len() > MAX_OUTPUT_BYTES {
+                exit.truncated = true;
+                let mut end = MAX_OUTPUT_BYTES - emitted;
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                on_output(stream, &text[..end]);
+                continue;
+            }
+            emitted += text.len();
+            on_output(stream, &text);
+        }
+
+        // The pipes can close while the command (or a child that closed them) keeps
+        // running, so the wait stays bounded by the same deadline and cancel
+        let status = if exit.timed_out || exit.cancelled {
+            None
+        } else {
+            tokio::select! {
+                status = self.child.wait() => Some(status),
+                _ = sleep_until(deadline) => {
+                    exit.timed_out = true;
+                    None
+                }
+                _ = wait_for_cancel(&mut cancel) => {
+                    exit.cancelled = true;
+                    None
+                }
+            }
+        };
+        let status = match status {
+            Some(status) => status,
+            None => {
+                info!(pid = ?self.child.id(), timed_out = exit.timed_out, "Killing command");
+                kill_process_tree(&mut self.child).await;
+                self.child.wait().await
+            }
+        };
+        match status {
+            Ok(status) => {
+                exit.exit_code = status.code();
+                #[cfg(unix)]
+                {
+                    use std::os::unix::process::ExitStatusExt;
+                    exit.signal = status.signal();
+                }
+            }
+            Err(e) => warn!(error = %e, "Failed to wait for command"),
+        }
+        exit.duration_ms = self.started.elapsed().as_millis() as u64;
+        exit
+    }
+}
+
+/// Read from a pipe; never resolves once the pipe is closed
+async fn read_pipe<R: AsyncRead + Unpin>(pipe: &mut Option<R>, buf: &mut [u8]) -> std::io::Result<usize> {
+    match pipe {
+        Some(pipe) => pipe.read(buf).await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn sleep_until(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Decode the complete UTF-8 prefix of `pending`, keeping a split trailing character for the next read
+pub(crate) fn drain_utf8(pending: &mut Vec<u8>) -> String {
+    let complete = match std::str::from_utf8(pending) {
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        // Valid, or invalid bytes that no further input can fix
+        _ => pending.len(),
+    };
+    let text = String::from_utf8_lossy(&pending[..complete]).into_owned();
+    pending.drain(..complete);
+    text
+}
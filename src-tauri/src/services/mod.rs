@@ -3,6 +3,15 @@
 //! This module contains business logic services for the application.
 
 pub mod ai;
-pub mod terminal;
+pub mod chat_branch;
+pub mod chat_export;
+pub mod chat_import;
+pub mod chat_retention;
+pub mod chat_schema;
+pub mod chat_search;
 pub mod chat_session;
-
+pub mod chat_summary;
+pub mod command_history;
+pub mod command_runner;
+pub mod secret_vault;
+pub mod terminal;
@@ -0,0 +1,517 @@
+//! Secret vault service
+//!
+//! Stores API keys and secret environment variables encrypted under the data
+//! directory. A random master key is kept in the OS keychain and in `vault.key`,
+//! itself encrypted under a key derived from the machine id; the file is used
+//! when the keychain is unavailable or has lost the key. A new master key is
+//! never created while the vault holds secrets. The AES-256-GCM key is derived from the master key with
+//! HKDF-SHA256 and a per-vault salt. Entries are bound to their name through the
+//! AEAD associated data.
+//!
+//! The app shares one `SharedVault`, so concurrent changes never overwrite each other.
+//! Model API keys and env var values can refer to an entry as `vault:<name>`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tracing::{debug, warn};
+
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::redact;
+
+/// Prefix marking a value as a reference to a vault entry
+pub const VAULT_REF_PREFIX: &str = "vault:";
+
+const VAULT_FILE: &str = "secrets.vault";
+const KEY_FILE: &str = "vault.key";
+const KEY_LEN: usize = 32;
+const HKDF_INFO: &[u8] = b"code-ai-assistant secret vault v1";
+/// HKDF info of the machine-bound key sealing `vault.key`
+const KEY_FILE_HKDF_INFO: &[u8] = b"code-ai-assistant vault key file v1";
+/// OS keychain entry holding the master key
+const KEYCHAIN_SERVICE: &str = "code-ai-assistant";
+const KEYCHAIN_USER: &str = "secret-vault";
+
+/// On-disk vault layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    /// Base64 HKDF salt
+    salt: String,
+    entries: BTreeMap<String, VaultEntry>,
+}
+
+/// `vault.key` layout: the master key encrypted under the machine-bound key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KeyFile {
+    version: u32,
+    /// Base64 96-bit nonce
+    nonce: String,
+    /// Base64 encrypted master key with GCM tag
+    ciphertext: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VaultEntry {
+    /// Base64 96-bit nonce
+    nonce: String,
+    /// Base64 ciphertext with GCM tag
+    ciphertext: String,
+}
+
+/// Encrypted secret store
+pub struct SecretVault {
+    dir: PathBuf,
+    cipher: Aes256Gcm,
+    file: VaultFile,
+}
+
+impl std::fmt::Debug for SecretVault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretVault")
+            .field("dir", &self.dir)
+            .field("entries", &self.file.entries.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl SecretVault {
+    /// Open the vault in the application data directory, with its master key in the
+    /// OS keychain when one is available
+    pub fn open_default() -> AppResult<Self> {
+        let dir = PathBuf::from(crate::config::get_default_data_dir()?);
+        fs::create_dir_all(&dir)?;
+        let master_key = match keychain_master_key(&dir) {
+            Ok(key) => key,
+            Err(e) => {
+                warn!(error = %e, "OS keychain unavailable, using the machine-bound vault key file");
+                load_or_create_key_file(&dir)?
+            }
+        };
+        Self::open_with_key(&dir, &master_key)
+    }
+
+    /// Open (or create) the vault stored in `dir`, with its master key in `dir`'s
+    /// machine-bound key file
+    pub fn open(dir: &Path) -> AppResult<Self> {
+        fs::create_dir_all(dir)?;
+        let master_key = load_or_create_key_file(dir)?;
+        Self::open_with_key(dir, &master_key)
+    }
+
+    fn open_with_key(dir: &Path, master_key: &[u8]) -> AppResult<Self> {
+        let vault_path = dir.join(VAULT_FILE);
+        let file = if vault_path.exists() {
+            let content = fs::read_to_string(&vault_path)?;
+            serde_json::from_str(&content)?
+        } else {
+            let mut salt = [0u8; KEY_LEN];
+            OsRng.fill_bytes(&mut salt);
+            VaultFile {
+                version: 1,
+                salt: BASE64.encode(salt),
+                entries: BTreeMap::new(),
+            }
+        };
+
+        let salt = decode(&file.salt)?;
+        let cipher = derive_cipher(Some(&salt), master_key, HKDF_INFO)?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            cipher,
+            file,
+        })
+    }
+
+    /// Names of all stored secrets
+    pub fn list(&self) -> Vec<String> {
+        self.file.entries.keys().cloned().collect()
+    }
+
+    /// Store (or replace) a secret
+    pub fn set(&mut self, name: &str, value: &str) -> AppResult<()> {
+        let name = validate_name(name)?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: value.as_bytes(),
+                    aad: name.as_bytes(),
+                },
+            )
+            .map_err(|_| AppError::GenericError(format!("Failed to encrypt secret {}", name)))?;
+
+        self.file.entries.insert(
+            name.to_string(),
+            VaultEntry {
+                nonce: BASE64.encode(nonce),
+                ciphertext: BASE64.encode(ciphertext),
+            },
+        );
+        self.save()?;
+        redact::register_secret(value);
+        debug!(name, "Secret stored");
+        Ok(())
+    }
+
+    /// Decrypt a secret; `None` if no entry exists
+    pub fn get(&self, name: &str) -> AppResult<Option<String>> {
+        let Some(entry) = self.file.entries.get(name) else {
+            return Ok(None);
+        };
+        let nonce = decode(&entry.nonce)?;
+        if nonce.len() != 12 {
+            return Err(AppError::ValidationError(format!("Corrupted vault entry: {}", name)));
+        }
+        let ciphertext = decode(&entry.ciphertext)?;
+        let plaintext = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: name.as_bytes(),
+                },
+            )
+            .map_err(|_| AppError::GenericError(format!("Failed to decrypt secret {}", name)))?;
+        let value = String::from_utf8(plaintext)
+            .map_err(|_| AppError::ValidationError(format!("Secret {} is not valid UTF-8", name)))?;
+        redact::register_secret(&value);
+        Ok(Some(value))
+    }
+
+    /// Delete a secret; returns whether it existed
+    pub fn delete(&mut self, name: &str) -> AppResult<bool> {
+        if let Ok(Some(value)) = self.get(name) {
+            redact::unregister_secret(&value);
+        }
+        let existed = self.file.entries.remove(name).is_some();
+        if existed {
+            self.save()?;
+            debug!(name, "Secret deleted");
+        }
+        Ok(existed)
+    }
+
+    /// Resolve a `vault:<name>` reference; other values are returned unchanged
+    pub fn resolve(&self, value: &str) -> AppResult<String> {
+        match value.trim().strip_prefix(VAULT_REF_PREFIX) {
+            Some(name) => self
+                .get(name.trim())?
+                .ok_or_else(|| AppError::ConfigError(format!("Secret not found in vault: {}", name))),
+            None => Ok(value.to_string()),
+        }
+    }
+
+    /// Resolve vault references in environment variable values.
+    ///
+    /// Unresolvable references are dropped with a warning so the raw reference never
+    /// reaches a child process.
+    pub fn resolve_env(&self, env: &[(String, String)]) -> Vec<(String, String)> {
+        env.iter()
+            .filter_map(|(key, value)| match self.resolve(value) {
+                Ok(resolved) => Some((key.clone(), resolved)),
+                Err(e) => {
+                    warn!(key = %key, error = %e, "Failed to resolve secret env var");
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn save(&self) -> AppResult<()> {
+        let path = self.dir.join(VAULT_FILE);
+        let tmp = self.dir.join(format!("{}.tmp", VAULT_FILE));
+        let _ = fs::remove_file(&tmp);
+        write_private(&tmp, serde_json::to_string_pretty(&self.file)?.as_bytes())?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+}
+
+/// The vault shared by the app, opened on first use.
+///
+/// Holding the lock across a change and its save keeps concurrent changes from
+/// overwriting each other.
+#[derive(Debug, Clone, Default)]
+pub struct SharedVault(Arc<Mutex<Option<SecretVault>>>);
+
+impl SharedVault {
+    /// Run `f` on the vault, opening it from the data directory first if needed
+    pub fn with<T>(&self, f: impl FnOnce(&mut SecretVault) -> AppResult<T>) -> AppResult<T> {
+        let mut vault = self
+            .0
+            .lock()
+            .map_err(|e| AppError::GenericError(format!("Failed to lock secret vault: {}", e)))?;
+        let vault = match vault.take() {
+            Some(opened) => vault.insert(opened),
+            None => vault.insert(SecretVault::open_default()?),
+        };
+        f(vault)
+    }
+}
+
+/// Whether `value` refers to a vault entry
+pub fn is_vault_ref(value: &str) -> bool {
+    value.trim().starts_with(VAULT_REF_PREFIX)
+}
+
+fn validate_name(name: &str) -> AppResult<&str> {
+    let name = name.trim();
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        return Err(AppError::ValidationError(format!(
+            "Invalid secret name (use letters, digits, '_', '-' or '.'): {}",
+            name
+        )));
+    }
+    Ok(name)
+}
+
+fn decode(value: &str) -> AppResult<Vec<u8>> {
+    BASE64
+        .decode(value)
+        .map_err(|e| AppError::SerializationError(format!("Invalid vault encoding: {}", e)))
+}
+
+fn derive_cipher(salt: Option<&[u8]>, key_material: &[u8], info: &[u8]) -> AppResult<Aes256Gcm> {
+    let hkdf = Hkdf::<Sha256>::new(salt, key_material);
+    let mut key = [0u8; KEY_LEN];
+    hkdf.expand(info, &mut key)
+        .map_err(|_| AppError::GenericError("Failed to derive vault key".to_string()))?;
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+}
+
+/// Master key from the OS keychain, with the sealed key file kept as its backup.
+///
+/// A keychain without the key (first use, or wiped) takes it from the key file,
+/// so existing secrets stay readable.
+fn keychain_master_key(dir: &Path) -> AppResult<Vec<u8>> {
+    let keychain_error = |e: keyring::Error| AppError::GenericError(format!("OS keychain error: {}", e));
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_USER).map_err(keychain_error)?;
+    match entry.get_password() {
+        Ok(encoded) => {
+            let key = decode(&encoded)?;
+            if key.len() != KEY_LEN {
+                return Err(AppError::ValidationError(
+                    "Vault key in the OS keychain is corrupted".to_string(),
+                ));
+            }
+            let key_path = dir.join(KEY_FILE);
+            if !key_path.exists() {
+                if let Err(e) = install_key_file(&key_path, &key) {
+                    warn!(error = %e, "Failed to back up the vault key to its key file");
+                }
+            }
+            Ok(key)
+        }
+        Err(keyring::Error::NoEntry) => {
+            let key = load_or_create_key_file(dir)?;
+            entry.set_password(&BASE64.encode(&key)).map_err(keychain_error)?;
+            debug!("Stored vault key in the OS keychain");
+            Ok(key)
+        }
+        Err(e) => Err(keychain_error(e)),
+    }
+}
+
+/// Cipher sealing `vault.key`, derived from the machine id so the file is useless elsewhere
+fn machine_cipher() -> AppResult<Aes256Gcm> {
+    let machine_id =
+        machine_uid::get().map_err(|e| AppError::GenericError(format!("Failed to read the machine id: {}", e)))?;
+    derive_cipher(None, machine_id.trim().as_bytes(), KEY_FILE_HKDF_INFO)
+}
+
+/// Master key from `dir`'s key file, created there if the vault holds no secrets yet
+fn load_or_create_key_file(dir: &Path) -> AppResult<Vec<u8>> {
+    let path = dir.join(KEY_FILE);
+    if path.exists() {
+        return read_key_file(&path);
+    }
+    ensure_no_secrets(dir)?;
+    install_key_file(&path, &random_key())
+}
+
+/// Refuse to replace a lost master key while secrets encrypted under it remain
+fn ensure_no_secrets(dir: &Path) -> AppResult<()> {
+    let vault_path = dir.join(VAULT_FILE);
+    if !vault_path.exists() {
+        return Ok(());
+    }
+    let file: VaultFile = serde_json::from_str(&fs::read_to_string(&vault_path)?)?;
+    if file.entries.is_empty() {
+        return Ok(());
+    }
+    Err(AppError::ConfigError(format!(
+        "The vault key is missing but {} holds {} secret(s); restore {} or the OS keychain entry instead of creating a new key",
+        vault_path.display(),
+        file.entries.len(),
+        KEY_FILE
+    )))
+}
+
+/// Write `key` sealed to `path` unless it exists; returns the key the file ends up holding
+fn install_key_file(path: &Path, key: &[u8]) -> AppResult<Vec<u8>> {
+    // Linking a complete file into place never exposes a partly written key
+    let tmp = write_sealed_key(path, key)?;
+    let linked = fs::hard_link(&tmp, path);
+    let _ = fs::remove_file(&tmp);
+    match linked {
+        Ok(()) => {
+            debug!(path = %path.display(), "Wrote vault key file");
+            Ok(key.to_vec())
+        }
+        // Created concurrently; use that key so both openers agree
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => read_key_file(path),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Read `vault.key`; a plaintext key from older versions is sealed in place
+fn read_key_file(path: &Path) -> AppResult<Vec<u8>> {
+    let content = fs::read(path)?;
+    if content.len() == KEY_LEN {
+        let key = content;
+        fs::rename(write_sealed_key(path, &key)?, path)?;
+        debug!(path = %path.display(), "Sealed plaintext vault key");
+        return Ok(key);
+    }
+
+    let corrupted = || AppError::ValidationError(format!("Vault key file is corrupted: {}", path.display()));
+    let file: KeyFile = serde_json::from_slice(&content).map_err(|_| corrupted())?;
+    let nonce = decode(&file.nonce)?;
+    if nonce.len() != 12 {
+        return Err(corrupted());
+    }
+    let key = machine_cipher()?
+        .decrypt(Nonce::from_slice(&nonce), decode(&file.ciphertext)?.as_slice())
+        .map_err(|_| {
+            AppError::GenericError(format!(
+                "Failed to decrypt vault key (was the data directory copied from another machine?): {}",
+                path.display()
+            ))
+        })?;
+    if key.len() != KEY_LEN {
+        return Err(corrupted());
+    }
+    Ok(key)
+}
+
+/// Write `key` sealed with the machine-bound key to a new temp file next to `path`
+fn write_sealed_key(path: &Path, key: &[u8]) -> AppResult<PathBuf> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = machine_cipher()?
+        .encrypt(&nonce, key)
+        .map_err(|_| AppError::GenericError("Failed to encrypt vault key".to_string()))?;
+    let file = KeyFile {
+        version: 1,
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    };
+    let tmp = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+    write_private(&tmp, serde_json::to_string_pretty(&file)?.as_bytes())?;
+    Ok(tmp)
+}
+
+fn random_key() -> Vec<u8> {
+    let mut key = vec![0u8; KEY_LEN];
+    OsRng.fill_bytes(&mut key);
+    key
+}
+
+/// Create `path` readable by the owner only from the start and write `content`;
+/// fails if it already exists
+fn write_private(path: &Path, content: &[u8]) -> AppResult<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_file_is_sealed_and_private() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut vault = SecretVault::open(dir.path()).unwrap();
+        vault.set("token", "s3cret").unwrap();
+
+        let key_file = fs::read(dir.path().join(KEY_FILE)).unwrap();
+        assert_ne!(key_file.len(), KEY_LEN);
+        serde_json::from_slice::<KeyFile>(&key_file).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            for file in [KEY_FILE, VAULT_FILE] {
+                let mode = fs::metadata(dir.path().join(file)).unwrap().permissions().mode();
+                assert_eq!(mode & 0o777, 0o600, "{}", file);
+            }
+        }
+
+        let vault = SecretVault::open(dir.path()).unwrap();
+        assert_eq!(vault.get("token").unwrap().as_deref(), Some("s3cret"));
+    }
+
+    #[test]
+    fn concurrent_opens_agree_on_the_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let keys: Vec<Vec<u8>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| load_or_create_key_file(dir.path()).unwrap()))
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+        assert!(keys.iter().all(|key| key == &keys[0]));
+    }
+
+    #[test]
+    fn plaintext_key_file_is_sealed_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = random_key();
+        fs::write(dir.path().join(KEY_FILE), &key).unwrap();
+
+        assert_eq!(load_or_create_key_file(dir.path()).unwrap(), key);
+        assert_ne!(fs::read(dir.path().join(KEY_FILE)).unwrap(), key);
+        assert_eq!(load_or_create_key_file(dir.path()).unwrap(), key);
+    }
+
+    #[test]
+    fn lost_key_is_not_replaced_while_secrets_remain() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut vault = SecretVault::open(dir.path()).unwrap();
+        vault.set("token", "s3cret").unwrap();
+        fs::remove_file(dir.path().join(KEY_FILE)).unwrap();
+
+        assert!(SecretVault::open(dir.path()).is_err());
+        assert!(!dir.path().join(KEY_FILE).exists());
+
+        vault.delete("token").unwrap();
+        SecretVault::open(dir.path()).unwrap();
+    }
+}
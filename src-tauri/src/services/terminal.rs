@@ -1,15 +1,134 @@
 //! Terminal Service module
 //!
-//! This module handles terminal session management.
+//! This module handles terminal session management. Each session can own a
+//! long-lived shell in a pseudo-terminal; its output is passed on as it arrives,
+//! ANSI sequences included, for the xterm frontend to render.
+//!
+//! A session follows the shell's working directory through OSC 7 sequences
+//! (`ESC ] 7 ; file://host/path BEL`), which bash is set up to print with every
+//! prompt; on Linux the shell's cwd is also read from `/proc`.
+//!
+//! Each session keeps the tail of its output. Open sessions are saved with their
+//! scrollback when the app quits and reopened for their workspace on the next launch.
 
 use std::collections::HashMap;
-use std::process::{Child, Command, Stdio};
-use std::sync::{Arc, Mutex};
-use tracing::{error, info};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use portable_pty::{ChildKiller, CommandBuilder, MasterPty, PtySize, native_pty_system};
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use tracing::{debug, error, info};
 use uuid::Uuid;
 
+use super::command_runner::{self, RunningCommand, drain_utf8};
+use crate::database::repositories::terminal_session_repository::{TerminalSessionRecord, TerminalSessionRepository};
 use crate::utils::error::{AppError, AppResult};
 
+/// How long to wait for the last output after the shell exited
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Output kept per session
+const SCROLLBACK_BYTES: usize = 256 * 1024;
+/// Output saved per session on quit
+const SAVED_SCROLLBACK_BYTES: usize = 64 * 1024;
+
+/// Start of an OSC 7 (current directory) sequence
+const OSC7_START: &str = "\x1b]7;";
+/// Longer unterminated OSC 7 sequences are not waited for
+const MAX_OSC7_LEN: usize = 4096;
+/// Prompt hook making bash report its directory
+const BASH_OSC7_HOOK: &str = r#"printf '\033]7;file://%s%s\033\\' "${HOSTNAME}" "${PWD}""#;
+
+/// Size of a terminal in character cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TerminalSize {
+    pub cols: u16,
+    pub rows: u16,
+}
+
+impl Default for TerminalSize {
+    fn default() -> Self {
+        Self { cols: 80, rows: 24 }
+    }
+}
+
+impl From<TerminalSize> for PtySize {
+    fn from(size: TerminalSize) -> Self {
+        PtySize {
+            rows: size.rows.max(1),
+            cols: size.cols.max(1),
+            pixel_width: 0,
+            pixel_height: 0,
+        }
+    }
+}
+
+/// Shell running in a pseudo-terminal
+struct PtyProcess {
+    pid: Option<u32>,
+    master: Box<dyn MasterPty + Send>,
+    writer: Box<dyn Write + Send>,
+    killer: Box<dyn ChildKiller + Send + Sync>,
+}
+
+impl std::fmt::Debug for PtyProcess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PtyProcess")
+            .field("pid", &self.pid)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Tail of a session's output, ANSI sequences included
+#[derive(Debug, Default)]
+struct Scrollback {
+    text: String,
+}
+
+impl Scrollback {
+    fn push(&mut self, output: &str) {
+        self.text.push_str(output);
+        // Trim in bulk instead of on every read
+        if self.text.len() > SCROLLBACK_BYTES + SCROLLBACK_BYTES / 4 {
+            let cut = char_boundary_after(&self.text, self.text.len() - SCROLLBACK_BYTES);
+            self.text.drain(..cut);
+        }
+    }
+
+    /// The last `max_bytes` at most, starting at a line so no escape sequence is cut in half
+    fn tail(&self, max_bytes: usize) -> &str {
+        if self.text.len() <= max_bytes {
+            return &self.text;
+        }
+        let tail = &self.text[char_boundary_after(&self.text, self.text.len() - max_bytes)..];
+        tail.find('\n').map_or(tail, |newline| &tail[newline + 1..])
+    }
+}
+
+fn char_boundary_after(text: &str, index: usize) -> usize {
+    (index..text.len())
+        .find(|&i| text.is_char_boundary(i))
+        .unwrap_or(text.len())
+}
+
+/// Options of a new terminal session
+#[derive(Debug, Clone, Default)]
+pub struct NewTerminalSession {
+    /// Defaults to `Terminal <n>`
+    pub name: Option<String>,
+    /// Defaults to the app's working directory
+    pub cwd: Option<String>,
+    pub env: Vec<(String, String)>,
+    pub workspace_id: Option<i32>,
+    /// Output of an earlier run of a restored session
+    pub scrollback: String,
+}
+
 /// Terminal session
 #[derive(Debug)]
 pub struct TerminalSession {
@@ -17,29 +136,71 @@ pub struct TerminalSession {
     pub id: String,
     /// Session name
     pub name: String,
-    /// Working directory
+    /// Working directory, updated as the shell reports it
     pub cwd: String,
-    /// Process handle
-    process: Option<Child>,
+    /// Extra environment of the shell and of commands run in the session
+    pub env: Vec<(String, String)>,
+    /// Workspace the session was opened in
+    pub workspace_id: Option<i32>,
+    /// Shell selected when the shell was started
+    pub shell: Option<String>,
+    scrollback: Scrollback,
+    opened_at: Instant,
+    /// Interactive shell, once started
+    pty: Option<PtyProcess>,
+}
+
+/// What the UI shows about a session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TerminalState {
+    pub id: String,
+    pub name: String,
+    pub cwd: String,
+    /// Branch (or short commit when detached) of the git repository containing `cwd`
+    pub git_branch: Option<String>,
+    pub shell: Option<String>,
+    pub workspace_id: Option<i32>,
+    /// Whether the interactive shell is running
+    pub running: bool,
+    pub pid: Option<u32>,
 }
 
 impl TerminalSession {
     /// Create a new terminal session
-    pub fn new(name: String, cwd: String) -> Self {
+    pub fn new(name: String, cwd: String, options: NewTerminalSession) -> Self {
+        let mut scrollback = Scrollback::default();
+        scrollback.push(&options.scrollback);
         Self {
             id: Uuid::new_v4().to_string(),
             name,
             cwd,
-            process: None,
+            env: options.env,
+            workspace_id: options.workspace_id,
+            shell: None,
+            scrollback,
+            opened_at: Instant::now(),
+            pty: None,
+        }
+    }
+
+    fn state(&self) -> TerminalState {
+        TerminalState {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            cwd: self.cwd.clone(),
+            git_branch: None,
+            shell: self.shell.clone(),
+            workspace_id: self.workspace_id,
+            running: self.pty.is_some(),
+            pid: self.pty.as_ref().and_then(|pty| pty.pid),
         }
     }
 
     /// Kill the terminal process
     pub fn kill(&mut self) -> AppResult<()> {
-        if let Some(ref mut process) = self.process {
-            process.kill().map_err(|e| AppError::ProcessError(e.to_string()))?;
+        if let Some(mut pty) = self.pty.take() {
+            pty.killer.kill().map_err(|e| AppError::ProcessError(e.to_string()))?;
         }
-        self.process = None;
         Ok(())
     }
 }
@@ -60,11 +221,14 @@ impl TerminalService {
     }
 
     /// Create a new terminal session
-    pub fn create_session(&self, name: Option<String>, cwd: Option<String>) -> AppResult<String> {
-        let session_name = name.unwrap_or_else(|| format!("Terminal {}", self.session_count() + 1));
-        let working_dir = cwd.unwrap_or_else(|| ".".to_string());
+    pub fn create_session(&self, mut options: NewTerminalSession) -> AppResult<String> {
+        let session_name = options
+            .name
+            .take()
+            .unwrap_or_else(|| format!("Terminal {}", self.session_count() + 1));
+        let working_dir = options.cwd.take().unwrap_or_else(|| ".".to_string());
 
-        let session = TerminalSession::new(session_name, working_dir);
+        let session = TerminalSession::new(session_name, working_dir, options);
         let session_id = session.id.clone();
 
         let mut sessions = self.sessions.lock().map_err(|e| {
@@ -91,15 +255,194 @@ impl TerminalService {
         Ok(())
     }
 
-    /// Execute command in a session
+    /// Start an interactive shell for a session in a pseudo-terminal.
+    ///
+    /// `on_output` receives the output as it arrives; `on_exit` is called with the
+    /// exit code once the shell exited, after the session was removed.
+    pub fn start_shell<O, E>(
+        &self,
+        session_id: &str,
+        shell: Option<&str>,
+        size: TerminalSize,
+        mut on_output: O,
+        on_exit: E,
+    ) -> AppResult<()>
+    where
+        O: FnMut(&str) + Send + 'static,
+        E: FnOnce(Option<u32>) + Send + 'static,
+    {
+        let mut sessions = self
+            .sessions
+            .lock()
+            .map_err(|e| AppError::ProcessError(format!("Failed to lock sessions: {}", e)))?;
+        let session = sessions
+            .get_mut(session_id)
+            .ok_or_else(|| AppError::ProcessError(format!("Session not found: {}", session_id)))?;
+        if session.pty.is_some() {
+            return Err(AppError::ProcessError(format!(
+                "Shell already running in session: {}",
+                session_id
+            )));
+        }
+
+        let mut cmd = shell_command(shell)?;
+        session.shell = shell.map(str::to_string);
+        cmd.cwd(&session.cwd);
+        for (key, value) in &session.env {
+            cmd.env(key, value);
+        }
+        cmd.env("TERM", "xterm-256color");
+        cmd.env("COLORTERM", "truecolor");
+        if cmd
+            .get_argv()
+            .first()
+            .is_some_and(|program| program.to_string_lossy().ends_with("bash"))
+        {
+            let prompt_command = match cmd.get_env("PROMPT_COMMAND").map(|c| c.to_string_lossy().into_owned()) {
+                Some(existing) if !existing.trim().is_empty() => format!("{};{}", BASH_OSC7_HOOK, existing),
+                _ => BASH_OSC7_HOOK.to_string(),
+            };
+            cmd.env("PROMPT_COMMAND", prompt_command);
+        }
+
+        let pair = native_pty_system()
+            .openpty(size.into())
+            .map_err(|e| AppError::ProcessError(format!("Failed to open pty: {}", e)))?;
+        let mut child = pair
+            .slave
+            .spawn_command(cmd)
+            .map_err(|e| AppError::ProcessError(format!("Failed to start shell: {}", e)))?;
+        // Only the shell keeps the slave side open, so the reader sees EOF once it exits
+        drop(pair.slave);
+        let mut reader = pair
+            .master
+            .try_clone_reader()
+            .map_err(|e| AppError::ProcessError(e.to_string()))?;
+        let writer = pair
+            .master
+            .take_writer()
+            .map_err(|e| AppError::ProcessError(e.to_string()))?;
+
+        let pid = child.process_id();
+        session.pty = Some(PtyProcess {
+            pid,
+            master: pair.master,
+            writer,
+            killer: child.clone_killer(),
+        });
+        info!("Started shell in terminal session {} (pid {:?})", session_id, pid);
+
+        let (done_tx, done_rx) = mpsc::channel::<()>();
+        let reader_sessions = Arc::clone(&self.sessions);
+        let reader_session_id = session_id.to_string();
+        thread::spawn(move || {
+            let mut buf = [0u8; 8192];
+            let mut pending = Vec::new();
+            let mut osc7 = Osc7Parser::default();
+            loop {
+                match reader.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        pending.extend_from_slice(&buf[..n]);
+                        let text = drain_utf8(&mut pending);
+                        if text.is_empty() {
+                            continue;
+                        }
+                        let cwd = osc7.push(&text);
+                        if let Some(session) = reader_sessions
+                            .lock()
+                            .ok()
+                            .as_mut()
+                            .and_then(|sessions| sessions.get_mut(&reader_session_id))
+                        {
+                            session.scrollback.push(&text);
+                            if let Some(cwd) = cwd {
+                                debug!("Terminal session {} changed directory to {}", reader_session_id, cwd);
+                                session.cwd = cwd;
+                            }
+                        }
+                        on_output(&text);
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    // Linux reports EIO once the slave side is closed
+                    Err(_) => break,
+                }
+            }
+            let _ = done_tx.send(());
+        });
+
+        let sessions = Arc::clone(&self.sessions);
+        let session_id = session_id.to_string();
+        thread::spawn(move || {
+            let code = match child.wait() {
+                Ok(status) => Some(status.exit_code()),
+                Err(e) => {
+                    error!("Failed to wait for shell of terminal session {}: {}", session_id, e);
+                    None
+                }
+            };
+            // Background jobs may keep the pty open; don't wait for them
+            let _ = done_rx.recv_timeout(OUTPUT_DRAIN_TIMEOUT);
+            if let Ok(mut sessions) = sessions.lock() {
+                sessions.remove(&session_id);
+            }
+            info!("Shell of terminal session {} exited with {:?}", session_id, code);
+            on_exit(code);
+        });
+
+        Ok(())
+    }
+
+    /// Write input (keystrokes or pasted text) to a session's shell
+    pub fn write_input(&self, session_id: &str, data: &str) -> AppResult<()> {
+        let mut sessions = self
+            .sessions
+            .lock()
+            .map_err(|e| AppError::ProcessError(format!("Failed to lock sessions: {}", e)))?;
+        let pty = sessions
+            .get_mut(session_id)
+            .and_then(|session| session.pty.as_mut())
+            .ok_or_else(|| AppError::ProcessError(format!("No shell running in session: {}", session_id)))?;
+
+        pty.writer
+            .write_all(data.as_bytes())
+            .and_then(|_| pty.writer.flush())
+            .map_err(|e| AppError::ProcessError(e.to_string()))
+    }
+
+    /// Resize a session's pseudo-terminal
+    pub fn resize(&self, session_id: &str, size: TerminalSize) -> AppResult<()> {
+        let sessions = self
+            .sessions
+            .lock()
+            .map_err(|e| AppError::ProcessError(format!("Failed to lock sessions: {}", e)))?;
+        let pty = sessions
+            .get(session_id)
+            .and_then(|session| session.pty.as_ref())
+            .ok_or_else(|| AppError::ProcessError(format!("No shell running in session: {}", session_id)))?;
+
+        debug!(
+            "Resizing terminal session {} to {}x{}",
+            session_id, size.cols, size.rows
+        );
+        pty.master
+            .resize(size.into())
+            .map_err(|e| AppError::ProcessError(e.to_string()))
+    }
+
+    /// Start a non-interactive command with a session's environment, in `cwd` or
+    /// else the session's working directory
+    ///
+    /// The caller streams its output with `RunningCommand::stream`.
     pub fn execute_command(
         &self,
         session_id: &str,
         shell: &str,
         command_line: &str,
-    ) -> AppResult<String> {
-        // 先在短时间内获取会话工作目录，然后释放锁，避免长时间持有锁阻塞并行执行
-        let cwd = {
+        cwd: Option<&str>,
+    ) -> AppResult<RunningCommand> {
+        // 先在短时间内获取会话工作目录和环境变量，然后释放锁，避免长时间持有锁阻塞并行执行
+        let (cwd, env) = {
             let sessions = self.sessions.lock().map_err(|e| {
                 AppError::ProcessError(format!("Failed to lock sessions: {}", e))
             })?;
@@ -108,7 +451,10 @@ impl TerminalService {
                 AppError::ProcessError(format!("Session not found: {}", session_id))
             })?;
 
-            session.cwd.clone()
+            (
+                cwd.map_or_else(|| session.cwd.clone(), str::to_string),
+                session.env.clone(),
+            )
         };
 
         // 根据前端选择的 shell 校验并构造具体命令
@@ -164,21 +510,85 @@ impl TerminalService {
             session_id, shell, command_line
         );
 
-        let output = cmd
-            .current_dir(&cwd)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()
-            .map_err(|e| AppError::ProcessError(e.to_string()))?;
+        cmd.current_dir(&cwd).envs(env);
+        command_runner::spawn(cmd)
+    }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
+    /// Current directory, git branch and shell status of a session
+    pub fn state(&self, session_id: &str) -> AppResult<TerminalState> {
+        let state = {
+            let sessions = self
+                .sessions
+                .lock()
+                .map_err(|e| AppError::ProcessError(format!("Failed to lock sessions: {}", e)))?;
+            let session = sessions
+                .get(session_id)
+                .ok_or_else(|| AppError::ProcessError(format!("Session not found: {}", session_id)))?;
+            session.state()
+        };
+        Ok(live_state(state))
+    }
 
-        if !stderr.is_empty() {
-            error!("Command stderr: {}", stderr);
-        }
+    /// Output kept for a session, e.g. to fill a reopened terminal view
+    pub fn scrollback(&self, session_id: &str) -> AppResult<String> {
+        let sessions = self
+            .sessions
+            .lock()
+            .map_err(|e| AppError::ProcessError(format!("Failed to lock sessions: {}", e)))?;
+        let session = sessions
+            .get(session_id)
+            .ok_or_else(|| AppError::ProcessError(format!("Session not found: {}", session_id)))?;
+        Ok(session.scrollback.tail(SCROLLBACK_BYTES).to_string())
+    }
+
+    /// All sessions in the order they were opened
+    pub fn list_sessions(&self) -> Vec<TerminalState> {
+        let mut sessions: Vec<(Instant, TerminalState)> = self
+            .sessions
+            .lock()
+            .map(|s| s.values().map(|session| (session.opened_at, session.state())).collect())
+            .unwrap_or_default();
+        sessions.sort_by_key(|(opened_at, _)| *opened_at);
+        sessions.into_iter().map(|(_, state)| live_state(state)).collect()
+    }
+
+    /// Save the open sessions with the tail of their scrollback; returns how many were saved
+    pub async fn save_sessions(&self, db: &DatabaseConnection) -> AppResult<usize> {
+        let mut sessions: Vec<(Instant, TerminalSessionRecord)> = {
+            let sessions = self
+                .sessions
+                .lock()
+                .map_err(|e| AppError::ProcessError(format!("Failed to lock sessions: {}", e)))?;
+            sessions
+                .values()
+                .map(|session| {
+                    let cwd = live_state(session.state()).cwd;
+                    let record = TerminalSessionRecord {
+                        id: session.id.clone(),
+                        workspace_id: session.workspace_id,
+                        name: session.name.clone(),
+                        cwd,
+                        shell: session.shell.clone(),
+                        scrollback: session.scrollback.tail(SAVED_SCROLLBACK_BYTES).to_string(),
+                        position: 0,
+                    };
+                    (session.opened_at, record)
+                })
+                .collect()
+        };
+        sessions.sort_by_key(|(opened_at, _)| *opened_at);
+        let records: Vec<TerminalSessionRecord> = sessions
+            .into_iter()
+            .enumerate()
+            .map(|(position, (_, record))| TerminalSessionRecord {
+                position: position as i32,
+                ..record
+            })
+            .collect();
 
-        Ok(stdout.to_string())
+        let count = records.len();
+        TerminalSessionRepository::save_all(db, records).await?;
+        Ok(count)
     }
 
     /// Get session count
@@ -186,12 +596,162 @@ impl TerminalService {
         self.sessions.lock().map(|s| s.len()).unwrap_or(0)
     }
 
-    /// Get all session IDs
-    pub fn get_session_ids(&self) -> Vec<String> {
-        self.sessions
-            .lock()
-            .map(|s| s.keys().cloned().collect())
-            .unwrap_or_default()
+}
+
+/// Fill in the shell's current directory (Linux, for shells that don't report it) and git branch
+fn live_state(mut state: TerminalState) -> TerminalState {
+    #[cfg(target_os = "linux")]
+    if let Some(cwd) = state
+        .pid
+        .and_then(|pid| std::fs::read_link(format!("/proc/{}/cwd", pid)).ok())
+    {
+        state.cwd = cwd.to_string_lossy().into_owned();
+    }
+    state.git_branch = git_branch(Path::new(&state.cwd));
+    state
+}
+
+/// Picks the directories reported with OSC 7 out of a shell's output
+#[derive(Debug, Default)]
+struct Osc7Parser {
+    /// Start of a sequence split across reads
+    partial: String,
+}
+
+impl Osc7Parser {
+    /// Feed the next piece of output; returns the last directory reported in it
+    fn push(&mut self, text: &str) -> Option<String> {
+        let mut data = std::mem::take(&mut self.partial);
+        data.push_str(text);
+        let mut cwd = None;
+        let mut rest = data.as_str();
+        while let Some(start) = rest.find(OSC7_START) {
+            let body = &rest[start + OSC7_START.len()..];
+            // Terminated by BEL or ST (`ESC \`)
+            match body.find(['\x07', '\x1b']) {
+                Some(end) => {
+                    cwd = parse_file_url(&body[..end]).or(cwd);
+                    rest = &body[end..];
+                }
+                None => {
+                    if body.len() < MAX_OSC7_LEN {
+                        self.partial = rest[start..].to_string();
+                    }
+                    return cwd;
+                }
+            }
+        }
+        // The read may end in the middle of the introducer
+        if let Some(len) = (1..OSC7_START.len())
+            .rev()
+            .find(|&len| rest.ends_with(&OSC7_START[..len]))
+        {
+            self.partial = OSC7_START[..len].to_string();
+        }
+        cwd
+    }
+}
+
+/// Local path of a `file://host/path` URL
+fn parse_file_url(url: &str) -> Option<String> {
+    let rest = url.strip_prefix("file://")?;
+    let path = &rest[rest.find('/')?..];
+    let path = percent_decode(path);
+    // `/C:/Users` on Windows
+    let path = match path.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => path[1..].to_string(),
+        _ => path,
+    };
+    (!path.is_empty()).then_some(path)
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Branch checked out in the git repository containing `dir`, or the short commit when detached
+pub fn git_branch(dir: &Path) -> Option<String> {
+    let git_dir = dir.ancestors().find_map(|ancestor| {
+        let dot_git = ancestor.join(".git");
+        if dot_git.is_dir() {
+            return Some(dot_git);
+        }
+        // Worktrees and submodules point to their git directory
+        let link = std::fs::read_to_string(&dot_git).ok()?;
+        let target = PathBuf::from(link.trim().strip_prefix("gitdir:")?.trim());
+        Some(if target.is_absolute() {
+            target
+        } else {
+            ancestor.join(target)
+        })
+    })?;
+
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+    match head.strip_prefix("ref:") {
+        Some(reference) => {
+            let reference = reference.trim();
+            Some(reference.strip_prefix("refs/heads/").unwrap_or(reference).to_string())
+        }
+        None => head.get(..7).map(str::to_string),
+    }
+}
+
+/// Command line of an interactive shell; `None` picks the platform default
+fn shell_command(shell: Option<&str>) -> AppResult<CommandBuilder> {
+    #[cfg(target_os = "windows")]
+    {
+        let shell_norm = shell.map(|s| s.trim().to_lowercase());
+        match shell_norm.as_deref() {
+            None | Some("powershell") | Some("powershell.exe") => {
+                let mut c = CommandBuilder::new("powershell.exe");
+                c.arg("-NoLogo");
+                Ok(c)
+            }
+            Some("pwsh") | Some("pwsh.exe") => {
+                let mut c = CommandBuilder::new("pwsh.exe");
+                c.arg("-NoLogo");
+                Ok(c)
+            }
+            Some("cmd") | Some("cmd.exe") => Ok(CommandBuilder::new("cmd.exe")),
+            Some(other) => Err(AppError::ProcessError(format!(
+                "Unsupported shell on Windows: {}",
+                other
+            ))),
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let program = match shell.map(|s| s.trim().to_lowercase()) {
+            Some(name) if matches!(name.as_str(), "bash" | "zsh" | "sh") => name,
+            Some(other) => {
+                return Err(AppError::ProcessError(format!(
+                    "Unsupported shell on Unix-like system: {}",
+                    other
+                )));
+            }
+            None => std::env::var("SHELL").unwrap_or_else(|_| "bash".to_string()),
+        };
+        let mut c = CommandBuilder::new(program);
+        c.arg("-l");
+        Ok(c)
     }
 }
 
@@ -0,0 +1,205 @@
+//! Tauri commands for the AI model registry
+
+use tauri::{AppHandle, Manager};
+use tracing::{debug, info};
+
+use super::event_handlers::emit_settings_updated;
+use crate::core::AppState;
+use crate::services::ai::context::ContextReport;
+use crate::services::ai::usage::{self, ModelPrice, UsageDimension, UsageQuery, UsageReport};
+use crate::services::ai::{AiChatOptions, AiModel, MASKED_API_KEY};
+use crate::utils::error::{AppError, AppResult};
+
+/// Get all registered AI models, with plaintext API keys masked
+#[tauri::command]
+pub async fn get_ai_models(app: AppHandle) -> AppResult<Vec<AiModel>> {
+    debug!("Getting AI models");
+    let state = app.state::<AppState>();
+    let models = state.ai.lock().unwrap().list_models();
+    // 明文密钥不返回前端，vault 引用原样返回
+    Ok(models.iter().map(AiModel::masked).collect())
+}
+
+/// Get the current AI model id
+#[tauri::command]
+pub async fn get_current_ai_model(app: AppHandle) -> AppResult<Option<String>> {
+    let state = app.state::<AppState>();
+    let current = state.ai.lock().unwrap().current_model();
+    Ok(current)
+}
+
+/// Set the current AI model
+#[tauri::command]
+pub async fn set_ai_model(app: AppHandle, model_id: String) -> AppResult<()> {
+    info!("Setting current AI model: {}", model_id);
+    update_registry(&app, |ai| ai.set_current_model(model_id)).await
+}
+
+/// Add an AI model
+#[tauri::command]
+pub async fn add_ai_model(app: AppHandle, model: AiModel) -> AppResult<()> {
+    info!("Adding AI model: {}", model.name);
+    if model.name.trim().is_empty() {
+        return Err(AppError::ValidationError("Model name is required".to_string()));
+    }
+    update_registry(&app, |ai| {
+        let mut model = model;
+        // 掩码只能代表已保存的密钥；复制出的新模型需要重新填写
+        if model.api_key == MASKED_API_KEY {
+            let id = if model.id.trim().is_empty() {
+                &model.name
+            } else {
+                &model.id
+            };
+            match ai.list_models().into_iter().find(|m| &m.id == id) {
+                Some(existing) => model.api_key = existing.api_key,
+                None => {
+                    return Err(AppError::ValidationError(
+                        "API key is masked; enter the key for the new model".to_string(),
+                    ));
+                }
+            }
+        }
+        ai.add_model(model);
+        Ok(())
+    })
+    .await
+}
+
+/// Update an existing AI model (matched by id)
+#[tauri::command]
+pub async fn update_ai_model(app: AppHandle, model: AiModel) -> AppResult<()> {
+    info!("Updating AI model: {}", model.id);
+    update_registry(&app, |ai| {
+        let Some(existing) = ai.list_models().into_iter().find(|m| m.id == model.id) else {
+            return Err(AppError::ConfigError(format!("Model not found: {}", model.id)));
+        };
+        let mut model = model;
+        // 前端拿到的是掩码，原样传回表示密钥未修改
+        if model.api_key == MASKED_API_KEY {
+            model.api_key = existing.api_key;
+        }
+        ai.add_model(model);
+        Ok(())
+    })
+    .await
+}
+
+/// Remove an AI model
+#[tauri::command]
+pub async fn remove_ai_model(app: AppHandle, model_id: String) -> AppResult<()> {
+    info!("Removing AI model: {}", model_id);
+    let mut removed = None;
+    update_registry(&app, |ai| {
+        removed = ai.list_models().into_iter().find(|m| m.id == model_id);
+        if ai.remove_model(&model_id) {
+            Ok(())
+        } else {
+            Err(AppError::ConfigError(format!("Model not found: {}", model_id)))
+        }
+    })
+    .await?;
+    // 注册表保存成功后再删除 vault 中的密钥
+    if let Some(model) = removed {
+        crate::core::app::get_ai_service(app.state::<AppState>()).forget_api_key(&model);
+    }
+    Ok(())
+}
+
+/// List the models served by an OpenAI-compatible endpoint (Ollama / llama.cpp / vLLM)
+#[tauri::command]
+pub async fn discover_ai_models(app: AppHandle, base_url: String, api_key: Option<String>) -> AppResult<Vec<String>> {
+    debug!("Discovering AI models at: {}", base_url);
+    let ai = crate::core::app::get_ai_service(app.state::<AppState>());
+    ai.discover_models(&base_url, api_key.as_deref()).await
+}
+
+/// Estimate what would be attached for `context_files` ("3 files, ~18k tokens") without sending
+#[tauri::command]
+pub async fn preview_chat_context(
+    app: AppHandle,
+    message: String,
+    context_files: Vec<String>,
+    code_cli: Option<String>,
+    model: Option<String>,
+    workspace_dir: Option<String>,
+) -> AppResult<ContextReport> {
+    debug!("Previewing chat context for {} files", context_files.len());
+    let ai = crate::core::app::get_ai_service(app.state::<AppState>());
+    let options = AiChatOptions {
+        code_cli,
+        model,
+        workspace_dir,
+        ..Default::default()
+    };
+    tauri::async_runtime::spawn_blocking(move || ai.preview_context(&message, &context_files, &options))
+        .await
+        .map_err(|e| AppError::GenericError(format!("预览上下文任务失败: {}", e)))
+}
+
+/// Requests, tokens, estimated cost and failure rate of chat runs.
+///
+/// Grouped by day, workspace, backend and model unless `group_by` names a subset.
+#[tauri::command]
+pub async fn get_usage_report(
+    app: AppHandle,
+    workspace_id: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    group_by: Option<Vec<UsageDimension>>,
+) -> AppResult<UsageReport> {
+    debug!(workspace_id = ?workspace_id, since = ?since, until = ?until, "Building usage report");
+    let workspace_id = workspace_id
+        .map(|id| {
+            id.parse::<i32>()
+                .map_err(|_| AppError::ValidationError(format!("Invalid workspace id: {}", id)))
+        })
+        .transpose()?;
+    let db = crate::database::connection::get_db_connection(&app).await?;
+    let query = UsageQuery {
+        workspace_id,
+        since,
+        until,
+        group_by: group_by.unwrap_or_default(),
+    };
+    usage::usage_report(&db, &query).await
+}
+
+/// Get the model price table used for cost estimates
+#[tauri::command]
+pub async fn get_model_prices(app: AppHandle) -> AppResult<Vec<ModelPrice>> {
+    let db = crate::database::connection::get_db_connection(&app).await?;
+    usage::load_prices(&db).await
+}
+
+/// Replace the model price table (USD per million tokens)
+#[tauri::command]
+pub async fn set_model_prices(app: AppHandle, prices: Vec<ModelPrice>) -> AppResult<()> {
+    info!("Saving {} model prices", prices.len());
+    let db = crate::database::connection::get_db_connection(&app).await?;
+    usage::save_prices(&db, &prices).await?;
+    emit_settings_updated(&app)?;
+    Ok(())
+}
+
+/// Apply `change` to a copy of the registry, persist it, then publish it to the app state.
+///
+/// Runs under `AppState::ai_update`, so a concurrent change starts from this one's result.
+async fn update_registry<F>(app: &AppHandle, change: F) -> AppResult<()>
+where
+    F: FnOnce(&mut crate::services::ai::AiService) -> AppResult<()>,
+{
+    let state = app.state::<AppState>();
+    let _update = state.ai_update.lock().await;
+    let mut ai = crate::core::app::get_ai_service(app.state::<AppState>());
+    change(&mut ai)?;
+    // 明文密钥存入 vault，设置表只保留 vault: 引用
+    ai.seal_api_keys()?;
+
+    let db = crate::database::connection::get_db_connection(app).await?;
+    ai.save_settings(&db).await?;
+
+    *state.ai.lock().unwrap() = ai;
+    emit_settings_updated(app)?;
+    Ok(())
+}
@@ -1,15 +1,25 @@
 //! Tauri commands for chat session management
 
-use crate::services::chat_session::{self, ChatMessage, ChatSession};
+use crate::database::connection::get_db_connection;
+use crate::services::chat_branch::ChatBranch;
+use crate::services::chat_export::{self, ExportFormat};
+use crate::services::chat_import::{self, ImportReport, ImportSource};
+use crate::services::chat_retention::{self, RetentionPolicy, RetentionReport};
+use crate::services::chat_search::{self, ChatSearchHit};
+use crate::services::chat_session::{self, ChatMessage, ChatSession, ChatSessionSummary, SessionFilter};
+use std::collections::HashMap;
+use tauri::AppHandle;
 use tracing::{debug, error};
 
 /// Save a chat session
 #[tauri::command]
 pub async fn save_chat_session(
+    app: AppHandle,
     session_id: Option<String>,
     name: Option<String>,
-    codeagent_session_id: Option<String>,
+    workspace_id: Option<String>,
     messages: Vec<ChatMessage>,
+    code_cli_task_ids: Option<HashMap<String, String>>,
 ) -> Result<ChatSession, String> {
     debug!(
         "Command: save_chat_session - session_id: {:?}, name: {:?}, message_count: {}",
@@ -18,13 +28,8 @@ pub async fn save_chat_session(
         messages.len()
     );
 
-    match chat_session::save_session(
-        session_id,
-        name,
-        codeagent_session_id,
-        messages,
-        None,
-    ) {
+    let db = get_db_connection(&app).await.map_err(|e| e.to_string())?;
+    match chat_session::save_session(&db, session_id, name, workspace_id, messages, code_cli_task_ids).await {
         Ok(session) => {
             debug!("Successfully saved chat session: {}", session.id);
             Ok(session)
@@ -36,12 +41,31 @@ pub async fn save_chat_session(
     }
 }
 
-/// Load all chat sessions
+/// Load a page of chat session summaries (without messages)
+///
+/// Archived sessions are only listed with `archived: true`; `tags` must all match.
 #[tauri::command]
-pub async fn load_chat_sessions(workspace_id: String, limit: Option<usize>) -> Result<Vec<ChatSession>, String> {
-    debug!("Command: load_chat_sessions - workspace_id: {}, limit: {:?}", workspace_id, limit);
+pub async fn load_chat_sessions(
+    app: AppHandle,
+    workspace_id: String,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    archived: Option<bool>,
+    pinned: Option<bool>,
+    tags: Option<Vec<String>>,
+) -> Result<Vec<ChatSessionSummary>, String> {
+    debug!(
+        "Command: load_chat_sessions - workspace_id: {}, limit: {:?}, offset: {:?}, archived: {:?}, pinned: {:?}, tags: {:?}",
+        workspace_id, limit, offset, archived, pinned, tags
+    );
 
-    match chat_session::load_all_sessions(workspace_id, limit) {
+    let db = get_db_connection(&app).await.map_err(|e| e.to_string())?;
+    let filter = SessionFilter {
+        archived: archived.unwrap_or(false),
+        pinned,
+        tags: tags.unwrap_or_default(),
+    };
+    match chat_session::load_all_sessions(&db, workspace_id, &filter, limit, offset).await {
         Ok(sessions) => {
             debug!("Successfully loaded {} chat sessions", sessions.len());
             Ok(sessions)
@@ -53,12 +77,24 @@ pub async fn load_chat_sessions(workspace_id: String, limit: Option<usize>) -> R
     }
 }
 
+/// Load a chat session with the messages of its active branch
+#[tauri::command]
+pub async fn load_chat_session(app: AppHandle, session_id: String) -> Result<ChatSession, String> {
+    debug!("Command: load_chat_session - session_id: {}", session_id);
+
+    let db = get_db_connection(&app).await.map_err(|e| e.to_string())?;
+    chat_session::load_session_by_id(&db, &session_id)
+        .await
+        .inspect_err(|e| error!("Failed to load chat session: {}", e))
+}
+
 /// Delete a chat session
 #[tauri::command]
-pub async fn delete_chat_session(session_id: String) -> Result<(), String> {
+pub async fn delete_chat_session(app: AppHandle, session_id: String) -> Result<(), String> {
     debug!("Command: delete_chat_session - session_id: {}", session_id);
 
-    match chat_session::delete_session(&session_id) {
+    let db = get_db_connection(&app).await.map_err(|e| e.to_string())?;
+    match chat_session::delete_session(&db, &session_id).await {
         Ok(()) => {
             debug!("Successfully deleted chat session: {}", session_id);
             Ok(())
@@ -72,16 +108,14 @@ pub async fn delete_chat_session(session_id: String) -> Result<(), String> {
 
 /// Update a chat session name
 #[tauri::command]
-pub async fn update_chat_session_name(
-    session_id: String,
-    name: String,
-) -> Result<ChatSession, String> {
+pub async fn update_chat_session_name(app: AppHandle, session_id: String, name: String) -> Result<ChatSession, String> {
     debug!(
         "Command: update_chat_session_name - session_id: {}, name: {}",
         session_id, name
     );
 
-    match chat_session::update_session_name(&session_id, name) {
+    let db = get_db_connection(&app).await.map_err(|e| e.to_string())?;
+    match chat_session::update_session_name(&db, &session_id, name).await {
         Ok(session) => {
             debug!("Successfully updated chat session name: {}", session_id);
             Ok(session)
@@ -92,3 +126,191 @@ pub async fn update_chat_session_name(
         }
     }
 }
+
+/// Replace the tags of a chat session
+#[tauri::command]
+pub async fn set_chat_session_tags(
+    app: AppHandle,
+    session_id: String,
+    tags: Vec<String>,
+) -> Result<ChatSession, String> {
+    debug!(
+        "Command: set_chat_session_tags - session_id: {}, tags: {:?}",
+        session_id, tags
+    );
+
+    let db = get_db_connection(&app).await.map_err(|e| e.to_string())?;
+    chat_session::set_session_tags(&db, &session_id, tags)
+        .await
+        .inspect_err(|e| error!("Failed to set chat session tags: {}", e))
+}
+
+/// Pin or unpin a chat session
+#[tauri::command]
+pub async fn set_chat_session_pinned(app: AppHandle, session_id: String, pinned: bool) -> Result<ChatSession, String> {
+    debug!(
+        "Command: set_chat_session_pinned - session_id: {}, pinned: {}",
+        session_id, pinned
+    );
+
+    let db = get_db_connection(&app).await.map_err(|e| e.to_string())?;
+    chat_session::set_session_pinned(&db, &session_id, pinned)
+        .await
+        .inspect_err(|e| error!("Failed to pin chat session: {}", e))
+}
+
+/// Archive or unarchive a chat session
+#[tauri::command]
+pub async fn set_chat_session_archived(
+    app: AppHandle,
+    session_id: String,
+    archived: bool,
+) -> Result<ChatSession, String> {
+    debug!(
+        "Command: set_chat_session_archived - session_id: {}, archived: {}",
+        session_id, archived
+    );
+
+    let db = get_db_connection(&app).await.map_err(|e| e.to_string())?;
+    chat_session::set_session_archived(&db, &session_id, archived)
+        .await
+        .inspect_err(|e| error!("Failed to archive chat session: {}", e))
+}
+
+/// Get the chat retention policy
+#[tauri::command]
+pub async fn get_chat_retention_policy(app: AppHandle) -> Result<RetentionPolicy, String> {
+    debug!("Command: get_chat_retention_policy");
+
+    let db = get_db_connection(&app).await.map_err(|e| e.to_string())?;
+    chat_retention::load_policy(&db)
+        .await
+        .inspect_err(|e| error!("Failed to load chat retention policy: {}", e))
+}
+
+/// Save the chat retention policy and apply it right away
+#[tauri::command]
+pub async fn set_chat_retention_policy(app: AppHandle, policy: RetentionPolicy) -> Result<RetentionReport, String> {
+    debug!("Command: set_chat_retention_policy - policy: {:?}", policy);
+
+    let db = get_db_connection(&app).await.map_err(|e| e.to_string())?;
+    chat_retention::save_policy(&db, &policy)
+        .await
+        .inspect_err(|e| error!("Failed to save chat retention policy: {}", e))?;
+    chat_retention::apply_policy(&db, &policy, chrono::Utc::now())
+        .await
+        .inspect_err(|e| error!("Failed to apply chat retention policy: {}", e))
+}
+
+/// List the branches of a chat session
+#[tauri::command]
+pub async fn list_chat_branches(app: AppHandle, session_id: String) -> Result<Vec<ChatBranch>, String> {
+    debug!("Command: list_chat_branches - session_id: {}", session_id);
+
+    let db = get_db_connection(&app).await.map_err(|e| e.to_string())?;
+    chat_session::list_branches(&db, &session_id)
+        .await
+        .inspect_err(|e| error!("Failed to list chat branches: {}", e))
+}
+
+/// Switch a chat session to the branch containing `message_id`
+#[tauri::command]
+pub async fn switch_chat_branch(app: AppHandle, session_id: String, message_id: String) -> Result<ChatSession, String> {
+    debug!(
+        "Command: switch_chat_branch - session_id: {}, message_id: {}",
+        session_id, message_id
+    );
+
+    let db = get_db_connection(&app).await.map_err(|e| e.to_string())?;
+    chat_session::switch_branch(&db, &session_id, &message_id)
+        .await
+        .inspect_err(|e| error!("Failed to switch chat branch: {}", e))
+}
+
+/// Search chat history in a workspace, or across all workspaces when `workspace_id` is omitted
+#[tauri::command]
+pub async fn search_chat_sessions(
+    app: AppHandle,
+    query: String,
+    workspace_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<ChatSearchHit>, String> {
+    debug!(
+        "Command: search_chat_sessions - query: {}, workspace_id: {:?}, limit: {:?}",
+        query, workspace_id, limit
+    );
+
+    let db = get_db_connection(&app).await.map_err(|e| e.to_string())?;
+    match chat_search::search_sessions(&db, &query, workspace_id, limit).await {
+        Ok(hits) => {
+            debug!("Found {} chat search hits", hits.len());
+            Ok(hits)
+        }
+        Err(e) => {
+            error!("Failed to search chat sessions: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Export a chat session as Markdown, HTML or JSONL.
+///
+/// Returns the rendered text; when `output_path` is given it is also written there.
+#[tauri::command]
+pub async fn export_chat_session(
+    app: AppHandle,
+    session_id: String,
+    format: ExportFormat,
+    output_path: Option<String>,
+) -> Result<String, String> {
+    debug!(
+        "Command: export_chat_session - session_id: {}, format: {:?}, output_path: {:?}",
+        session_id, format, output_path
+    );
+
+    let db = get_db_connection(&app).await.map_err(|e| e.to_string())?;
+    chat_export::export_session(&db, &session_id, format, output_path.as_deref())
+        .await
+        .inspect_err(|e| error!("Failed to export chat session: {}", e))
+}
+
+/// Export every chat session of a workspace as Markdown, HTML or JSONL
+#[tauri::command]
+pub async fn export_workspace_chat_sessions(
+    app: AppHandle,
+    workspace_id: String,
+    format: ExportFormat,
+    output_path: Option<String>,
+) -> Result<String, String> {
+    debug!(
+        "Command: export_workspace_chat_sessions - workspace_id: {}, format: {:?}, output_path: {:?}",
+        workspace_id, format, output_path
+    );
+
+    let db = get_db_connection(&app).await.map_err(|e| e.to_string())?;
+    chat_export::export_workspace_sessions(&db, &workspace_id, format, output_path.as_deref())
+        .await
+        .inspect_err(|e| error!("Failed to export workspace chat sessions: {}", e))
+}
+
+/// Import Claude Code / Codex / Gemini CLI transcripts or our JSONL export into a workspace.
+///
+/// `paths` may be files or directories; `source` is detected per file when omitted.
+#[tauri::command]
+pub async fn import_chat_sessions(
+    app: AppHandle,
+    paths: Vec<String>,
+    workspace_id: String,
+    source: Option<ImportSource>,
+    code_cli: Option<String>,
+) -> Result<ImportReport, String> {
+    debug!(
+        "Command: import_chat_sessions - paths: {:?}, workspace_id: {}, source: {:?}, code_cli: {:?}",
+        paths, workspace_id, source, code_cli
+    );
+
+    let db = get_db_connection(&app).await.map_err(|e| e.to_string())?;
+    chat_import::import_sessions(&db, &paths, source, &workspace_id, code_cli.as_deref())
+        .await
+        .inspect_err(|e| error!("Failed to import chat sessions: {}", e))
+}
@@ -2,34 +2,65 @@
 //!
 //! This module defines Tauri IPC commands that can be called from the frontend.
 
+use super::event_handlers::{
+    emit_ai_context, emit_ai_response, emit_command_exit, emit_command_output, emit_terminal_exit, emit_terminal_output,
+};
+use crate::config::AppConfig;
+use crate::core::AppState;
+use crate::core::app::StreamingTask;
+use crate::database::models::command_history::Model as CommandHistoryEntry;
+use crate::database::repositories::command_history_repository::CommandHistoryRepository;
+use crate::database::repositories::terminal_session_repository::TerminalSessionRepository;
+use crate::database::repositories::workspace_repository::WorkspaceRepository;
+use crate::services::ai::backend::ChatTurn;
+use crate::services::ai::{AiChatOptions, RunMetadata};
+use crate::services::chat_session::{self, ChatMessage};
+use crate::services::chat_summary;
+use crate::services::command_history::{self, HistorySearchMode};
+use crate::services::command_runner::{self, RunningCommand};
+use crate::services::secret_vault::{self, SharedVault};
+use crate::services::terminal::{NewTerminalSession, TerminalSize, TerminalState};
 use std::fs;
 use std::path::PathBuf;
-use std::time::Duration;
-use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, Manager, State};
-use tracing::{error, info, debug};
+use std::sync::Arc;
 use tauri::async_runtime;
-use crate::core::AppState;
-use crate::services::ai::{AiChatOptions, AiService};
-use crate::services::chat_session::{self, ChatMessage};
-use super::event_handlers::emit_ai_response;
+use tauri::{AppHandle, Manager, State};
+use tracing::{debug, error, info};
+
+/// Configured environment variables with `vault:<name>` references decrypted
+fn resolve_config_env(vault: &SharedVault, config: &AppConfig) -> Vec<(String, String)> {
+    // `vault:<name>` 引用在这里解密，避免明文写入配置
+    match vault.with(|vault| Ok(vault.resolve_env(&config.env_vars))) {
+        Ok(env) => env,
+        Err(e) => {
+            error!("Failed to open secret vault: {}", e);
+            config
+                .env_vars
+                .iter()
+                .filter(|(_, v)| !secret_vault::is_vault_ref(v))
+                .cloned()
+                .collect()
+        }
+    }
+}
 
 /// Send chat message to AI
 #[tauri::command]
 pub async fn send_chat_message(
+    app_handle: AppHandle,
     message: String,
     context_files: Option<Vec<String>>,
 ) -> Result<String, String> {
-    debug!("Sending chat message: {}", message);
+    debug!(message_len = message.len(), "Sending chat message");
 
-    // Use AiService as the single entry; internally it calls codeagent-wrapper.
-    let ai = AiService::new();
+    // Use AiService as the single entry; it picks codeagent-wrapper or a direct HTTP backend.
+    let ai = crate::core::app::get_ai_service(app_handle.state::<AppState>());
     ai.send_message(&message, context_files)
         .await
         .map_err(|e| e.to_string())
 }
 
-/// Send chat message to AI with simulated streaming response
+/// Send chat message to AI and stream the wrapper output as `ai-response` events
 #[tauri::command]
 pub async fn send_chat_message_streaming(
     app_handle: AppHandle,
@@ -42,19 +73,23 @@ pub async fn send_chat_message_streaming(
     workspace_dir: Option<String>,
     code_cli_changed: Option<bool>,
     code_cli_task_id: Option<String>,
+    model: Option<String>,
+    edit_message_id: Option<String>,
 ) -> Result<String, String> {
-    debug!("Sending chat message (streaming): {}", message);
+    debug!(message_len = message.len(), "Sending chat message (streaming)");
     debug!(
         code_cli = ?code_cli,
         session_id = ?session_id,
         codex_model = ?codex_model,
         code_cli_changed = ?code_cli_changed,
         code_cli_task_id = ?code_cli_task_id,
+        model = ?model,
+        edit_message_id = ?edit_message_id,
         "Streaming chat options"
     );
-    // let db = crate::database::connection::get_db_connection(&app_handle)
-    //     .await
-    //     .map_err(|e| e.to_string())?;
+    let db = crate::database::connection::get_db_connection(&app_handle)
+        .await
+        .map_err(|e| e.to_string())?;
     
     // let workspace_id_parsed = workspace_id
     //     .as_ref()
@@ -84,120 +119,195 @@ pub async fn send_chat_message_streaming(
     let code_cli_for_append = code_cli.clone();
     let codex_model_for_task = codex_model.clone();
     let workspace_dir_for_task = workspace_dir.clone();
-    let code_cli_task_id_for_resume = code_cli_task_id.clone();
     let code_cli_changed_flag = code_cli_changed;
+    let model_for_append = model.clone();
+    // 编辑历史消息时从该消息的父节点分叉，只发送分叉点之前的历史
+    let branch = chat_session::load_branch_context(&db, &session_id, edit_message_id.as_deref())
+        .await
+        .inspect_err(|e| error!("Failed to load branch of session {}: {}", session_id, e))?;
+    // 分叉后原 CLI 会话包含另一分支的内容，只能恢复属于当前分支的会话
+    let code_cli_task_id_for_resume = if edit_message_id.is_some() {
+        code_cli
+            .as_ref()
+            .and_then(|cli| branch.code_cli_task_ids.get(cli).cloned())
+    } else {
+        code_cli_task_id.clone()
+    };
+    // 直连 HTTP 的模型是无状态的，需要把会话历史一并发送；较早的部分由摘要代替
+    let summary = branch.summary;
+    let history: Vec<ChatTurn> = branch
+        .history
+        .into_iter()
+        .filter(|m| (m.role == "user" || m.role == "assistant") && !m.content.trim().is_empty())
+        // 失败运行保存的是错误信息，不作为上下文
+        .filter(|m| m.run.as_ref().is_none_or(|run| run.error.is_none()))
+        .map(|m| ChatTurn {
+            role: m.role,
+            content: m.content,
+        })
+        .collect();
+
+    let env = resolve_config_env(&app_handle.state::<AppState>().vault, &config);
+
+    let (streaming_task, cancel_rx) = StreamingTask::new();
 
     let app_handle_for_task = app_handle.clone();
     let request_id_for_spawn = request_id_for_task.clone();
+    let ai = crate::core::app::get_ai_service(app_handle.state::<AppState>());
     let join_handle = async_runtime::spawn(async move {
-        let ai = AiService::new();
-        match ai
-            .send_message_with_options(
-                &msg,
-                ctx_files,
-                AiChatOptions {
-                    code_cli: code_cli_for_task,
-                    resume_session_id: code_cli_task_id_for_resume,
-                    parallel: false,
-                    codex_model: codex_model_for_task,
-                    workspace_dir: workspace_dir_for_task,
-                    code_cli_changed: code_cli_changed_flag,
-                    env: config.env_vars.clone(),
-                },
-            )
-            .await
-        {   
+        let mut emit_failed = false;
+        // 摘要沿用本次请求的后端，但不恢复 CLI 会话
+        let summary_options = AiChatOptions {
+            code_cli: code_cli_for_task.clone(),
+            codex_model: codex_model_for_task.clone(),
+            workspace_dir: workspace_dir_for_task.clone(),
+            env: env.clone(),
+            model: model.clone(),
+            ..Default::default()
+        };
+        let options = AiChatOptions {
+            code_cli: code_cli_for_task,
+            resume_session_id: code_cli_task_id_for_resume,
+            parallel: false,
+            codex_model: codex_model_for_task,
+            workspace_dir: workspace_dir_for_task.clone(),
+            code_cli_changed: code_cli_changed_flag,
+            env,
+            cancel: Some(cancel_rx),
+            model,
+            history,
+            summary,
+        };
+        let backend_label = ai.backend_label(&options);
+        let started = std::time::Instant::now();
+        let outcome = ai
+            .send_message_streaming(&msg, ctx_files, options, |delta| {
+                if emit_failed {
+                    return;
+                }
+                if let Err(e) = emit_ai_response(
+                    &app_handle_for_task,
+                    &request_id_for_spawn,
+                    delta,
+                    false,
+                    "streaming",
+                    Some(&session_id),
+                    workspace_id_for_append.as_deref(),
+                    None,
+                ) {
+                    error!("Failed to emit AI response chunk: {:?}", e);
+                    emit_failed = true;
+                }
+            })
+            .await;
+
+        let (reply, task_id, run) = match outcome {
             Ok(result) => {
                 debug!("AI response: {}", result.message);
-                let chars: Vec<char> = result.message.chars().collect();
-                let total = chars.len();
-                let mut buffer = String::new();
-                let mut full_response = String::new();
-
-                for (idx, ch) in chars.into_iter().enumerate() {
-                    buffer.push(ch);
-
-                    let is_last = idx + 1 == total;
-                    // 每凑够一定长度，或者到达结尾，就发送一块增量
-                    if buffer.len() >= 32 || is_last {
-                        let delta = buffer.clone();
-                        buffer.clear();
-
-                        let codeagent_session_id = if is_last {
-                            result.codeagent_session_id.clone()
-                        } else {
-                            None
-                        };
-                        full_response.push_str(&delta);
-
-                        if let Err(e) = emit_ai_response(
-                            &app_handle_for_task,
-                            &request_id_for_spawn,
-                            &delta,
-                            is_last,
-                            Some(&session_id),
-                            workspace_id_for_append.as_deref(),
-                            codeagent_session_id.as_deref(),
-                        ) {
-                            error!("Failed to emit AI response chunk: {:?}", e);
-                            break;
-                        }
-
-                        // 模拟流式延迟效果（阻塞当前后台任务线程即可）
-                        std::thread::sleep(Duration::from_millis(60));
-                    }
+                let status = if result.cancelled { "cancelled" } else { "done" };
+                // 流结束后单独发送 done 事件，并携带解析出的 SESSION_ID
+                if let Err(e) = emit_ai_response(
+                    &app_handle_for_task,
+                    &request_id_for_spawn,
+                    "",
+                    true,
+                    status,
+                    Some(&session_id),
+                    workspace_id_for_append.as_deref(),
+                    result.codeagent_session_id.as_deref(),
+                ) {
+                    error!("Failed to emit final AI response event: {:?}", e);
                 }
-                
-                if let Some(task_id) = result.codeagent_session_id.clone() {
-                    let user_message = ChatMessage {
-                        id: uuid::Uuid::new_v4().to_string(),
-                        role: "user".to_string(),
-                        content: msg_for_spawn.clone(),
-                        timestamp: chrono::Local::now().to_rfc3339().to_string(),
-                        files: None,
-                        session_id: Some(session_id.clone()),
-                        workspace_id: workspace_id_for_append.clone(),
-                        model: None,
-                    };
-                    let assistant_message = ChatMessage {
-                        id: request_id_for_spawn.clone(),
-                        role: "assistant".to_string(),
-                        content: full_response,
-                        timestamp: chrono::Local::now().to_rfc3339(),
-                        files: None,
-                        session_id: Some(session_id.clone()),
-                        workspace_id: workspace_id_for_append.clone(),
-                        model: None,
-                    };
-                    if let Err(e) = chat_session::append_message_to_session(
-                        &session_id,
-                        vec![user_message, assistant_message],
-                        code_cli_for_append.clone(),
-                        Some(task_id.clone()),
-                    ) {
-                        error!(
-                            "Failed to append chat messages to session {}: {}",
-                            session_id, e
-                        );
+                if let Some(report) = result.context.as_ref() {
+                    if let Err(e) =
+                        emit_ai_context(&app_handle_for_task, &request_id_for_spawn, Some(&session_id), report)
+                    {
+                        error!("Failed to emit AI context event: {:?}", e);
                     }
                 }
+                // 取消的请求也记录已收到的部分输出；直连 HTTP 的模型没有 SESSION_ID，同样需要保存
+                (result.message, result.codeagent_session_id, result.run)
             }
             Err(e) => {
                 error!("Failed to build AI response for streaming: {}", e);
+                let reply = format!("[AI 错误] {}", e);
                 let _ = emit_ai_response(
                     &app_handle_for_task,
                     &request_id_for_spawn,
-                    &format!("[AI 错误] {}", e),
+                    &reply,
                     true,
+                    "error",
                     None,
                     workspace_id_for_append.as_deref(),
                     None,
                 );
+                // 失败的运行同样保存，便于事后排查
+                let run = RunMetadata {
+                    backend: backend_label
+                        .or_else(|| code_cli_for_append.clone())
+                        .unwrap_or_default(),
+                    model: model_for_append.clone(),
+                    workdir: workspace_dir_for_task,
+                    duration_ms: started.elapsed().as_millis() as u64,
+                    error: Some(e.to_string()),
+                    ..RunMetadata::default()
+                };
+                (reply, None, run)
             }
+        };
+
+        let succeeded = run.error.is_none() && !run.cancelled;
+        let user_message = ChatMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            role: "user".to_string(),
+            content: msg_for_spawn.clone(),
+            timestamp: chrono::Local::now().to_rfc3339().to_string(),
+            files: None,
+            session_id: Some(session_id.clone()),
+            workspace_id: workspace_id_for_append.clone(),
+            model: None,
+            parent_id: None,
+            code_cli: None,
+            code_cli_task_id: None,
+            run: None,
+        };
+        let assistant_message = ChatMessage {
+            id: request_id_for_spawn.clone(),
+            role: "assistant".to_string(),
+            content: reply,
+            timestamp: chrono::Local::now().to_rfc3339(),
+            files: None,
+            session_id: Some(session_id.clone()),
+            workspace_id: workspace_id_for_append.clone(),
+            model: run.model.clone().or_else(|| model_for_append.clone()),
+            parent_id: None,
+            code_cli: None,
+            code_cli_task_id: None,
+            run: Some(run),
+        };
+        if let Err(e) = chat_session::append_message_to_session(
+            &db,
+            &session_id,
+            vec![user_message, assistant_message],
+            code_cli_for_append.clone(),
+            task_id,
+            edit_message_id.as_deref(),
+        )
+        .await
+        {
+            error!("Failed to append chat messages to session {}: {}", session_id, e);
+        } else if succeeded {
+            // 历史过长时滚动更新摘要，切换 CLI 后作为上下文；单独运行以免占用本次请求
+            async_runtime::spawn(async move {
+                if let Err(e) = chat_summary::summarize_if_needed(&db, &ai, &session_id, summary_options).await {
+                    error!("Failed to summarize session {}: {}", session_id, e);
+                }
+            });
         }
     });
 
-    let handle_entry = Arc::new(Mutex::new(Some(join_handle)));
+    *streaming_task.handle.lock().unwrap() = Some(join_handle);
+    let handle_entry = Arc::new(streaming_task);
     {
         let state = app_handle.state::<AppState>();
         state
@@ -211,7 +321,7 @@ pub async fn send_chat_message_streaming(
     let request_id_for_cleanup = request_id_for_task.clone();
     async_runtime::spawn(async move {
         if let Some(handle) = {
-            let mut guard = handle_entry.lock().unwrap();
+            let mut guard = handle_entry.handle.lock().unwrap();
             guard.take()
         } {
             let _ = handle.await;
@@ -256,6 +366,10 @@ pub async fn save_clipboard_image(
 }
 
 /// Cancel an in-flight streaming request by request_id
+///
+/// The streaming task kills the codeagent-wrapper process tree, emits a final
+/// `ai-response` event with status `cancelled` and stores the partial output.
+/// The cleanup task removes the entry once the streaming task has finished.
 #[tauri::command]
 pub async fn cancel_streaming_request(
     app_handle: AppHandle,
@@ -263,82 +377,418 @@ pub async fn cancel_streaming_request(
 ) -> Result<(), String> {
     let handle_entry = {
         let state = app_handle.state::<AppState>();
-        let mut tasks = state.streaming_tasks.lock().unwrap();
-        tasks.remove(&request_id)
+        let tasks = state.streaming_tasks.lock().unwrap();
+        tasks.get(&request_id).cloned()
     };
 
     if let Some(handle_entry) = handle_entry {
-        if let Some(handle) = handle_entry.lock().unwrap().take() {
-            handle.abort();
-        }
+        info!("Cancelling streaming request: {}", request_id);
+        handle_entry.cancel();
         Ok(())
     } else {
         Err("Streaming request not found or already finished".to_string())
     }
 }
 
-/// Execute command in terminal
+/// Execute a command without a shell
+///
+/// Returns a command id right away; output arrives as `command-output` events tagged
+/// with `stdout`/`stderr`, followed by one `command-exit` event.
 #[tauri::command]
 pub async fn execute_command(
+    app_handle: AppHandle,
     command: String,
     args: Vec<String>,
     cwd: Option<String>,
+    timeout_ms: Option<u64>,
 ) -> Result<String, String> {
     info!("Executing command: {} {:?}", command, args);
 
-    async_runtime::spawn_blocking(move || {
-        let mut cmd = std::process::Command::new(&command);
-        cmd.args(&args);
-
-        if let Some(dir) = cwd {
-            cmd.current_dir(dir);
-        }
-
-        let output = cmd.output().map_err(|e| e.to_string())?;
-        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
-        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
-
-        if !stderr.is_empty() {
-            error!("Command stderr: {}", stderr);
-        }
+    let mut cmd = tokio::process::Command::new(&command);
+    cmd.args(&args);
+    if let Some(dir) = cwd {
+        cmd.current_dir(dir);
+    }
 
-        Ok::<String, String>(stdout)
-    })
-    .await
-    .map_err(|e| format!("执行命令任务失败: {}", e))?
+    let running = command_runner::spawn(cmd)
+        .inspect_err(|e| error!("Failed to start command {}: {}", command, e))
+        .map_err(|e| e.to_string())?;
+    Ok(stream_command(app_handle, running, timeout_ms, None))
 }
 
-/// Execute a command in an existing terminal session
+/// Execute a command through a shell in an existing terminal session's working directory
+///
+/// Streams like `execute_command`.
 #[tauri::command]
 pub async fn execute_terminal_command(
+    app_handle: AppHandle,
     state: State<'_, AppState>,
     session_id: String,
     shell: String,
     command: String,
+    timeout_ms: Option<u64>,
 ) -> Result<String, String> {
     info!(
         "Executing terminal command in session {} with shell {}: {}",
         session_id, shell, command
     );
 
-    state
+    run_in_terminal(app_handle, &state, &session_id, &shell, &command, None, timeout_ms).await
+}
+
+/// Search the command history of a workspace (the active one by default)
+///
+/// Each distinct command line is returned once, as its most recent run.
+#[tauri::command]
+pub async fn search_command_history(
+    app_handle: AppHandle,
+    workspace_id: Option<String>,
+    query: Option<String>,
+    mode: Option<HistorySearchMode>,
+    limit: Option<usize>,
+) -> Result<Vec<CommandHistoryEntry>, String> {
+    debug!(workspace_id = ?workspace_id, query = ?query, mode = ?mode, "Searching command history");
+    let db = crate::database::connection::get_db_connection(&app_handle)
+        .await
+        .map_err(|e| e.to_string())?;
+    let workspace_id = match workspace_id {
+        Some(id) => Some(id.parse::<i32>().map_err(|_| format!("Invalid workspace id: {}", id))?),
+        None => WorkspaceRepository::get_active(&db)
+            .await
+            .map_err(|e| e.to_string())?
+            .map(|workspace| workspace.id),
+    };
+    command_history::search(
+        &db,
+        workspace_id,
+        query.as_deref().unwrap_or_default(),
+        mode.unwrap_or_default(),
+        limit.unwrap_or(50),
+    )
+    .await
+    .map_err(|e| e.to_string())
+    .inspect_err(|e| error!("Failed to search command history: {}", e))
+}
+
+/// Run a command from the history again, with its shell and in its directory
+///
+/// Uses the environment of the given terminal session and falls back to the
+/// session's directory when the recorded one no longer exists. Streams like
+/// `execute_terminal_command` and returns the command id.
+#[tauri::command]
+pub async fn rerun_command_history(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    history_id: i32,
+    session_id: String,
+    timeout_ms: Option<u64>,
+) -> Result<String, String> {
+    let db = crate::database::connection::get_db_connection(&app_handle)
+        .await
+        .map_err(|e| e.to_string())?;
+    let entry = CommandHistoryRepository::find_by_id(&db, history_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Command history entry not found: {}", history_id))?;
+    info!(
+        "Re-running command {} in session {}: {}",
+        history_id, session_id, entry.command
+    );
+
+    let cwd = std::path::Path::new(&entry.cwd).is_dir().then_some(entry.cwd);
+    run_in_terminal(
+        app_handle,
+        &state,
+        &session_id,
+        &entry.shell,
+        &entry.command,
+        cwd,
+        timeout_ms,
+    )
+    .await
+}
+
+/// Start a command with a terminal session's environment, record it in the
+/// command history and stream its output; returns the command id
+async fn run_in_terminal(
+    app_handle: AppHandle,
+    state: &AppState,
+    session_id: &str,
+    shell: &str,
+    command: &str,
+    cwd: Option<String>,
+    timeout_ms: Option<u64>,
+) -> Result<String, String> {
+    let terminal = state.terminal.state(session_id).map_err(|e| e.to_string())?;
+    // 使用 shell 当前所在目录，与交互终端保持一致
+    let cwd = cwd.unwrap_or(terminal.cwd);
+    let running = state
         .terminal
-        .execute_command(&session_id, &shell, &command)
-        .map_err(|e| e.to_string())
+        .execute_command(session_id, shell, command, Some(&cwd))
+        .map_err(|e| e.to_string())?;
+
+    // 历史记录失败不影响命令执行
+    let history_id = match crate::database::connection::get_db_connection(&app_handle).await {
+        Ok(db) => CommandHistoryRepository::create(&db, terminal.workspace_id, command, shell, &cwd)
+            .await
+            .map(|entry| entry.id)
+            .inspect_err(|e| error!("Failed to record command history: {}", e))
+            .ok(),
+        Err(e) => {
+            error!("Failed to record command history: {}", e);
+            None
+        }
+    };
+    Ok(stream_command(app_handle, running, timeout_ms, history_id))
 }
 
-/// Spawn new terminal session using TerminalService
+/// Cancel a running command started by `execute_command` or `execute_terminal_command`
 #[tauri::command]
-pub async fn spawn_terminal(state: State<'_, AppState>, cwd: Option<String>) -> Result<String, String> {
+pub async fn cancel_command(state: State<'_, AppState>, command_id: String) -> Result<(), String> {
+    info!("Cancelling command: {}", command_id);
+
+    let task = state.command_tasks.lock().unwrap().get(&command_id).cloned();
+    match task {
+        Some(task) => {
+            task.cancel();
+            Ok(())
+        }
+        None => Err("Command not found or already finished".to_string()),
+    }
+}
+
+/// Stream a spawned command's output as events in the background and return its command id
+///
+/// With a `history_id` the exit code and duration are stored on that history entry.
+fn stream_command(
+    app_handle: AppHandle,
+    running: RunningCommand,
+    timeout_ms: Option<u64>,
+    history_id: Option<i32>,
+) -> String {
+    let command_id = uuid::Uuid::new_v4().to_string();
+    let (task, cancel_rx) = StreamingTask::new();
+    let task = Arc::new(task);
+    // 先登记再启动，保证命令结束时能从表中移除
+    app_handle
+        .state::<AppState>()
+        .command_tasks
+        .lock()
+        .unwrap()
+        .insert(command_id.clone(), task.clone());
+
+    let id = command_id.clone();
+    let handle = async_runtime::spawn(async move {
+        let timeout = timeout_ms.map(std::time::Duration::from_millis);
+        let exit = running
+            .stream(timeout, Some(cancel_rx), |stream, data| {
+                let _ = emit_command_output(&app_handle, &id, stream, data);
+            })
+            .await;
+        debug!(command_id = %id, exit = ?exit, "Command finished");
+        if let Err(e) = emit_command_exit(&app_handle, &id, &exit) {
+            error!("Failed to emit command exit for {}: {}", id, e);
+        }
+        if let Some(history_id) = history_id {
+            let recorded = match crate::database::connection::get_db_connection(&app_handle).await {
+                Ok(db) => {
+                    CommandHistoryRepository::finish(&db, history_id, exit.exit_code, exit.duration_ms as i64).await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = recorded {
+                error!("Failed to record exit of command {}: {}", id, e);
+            }
+        }
+        app_handle.state::<AppState>().command_tasks.lock().unwrap().remove(&id);
+    });
+    *task.handle.lock().unwrap() = Some(handle);
+
+    command_id
+}
+
+/// Spawn new terminal session with an interactive shell in a pseudo-terminal
+///
+/// Output is emitted as `terminal-output` events, the shell's exit as `terminal-exit`.
+#[tauri::command]
+pub async fn spawn_terminal(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    cwd: Option<String>,
+    shell: Option<String>,
+    cols: Option<u16>,
+    rows: Option<u16>,
+) -> Result<String, String> {
     info!("Spawning new terminal");
 
+    let db = crate::database::connection::get_db_connection(&app_handle)
+        .await
+        .map_err(|e| e.to_string())?;
+    let workspace = WorkspaceRepository::get_active(&db).await.map_err(|e| e.to_string())?;
+    // 未指定目录时在当前工作区打开，没有工作区则在用户主目录
+    let cwd = cwd
+        .or_else(|| workspace.as_ref().map(|workspace| workspace.path.clone()))
+        .or_else(|| crate::config::get_user_home().ok());
+    let options = NewTerminalSession {
+        cwd,
+        workspace_id: workspace.map(|workspace| workspace.id),
+        ..Default::default()
+    };
+    open_terminal(
+        &app_handle,
+        &state,
+        options,
+        shell.as_deref(),
+        terminal_size(cols, rows),
+    )
+}
+
+/// Reopen the terminals of the active workspace saved when the app last quit
+///
+/// Returns the reopened terminals in tab order; their earlier output is available
+/// through `get_terminal_scrollback`.
+#[tauri::command]
+pub async fn restore_terminals(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    cols: Option<u16>,
+    rows: Option<u16>,
+) -> Result<Vec<TerminalState>, String> {
+    let db = crate::database::connection::get_db_connection(&app_handle)
+        .await
+        .map_err(|e| e.to_string())?;
+    let workspace = WorkspaceRepository::get_active(&db).await.map_err(|e| e.to_string())?;
+    let saved = TerminalSessionRepository::find_by_workspace(&db, workspace.as_ref().map(|w| w.id))
+        .await
+        .inspect_err(|e| error!("Failed to load saved terminals: {}", e))
+        .map_err(|e| e.to_string())?;
+    info!("Restoring {} terminals", saved.len());
+
+    let mut restored = Vec::with_capacity(saved.len());
+    for terminal in saved {
+        let saved_id = terminal.id.clone();
+        // 保存的目录可能已被删除，退回工作区目录
+        let cwd = if std::path::Path::new(&terminal.cwd).is_dir() {
+            Some(terminal.cwd)
+        } else {
+            workspace.as_ref().map(|w| w.path.clone())
+        };
+        let options = NewTerminalSession {
+            name: Some(terminal.name),
+            cwd,
+            workspace_id: terminal.workspace_id,
+            scrollback: terminal.scrollback,
+            ..Default::default()
+        };
+        match open_terminal(
+            &app_handle,
+            &state,
+            options,
+            terminal.shell.as_deref(),
+            terminal_size(cols, rows),
+        ) {
+            Ok(terminal_id) => {
+                // 恢复成功后才删除保存的记录，失败的终端下次启动仍可恢复
+                if let Err(e) = TerminalSessionRepository::delete(&db, &saved_id).await {
+                    error!("Failed to delete restored terminal {}: {}", saved_id, e);
+                }
+                restored.push(state.terminal.state(&terminal_id).map_err(|e| e.to_string())?);
+            }
+            Err(e) => error!("Failed to restore terminal {}: {}", saved_id, e),
+        }
+    }
+    Ok(restored)
+}
+
+fn terminal_size(cols: Option<u16>, rows: Option<u16>) -> TerminalSize {
+    let default_size = TerminalSize::default();
+    TerminalSize {
+        cols: cols.unwrap_or(default_size.cols),
+        rows: rows.unwrap_or(default_size.rows),
+    }
+}
+
+/// Create a terminal session with the configured env vars and start its shell
+fn open_terminal(
+    app_handle: &AppHandle,
+    state: &AppState,
+    mut options: NewTerminalSession,
+    shell: Option<&str>,
+    size: TerminalSize,
+) -> Result<String, String> {
+    options.env = resolve_config_env(
+        &state.vault,
+        &crate::core::app::get_config(app_handle.state::<AppState>()),
+    );
+    let terminal_id = state.terminal.create_session(options).map_err(|e| e.to_string())?;
+
+    let output_handle = app_handle.clone();
+    let exit_handle = app_handle.clone();
+    let output_id = terminal_id.clone();
+    let exit_id = terminal_id.clone();
+    let started = state.terminal.start_shell(
+        &terminal_id,
+        shell,
+        size,
+        move |output| {
+            let _ = emit_terminal_output(&output_handle, &output_id, output);
+        },
+        move |code| {
+            let _ = emit_terminal_exit(&exit_handle, &exit_id, code);
+        },
+    );
+    if let Err(e) = started {
+        // 启动 shell 失败时不保留空会话
+        let _ = state.terminal.kill_session(&terminal_id);
+        error!("Failed to start shell for terminal {}: {}", terminal_id, e);
+        return Err(e.to_string());
+    }
+
+    Ok(terminal_id)
+}
+
+/// List open terminals in the order they were opened
+#[tauri::command]
+pub async fn list_terminals(state: State<'_, AppState>) -> Result<Vec<TerminalState>, String> {
+    Ok(state.terminal.list_sessions())
+}
+
+/// Get the output kept for a terminal, ANSI sequences included
+#[tauri::command]
+pub async fn get_terminal_scrollback(state: State<'_, AppState>, terminal_id: String) -> Result<String, String> {
+    state.terminal.scrollback(&terminal_id).map_err(|e| e.to_string())
+}
+
+/// Write input (keystrokes or pasted text) to a terminal's shell
+#[tauri::command]
+pub async fn write_terminal(state: State<'_, AppState>, terminal_id: String, data: String) -> Result<(), String> {
+    state
+        .terminal
+        .write_input(&terminal_id, &data)
+        .map_err(|e| e.to_string())
+}
+
+/// Resize a terminal's pseudo-terminal to the frontend's cell size
+#[tauri::command]
+pub async fn resize_terminal(
+    state: State<'_, AppState>,
+    terminal_id: String,
+    cols: u16,
+    rows: u16,
+) -> Result<(), String> {
     state
         .terminal
-        .create_session(None, cwd)
+        .resize(&terminal_id, TerminalSize { cols, rows })
         .map_err(|e| e.to_string())
 }
 
-/// Kill terminal session via TerminalService
+/// Get a terminal's current directory, git branch and whether its shell is running
+#[tauri::command]
+pub async fn get_terminal_state(state: State<'_, AppState>, terminal_id: String) -> Result<TerminalState, String> {
+    state.terminal.state(&terminal_id).map_err(|e| e.to_string())
+}
+
+/// Close a terminal session, killing its shell
 #[tauri::command]
 pub async fn kill_terminal(state: State<'_, AppState>, terminal_id: String) -> Result<(), String> {
     info!("Killing terminal: {}", terminal_id);
@@ -5,6 +5,8 @@
 use tauri::{AppHandle, Emitter, Listener};
 use tracing::debug;
 
+use crate::services::ai::context::ContextReport;
+use crate::services::command_runner::{CommandExit, OutputStream};
 use crate::utils::error::{AppError, AppResult};
 
 /// Register event handlers
@@ -58,12 +60,61 @@ pub fn emit_terminal_output(app_handle: &AppHandle, terminal_id: &str, output: &
         .map_err(|e| AppError::TauriError(e))
 }
 
+/// Emit terminal exit event once a terminal's shell exited (`exit_code` is unknown when waiting failed)
+pub fn emit_terminal_exit(app_handle: &AppHandle, terminal_id: &str, exit_code: Option<u32>) -> AppResult<()> {
+    let payload = serde_json::json!({
+        "terminal_id": terminal_id,
+        "exit_code": exit_code,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+
+    app_handle
+        .emit("terminal-exit", payload.to_string())
+        .map_err(|e| AppError::TauriError(e))
+}
+
+/// Emit a chunk of a non-interactive command's output
+pub fn emit_command_output(
+    app_handle: &AppHandle,
+    command_id: &str,
+    stream: OutputStream,
+    data: &str,
+) -> AppResult<()> {
+    let payload = serde_json::json!({
+        "command_id": command_id,
+        "stream": stream,
+        "data": data,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+
+    app_handle
+        .emit("command-output", payload.to_string())
+        .map_err(|e| AppError::TauriError(e))
+}
+
+/// Emit the end of a non-interactive command (exit code, signal, duration, truncation)
+pub fn emit_command_exit(app_handle: &AppHandle, command_id: &str, exit: &CommandExit) -> AppResult<()> {
+    let payload = serde_json::json!({
+        "command_id": command_id,
+        "exit": exit,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+
+    app_handle
+        .emit("command-exit", payload.to_string())
+        .map_err(|e| AppError::TauriError(e))
+}
+
 /// Emit AI response event (supports streaming chunks)
+///
+/// `status` is one of `streaming`, `done`, `error` or `cancelled`.
+#[allow(clippy::too_many_arguments)]
 pub fn emit_ai_response(
     app_handle: &AppHandle,
     request_id: &str,
     delta: &str,
     done: bool,
+    status: &str,
     session_id: Option<&str>,
     workspace_id: Option<&str>,
     code_cli_task_id: Option<&str>,
@@ -72,6 +123,7 @@ pub fn emit_ai_response(
         "request_id": request_id,
         "delta": delta,
         "done": done,
+        "status": status,
         "session_id": session_id,
         "workspace_id": workspace_id,
         "code_cli_task_id": code_cli_task_id,
@@ -82,6 +134,25 @@ pub fn emit_ai_response(
         .map_err(|e| AppError::TauriError(e))
 }
 
+/// Emit the context report of a chat request (which files were attached, estimated tokens)
+pub fn emit_ai_context(
+    app_handle: &AppHandle,
+    request_id: &str,
+    session_id: Option<&str>,
+    report: &ContextReport,
+) -> AppResult<()> {
+    let payload = serde_json::json!({
+        "request_id": request_id,
+        "session_id": session_id,
+        "context": report,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+
+    app_handle
+        .emit("ai-context", payload.to_string())
+        .map_err(|e| AppError::TauriError(e))
+}
+
 /// Emit log message event
 pub fn emit_log_message(app_handle: &AppHandle, level: &str, message: &str) -> AppResult<()> {
     let payload = serde_json::json!({
@@ -1,9 +1,11 @@
 //! Tauri modules
 
+pub mod ai_commands;
+pub mod chat_session_commands;
 pub mod commands;
 pub mod event_handlers;
+pub mod fs_command;
+pub mod notification_commands;
+pub mod secret_commands;
 pub mod settings_commands;
 pub mod workspace_command;
-pub mod fs_command;
-pub mod chat_session_commands;
-pub mod notification_commands;
\ No newline at end of file
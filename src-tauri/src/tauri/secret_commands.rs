@@ -0,0 +1,36 @@
+//! Tauri commands for the secret vault
+
+use tauri::State;
+use tracing::info;
+
+use crate::core::AppState;
+use crate::services::secret_vault::VAULT_REF_PREFIX;
+use crate::utils::error::AppResult;
+
+/// Store a secret and return the `vault:<name>` reference to use in settings
+#[tauri::command]
+pub async fn set_secret(state: State<'_, AppState>, name: String, value: String) -> AppResult<String> {
+    info!("Storing secret: {}", name);
+    state.vault.with(|vault| vault.set(&name, &value))?;
+    Ok(format!("{}{}", VAULT_REF_PREFIX, name.trim()))
+}
+
+/// Read a secret
+#[tauri::command]
+pub async fn get_secret(state: State<'_, AppState>, name: String) -> AppResult<Option<String>> {
+    info!("Reading secret: {}", name);
+    state.vault.with(|vault| vault.get(&name))
+}
+
+/// Delete a secret
+#[tauri::command]
+pub async fn delete_secret(state: State<'_, AppState>, name: String) -> AppResult<bool> {
+    info!("Deleting secret: {}", name);
+    state.vault.with(|vault| vault.delete(&name))
+}
+
+/// List the names of stored secrets
+#[tauri::command]
+pub async fn list_secrets(state: State<'_, AppState>) -> AppResult<Vec<String>> {
+    state.vault.with(|vault| Ok(vault.list()))
+}
@@ -1,10 +1,12 @@
 use serde::{Deserialize, Serialize};
-use tauri::{State, AppHandle};
-use tracing::{error, info, debug};
 use tauri::async_runtime;
+use tauri::{AppHandle, State};
+use tracing::{debug, error, info};
 
+use super::event_handlers::emit_settings_updated;
 use crate::config::AppConfig;
 use crate::core::AppState;
+use crate::services::secret_vault::{VAULT_REF_PREFIX, is_vault_ref};
 
 /// Get application settings
 #[tauri::command]
@@ -160,4 +162,84 @@ pub async fn get_settings_by_category(
     }
 
     Ok(serde_json::Value::Object(settings_map))
-}
\ No newline at end of file
+}
+
+/// Environment variable as shown on the Environment settings page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentVariable {
+    pub name: String,
+    pub value: String,
+    #[serde(rename = "isSecret", default)]
+    pub is_secret: bool,
+}
+
+/// Vault entry name used for a secret environment variable
+fn env_secret_name(name: &str) -> String {
+    format!("env.{}", name)
+}
+
+/// Get the environment variables from the `.env` file
+///
+/// Secret variables are returned as their `vault:<name>` reference, never in clear text.
+#[tauri::command]
+pub async fn get_environment_variables(state: State<'_, AppState>) -> Result<Vec<EnvironmentVariable>, String> {
+    debug!("Getting environment variables");
+    let env_vars = state.config.lock().map_err(|e| e.to_string())?.env_vars.clone();
+
+    Ok(env_vars
+        .into_iter()
+        .map(|(name, value)| EnvironmentVariable {
+            is_secret: is_vault_ref(&value),
+            name,
+            value,
+        })
+        .collect())
+}
+
+/// Save the environment variables to the `.env` file
+///
+/// Secret variables with a new value are moved into the secret vault and written
+/// as `vault:<name>` references.
+#[tauri::command]
+pub async fn save_environment_variables(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    variables: Vec<EnvironmentVariable>,
+) -> Result<(), String> {
+    info!("Saving {} environment variables", variables.len());
+
+    let vault = state.vault.clone();
+    let env_vars = async_runtime::spawn_blocking(move || {
+        let mut env_vars = Vec::with_capacity(variables.len());
+        for var in variables {
+            let name = var.name.trim().to_string();
+            if name.is_empty() {
+                continue;
+            }
+            let value = if var.is_secret && !is_vault_ref(&var.value) {
+                let secret_name = env_secret_name(&name);
+                vault
+                    .with(|vault| vault.set(&secret_name, &var.value))
+                    .map_err(|e| e.to_string())?;
+                format!("{}{}", VAULT_REF_PREFIX, secret_name)
+            } else {
+                var.value
+            };
+            env_vars.push((name, value));
+        }
+        crate::config::save_env_to_file(&env_vars).map_err(|e| e.to_string())?;
+        crate::config::load_env_from_file().map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("保存环境变量任务失败: {}", e))??;
+
+    {
+        let mut state_config = state.config.lock().map_err(|e| e.to_string())?;
+        state_config.env_vars = env_vars;
+    }
+
+    if let Err(e) = emit_settings_updated(&app) {
+        error!("Failed to emit settings-updated event: {}", e);
+    }
+    Ok(())
+}
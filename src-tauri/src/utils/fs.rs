@@ -27,3 +27,27 @@ pub fn init_dir(dir: &str) -> Result<(), std::io::Error> {
 pub fn normalize_path(path: &str) -> String {
     path.replace(std::path::MAIN_SEPARATOR, "/")
 }
+
+/// 写入临时文件并同步到磁盘后再重命名，崩溃时不会留下写了一半的文件
+pub fn write_atomic(path: &std::path::Path, contents: &[u8]) -> Result<(), std::io::Error> {
+    use std::io::Write;
+
+    let file_name = path.file_name().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Not a file path: {}", path.display()),
+        )
+    })?;
+    let tmp = path.with_file_name(format!(".{}.{}.tmp", file_name.to_string_lossy(), uuid::Uuid::new_v4()));
+
+    let result = std::fs::File::create(&tmp)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|_| std::fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
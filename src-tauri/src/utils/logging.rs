@@ -3,22 +3,28 @@
 //! This module handles application logging configuration.
 
 use tauri::{App, Manager};
-use time::{format_description::{BorrowedFormatItem, parse}, macros::format_description, UtcOffset};
+use time::{
+    UtcOffset,
+    format_description::{BorrowedFormatItem, parse},
+    macros::format_description,
+};
 use tracing_subscriber::fmt::time::UtcTime;
 
+use crate::utils::redact::RedactingMakeWriter;
 use anyhow::{Context, Result};
-use tracing::Level;
 use std::{fs, path::Path};
+use tracing::Level;
+use tracing_appender::{
+    non_blocking,
+    rolling::{RollingFileAppender, Rotation},
+};
 use tracing_subscriber::{
-    fmt,
+    EnvFilter, fmt,
     layer::{Layer, SubscriberExt},
     prelude::*,
     registry::Registry,
     util::SubscriberInitExt,
-    EnvFilter
 };
-use tracing_appender::{non_blocking, rolling::{RollingFileAppender, Rotation}};
-
 
 fn build_timer() -> UtcTime<&'static [BorrowedFormatItem<'static>]> {
     // 等价：2025-12-18 12:34:56.123
@@ -51,7 +57,7 @@ pub fn init_tracing(app: &mut App) -> Result<()> {
 
     let file_layer = fmt::layer()
         .with_timer(timer.clone())
-        .with_writer(file_writer)
+        .with_writer(RedactingMakeWriter::new(file_writer))
         .with_level(true)
         .with_target(true)
         .with_file(true)
@@ -61,7 +67,7 @@ pub fn init_tracing(app: &mut App) -> Result<()> {
     // === 控制台输出 ===
     let stdout_layer = fmt::layer()
         .with_timer(timer)
-        .with_writer(std::io::stdout)
+        .with_writer(RedactingMakeWriter::new(std::io::stdout))
         .with_level(true)
         .with_target(true)
         .with_file(true)
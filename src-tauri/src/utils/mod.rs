@@ -1,6 +1,7 @@
 //! Utility modules
 
 pub mod error;
-pub mod logging;
 pub mod fs;
-
+pub mod logging;
+pub mod process;
+pub mod redact;
@@ -0,0 +1,60 @@
+//! Child process helpers
+
+use tokio::process::Child;
+use tracing::{debug, warn};
+
+/// Terminate a child and every process it spawned.
+///
+/// The child must have been spawned with `process_group(0)` on Unix: SIGTERM goes to
+/// its process group, and after a short grace period SIGKILL to whatever is left of it.
+/// Windows: `taskkill /T` walks the process tree.
+pub async fn kill_process_tree(child: &mut Child) {
+    let Some(pid) = child.id() else {
+        return;
+    };
+
+    #[cfg(unix)]
+    {
+        let pgid = pid as libc::pid_t;
+        // SAFETY: killpg only sends a signal; the group was created by `process_group(0)`.
+        unsafe {
+            libc::killpg(pgid, libc::SIGTERM);
+        }
+        let exited = tokio::time::timeout(std::time::Duration::from_secs(3), child.wait())
+            .await
+            .is_ok();
+        // An unreaped leader keeps its pid, and a group id is not reused while any
+        // member is alive; once both are gone the id may belong to someone else.
+        let leader_alive = matches!(child.try_wait(), Ok(None));
+        // SAFETY: signal 0 only checks whether the group still exists.
+        let group_alive = unsafe { libc::killpg(pgid, 0) } == 0;
+        if leader_alive || group_alive {
+            if exited {
+                debug!(pid, "Descendants outlived the process, sending SIGKILL");
+            } else {
+                warn!(pid, "Process did not exit after SIGTERM, sending SIGKILL");
+            }
+            // SAFETY: the group still exists, so the id was not reused.
+            unsafe {
+                libc::killpg(pgid, libc::SIGKILL);
+            }
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let result = tokio::process::Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .await;
+        if let Err(e) = result {
+            warn!(pid, error = %e, "taskkill failed");
+        }
+    }
+
+    if let Err(e) = child.start_kill() {
+        debug!(pid, error = %e, "Process already exited");
+    }
+}
@@ -0,0 +1,96 @@
+//! Secret redaction module
+//!
+//! Secret values (vault entries, API keys) are registered here once they are
+//! loaded, and every log writer built with `RedactingMakeWriter` masks them.
+
+use std::io::{self, Write};
+use std::sync::{OnceLock, RwLock};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Replacement text written instead of a secret
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Secrets shorter than this are not redacted (too likely to match ordinary text)
+const MIN_SECRET_LEN: usize = 6;
+
+fn secrets() -> &'static RwLock<Vec<String>> {
+    static SECRETS: OnceLock<RwLock<Vec<String>>> = OnceLock::new();
+    SECRETS.get_or_init(|| RwLock::new(Vec::new()))
+}
+
+/// Register a secret value so that it is masked in all log output
+pub fn register_secret(value: &str) {
+    let value = value.trim();
+    if value.len() < MIN_SECRET_LEN {
+        return;
+    }
+    let mut secrets = secrets().write().unwrap();
+    if !secrets.iter().any(|s| s == value) {
+        secrets.push(value.to_string());
+        // Longest first so a secret containing another one is masked as a whole
+        secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+    }
+}
+
+/// Forget a secret value (e.g. after it was deleted from the vault)
+pub fn unregister_secret(value: &str) {
+    secrets().write().unwrap().retain(|s| s != value.trim());
+}
+
+/// Replace every registered secret in `text` with `[REDACTED]`
+pub fn redact(text: &str) -> String {
+    let secrets = secrets().read().unwrap();
+    let mut out = text.to_string();
+    for secret in secrets.iter() {
+        if out.contains(secret.as_str()) {
+            out = out.replace(secret.as_str(), REDACTED);
+        }
+    }
+    out
+}
+
+/// `MakeWriter` wrapper that redacts registered secrets before writing
+#[derive(Debug, Clone)]
+pub struct RedactingMakeWriter<M> {
+    inner: M,
+}
+
+impl<M> RedactingMakeWriter<M> {
+    pub fn new(inner: M) -> Self {
+        Self { inner }
+    }
+}
+
+impl<'a, M> MakeWriter<'a> for RedactingMakeWriter<M>
+where
+    M: MakeWriter<'a>,
+{
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter {
+            inner: self.inner.make_writer(),
+        }
+    }
+}
+
+/// Writer produced by `RedactingMakeWriter`
+///
+/// The fmt layer writes each formatted event in a single call, so redacting
+/// per write call covers whole log lines.
+#[derive(Debug)]
+pub struct RedactingWriter<W> {
+    inner: W,
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        self.inner.write_all(redact(&text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}